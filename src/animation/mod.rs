@@ -0,0 +1,419 @@
+mod player;
+mod skeleton;
+
+use std::ops::{Add, Mul, Sub};
+
+use crate::types::matrix::Quaternion;
+use crate::{vec2, vec3, Vec2, Vec3};
+
+pub use player::*;
+pub use skeleton::*;
+
+
+
+/// how a track gets from one keyframe to the next
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interpolation {
+    // holds each value until the next keyframe
+    Step,
+    Linear,
+    // hermite spline through the keyframes, with the tangents stored per keyframe
+    Cubic,
+}
+
+
+
+impl Interpolation {
+    pub fn parse(name: &str) -> Option<Interpolation> {
+        match name {
+            "step" => return Some(Interpolation::Step),
+            "linear" => return Some(Interpolation::Linear),
+            "cubic" => return Some(Interpolation::Cubic),
+            _ => return None,
+        }
+    }
+
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Interpolation::Step => return "step",
+            Interpolation::Linear => return "linear",
+            Interpolation::Cubic => return "cubic",
+        }
+    }
+}
+
+
+
+/// what happens when playback runs past the end of a clip
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WrapMode {
+    // stops on the last frame
+    Once,
+    Loop,
+    // forwards, then backwards, then forwards again
+    PingPong,
+}
+
+
+
+impl WrapMode {
+    pub fn parse(name: &str) -> Option<WrapMode> {
+        match name {
+            "once" => return Some(WrapMode::Once),
+            "loop" => return Some(WrapMode::Loop),
+            "ping_pong" | "ping-pong" => return Some(WrapMode::PingPong),
+            _ => return None,
+        }
+    }
+
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WrapMode::Once => return "once",
+            WrapMode::Loop => return "loop",
+            WrapMode::PingPong => return "ping_pong",
+        }
+    }
+
+
+    /// playback time to a time within `0..=duration`
+    pub fn apply(self, time: f32, duration: f32) -> f32 {
+        if duration <= 0.0 {
+            return 0.0;
+        }
+        match self {
+            WrapMode::Once => return time.clamp(0.0, duration),
+            WrapMode::Loop => return time.rem_euclid(duration),
+            WrapMode::PingPong => {
+                let t = time.rem_euclid(2.0 * duration);
+                return if t > duration { 2.0 * duration - t } else { t };
+            }
+        }
+    }
+}
+
+
+
+/// something in the scene a track can drive. models and lights are indices into the scene's lists, joints
+/// into the model's skeleton and morph targets into its `morph_targets`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Property {
+    ModelPosition(usize),
+    // (pitch, yaw) in degrees
+    ModelRotation(usize),
+    ModelScale(usize),
+    CameraPosition,
+    CameraRotation,
+    // degrees
+    CameraFov,
+    // only directional lights have a direction
+    LightDirection(usize),
+    // only ambient lights have an intensity
+    LightIntensity(usize),
+    // (model, joint), relative to the joint's parent
+    JointTranslation(usize, usize),
+    JointRotation(usize, usize),
+    JointScale(usize, usize),
+    // (model, morph target)
+    MorphWeight(usize, usize),
+}
+
+
+
+impl Property {
+    /// number of components its values have
+    pub fn width(self) -> usize {
+        match self {
+            Property::CameraFov | Property::LightIntensity(_) | Property::MorphWeight(_, _) => return 1,
+            Property::ModelRotation(_) | Property::CameraRotation => return 2,
+            Property::JointRotation(_, _) => return 4,
+            _ => return 3,
+        }
+    }
+
+
+    /// the same property on another model, for clips loaded from a file whose models are appended to a scene
+    pub fn offset_model(self, offset: usize) -> Property {
+        match self {
+            Property::ModelPosition(model) => return Property::ModelPosition(model + offset),
+            Property::ModelRotation(model) => return Property::ModelRotation(model + offset),
+            Property::ModelScale(model) => return Property::ModelScale(model + offset),
+            Property::JointTranslation(model, joint) => return Property::JointTranslation(model + offset, joint),
+            Property::JointRotation(model, joint) => return Property::JointRotation(model + offset, joint),
+            Property::JointScale(model, joint) => return Property::JointScale(model + offset, joint),
+            Property::MorphWeight(model, target) => return Property::MorphWeight(model + offset, target),
+            other => return other,
+        }
+    }
+}
+
+
+
+/// a keyframe value, as wide as the property it's for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnimatedValue {
+    Scalar(f32),
+    Vector2(Vec2),
+    Vector3(Vec3),
+    // interpolated and blended component-wise, normalized when applied
+    Quaternion(Quaternion),
+}
+
+
+
+impl AnimatedValue {
+    /// from 1, 2, 3 or 4 numbers, 4 being a quaternion
+    pub fn from_slice(components: &[f32]) -> Option<AnimatedValue> {
+        match *components {
+            [x] => return Some(AnimatedValue::Scalar(x)),
+            [x, y] => return Some(AnimatedValue::Vector2(vec2!(x, y))),
+            [x, y, z] => return Some(AnimatedValue::Vector3(vec3!(x, y, z))),
+            [x, y, z, w] => return Some(AnimatedValue::Quaternion([x, y, z, w])),
+            _ => return None,
+        }
+    }
+
+
+    pub fn components(self) -> Vec<f32> {
+        match self {
+            AnimatedValue::Scalar(x) => return vec![x],
+            AnimatedValue::Vector2(v) => return vec![v.x, v.y],
+            AnimatedValue::Vector3(v) => return vec![v.x, v.y, v.z],
+            AnimatedValue::Quaternion(q) => return q.to_vec(),
+        }
+    }
+
+
+    pub fn width(self) -> usize {
+        match self {
+            AnimatedValue::Scalar(_) => return 1,
+            AnimatedValue::Vector2(_) => return 2,
+            AnimatedValue::Vector3(_) => return 3,
+            AnimatedValue::Quaternion(_) => return 4,
+        }
+    }
+
+
+    /// quaternions take the shorter way round
+    pub fn lerp(self, other: AnimatedValue, t: f32) -> AnimatedValue {
+        return self + (other.aligned_with(self) - self) * t;
+    }
+
+
+    /// a quaternion negated if it's in the other hemisphere from `reference`, the same rotation but safe to sum
+    /// with it. other values are returned as they are
+    pub fn aligned_with(self, reference: AnimatedValue) -> AnimatedValue {
+        if let (AnimatedValue::Quaternion(q), AnimatedValue::Quaternion(r)) = (self, reference)
+            && q.iter().zip(&r).map(|(q, r)| q * r).sum::<f32>() < 0.0
+        {
+            return self * -1.0;
+        }
+        return self;
+    }
+
+
+    fn zip(self, other: AnimatedValue, f: impl Fn(f32, f32) -> f32) -> AnimatedValue {
+        match (self, other) {
+            (AnimatedValue::Scalar(a), AnimatedValue::Scalar(b)) => return AnimatedValue::Scalar(f(a, b)),
+            (AnimatedValue::Vector2(a), AnimatedValue::Vector2(b)) => return AnimatedValue::Vector2(vec2!(f(a.x, b.x), f(a.y, b.y))),
+            (AnimatedValue::Vector3(a), AnimatedValue::Vector3(b)) => return AnimatedValue::Vector3(vec3!(f(a.x, b.x), f(a.y, b.y), f(a.z, b.z))),
+            (AnimatedValue::Quaternion(a), AnimatedValue::Quaternion(b)) => return AnimatedValue::Quaternion([0, 1, 2, 3].map(|i| f(a[i], b[i]))),
+            // tracks are checked against their property's width, so this is a bug
+            (a, b) => panic!("mixing animated values of different widths: {:?} and {:?}", a, b),
+        }
+    }
+}
+
+
+
+impl Add for AnimatedValue {
+    type Output = AnimatedValue;
+
+    fn add(self, other: AnimatedValue) -> AnimatedValue {
+        return self.zip(other, |a, b| a + b);
+    }
+}
+
+
+
+impl Sub for AnimatedValue {
+    type Output = AnimatedValue;
+
+    fn sub(self, other: AnimatedValue) -> AnimatedValue {
+        return self.zip(other, |a, b| a - b);
+    }
+}
+
+
+
+impl Mul<f32> for AnimatedValue {
+    type Output = AnimatedValue;
+
+    fn mul(self, scale: f32) -> AnimatedValue {
+        return self.zip(self, |a, _| a * scale);
+    }
+}
+
+
+
+/// keyframes for one property. cubic tracks keep a tangent (change per second) on each side of every keyframe
+#[derive(Debug, Clone)]
+pub struct Track {
+    pub interpolation: Interpolation,
+    pub times: Vec<f32>,
+    pub values: Vec<AnimatedValue>,
+    pub in_tangents: Vec<AnimatedValue>,
+    pub out_tangents: Vec<AnimatedValue>,
+}
+
+
+
+impl Track {
+    /// checks the keyframes, cubic tracks get Catmull-Rom tangents so the curve passes smoothly through every value
+    pub fn new(interpolation: Interpolation, times: Vec<f32>, values: Vec<AnimatedValue>) -> Result<Track, String> {
+        let mut track = Track { interpolation, times, values, in_tangents: Vec::new(), out_tangents: Vec::new() };
+        track.validate()?;
+        if interpolation == Interpolation::Cubic {
+            track.out_tangents = catmull_rom_tangents(&track.times, &track.values);
+            track.in_tangents = track.out_tangents.clone();
+        }
+        return Ok(track);
+    }
+
+
+    /// a cubic track with explicit tangents, e.g. from glTF's CUBICSPLINE
+    pub fn with_tangents(times: Vec<f32>, values: Vec<AnimatedValue>, in_tangents: Vec<AnimatedValue>, out_tangents: Vec<AnimatedValue>) -> Result<Track, String> {
+        let track = Track { interpolation: Interpolation::Cubic, times, values, in_tangents, out_tangents };
+        track.validate()?;
+        if track.in_tangents.len() != track.values.len() || track.out_tangents.len() != track.values.len() {
+            return Err("expected a tangent on each side of every keyframe".to_string());
+        }
+        if track.in_tangents.iter().chain(&track.out_tangents).any(|tangent| tangent.width() != track.width()) {
+            return Err("tangents must have as many components as the values".to_string());
+        }
+        return Ok(track);
+    }
+
+
+    fn validate(&self) -> Result<(), String> {
+        if self.times.is_empty() {
+            return Err("expected at least one keyframe".to_string());
+        }
+        if self.times.len() != self.values.len() {
+            return Err(format!("{} times but {} values", self.times.len(), self.values.len()));
+        }
+        if self.times.iter().any(|time| !time.is_finite() || *time < 0.0) {
+            return Err("keyframe times must be finite and not negative".to_string());
+        }
+        if self.times.windows(2).any(|pair| pair[1] <= pair[0]) {
+            return Err("keyframe times must increase".to_string());
+        }
+        if self.values.iter().any(|value| value.width() != self.width()) {
+            return Err("every value must have the same number of components".to_string());
+        }
+        return Ok(());
+    }
+
+
+    pub fn width(&self) -> usize {
+        return self.values[0].width();
+    }
+
+
+    /// time of the last keyframe
+    pub fn duration(&self) -> f32 {
+        return *self.times.last().unwrap();
+    }
+
+
+    /// holds the first and last values outside the keyframes
+    pub fn sample(&self, time: f32) -> AnimatedValue {
+        let last = self.times.len() - 1;
+        if time <= self.times[0] {
+            return self.values[0];
+        }
+        if time >= self.times[last] {
+            return self.values[last];
+        }
+
+        let next = self.times.partition_point(|&t| t <= time);
+        let previous = next - 1;
+        let span = self.times[next] - self.times[previous];
+        let t = (time - self.times[previous]) / span;
+        let (from, to) = (self.values[previous], self.values[next]);
+
+        match self.interpolation {
+            Interpolation::Step => return from,
+            Interpolation::Linear => return from.lerp(to, t),
+            Interpolation::Cubic => {
+                let (t2, t3) = (t * t, t * t * t);
+                let out_tangent = self.out_tangents[previous] * span;
+                let in_tangent = self.in_tangents[next] * span;
+                return from * (2.0 * t3 - 3.0 * t2 + 1.0)
+                    + out_tangent * (t3 - 2.0 * t2 + t)
+                    + to * (-2.0 * t3 + 3.0 * t2)
+                    + in_tangent * (t3 - t2);
+            }
+        }
+    }
+}
+
+
+
+// central differences, one sided at the ends
+fn catmull_rom_tangents(times: &[f32], values: &[AnimatedValue]) -> Vec<AnimatedValue> {
+    let last = values.len() - 1;
+    return (0..values.len()).map(|i| {
+        if last == 0 {
+            return values[0] * 0.0;
+        }
+        let (a, b) = (i.saturating_sub(1), (i + 1).min(last));
+        return (values[b] - values[a]) * (1.0 / (times[b] - times[a]));
+    }).collect();
+}
+
+
+
+/// a track and the property it drives
+#[derive(Debug, Clone)]
+pub struct Channel {
+    pub property: Property,
+    pub track: Track,
+}
+
+
+
+/// channels that play together
+#[derive(Debug, Clone)]
+pub struct AnimationClip {
+    pub name: String,
+    pub wrap: WrapMode,
+    pub channels: Vec<Channel>,
+    // the model file it came with, None when it's declared in the scene file or built in code
+    pub asset: Option<String>,
+}
+
+
+
+impl AnimationClip {
+    pub fn new(name: &str) -> AnimationClip {
+        return AnimationClip { name: name.to_string(), wrap: WrapMode::Loop, channels: Vec::new(), asset: None };
+    }
+
+
+    /// checks the track is as wide as the property
+    pub fn add_channel(&mut self, property: Property, track: Track) -> Result<(), String> {
+        if track.width() != property.width() {
+            return Err(format!("{:?} takes {} numbers per keyframe, got {}", property, property.width(), track.width()));
+        }
+        self.channels.push(Channel { property, track });
+        return Ok(());
+    }
+
+
+    /// the end of the longest track
+    pub fn duration(&self) -> f32 {
+        return self.channels.iter().map(|channel| channel.track.duration()).fold(0.0, f32::max);
+    }
+}
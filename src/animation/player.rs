@@ -0,0 +1,303 @@
+use crate::animation::{AnimatedValue, AnimationClip, Joint, Property};
+use crate::{vec2, Camera, Light, Model, Vec2};
+
+
+
+// seconds switching clips with the next_animation action takes
+pub const CROSS_FADE_TIME: f32 = 0.3;
+
+
+
+/// the parts of a scene clips can drive, borrowed separately so the player can live in the scene too
+pub struct AnimationTargets<'a> {
+    pub models: &'a mut [Model],
+    pub camera: &'a mut Camera,
+    pub lights: &'a mut [Light],
+}
+
+
+
+impl AnimationTargets<'_> {
+    /// None when the model or light doesn't exist, or is the wrong kind of light
+    pub fn get(&self, property: Property) -> Option<AnimatedValue> {
+        match property {
+            Property::ModelPosition(model) => return self.models.get(model).map(|model| AnimatedValue::Vector3(model.transform.position)),
+            Property::ModelRotation(model) => return self.models.get(model).map(|model| AnimatedValue::Vector2(vec2!(model.transform.pitch, model.transform.yaw))),
+            Property::ModelScale(model) => return self.models.get(model).map(|model| AnimatedValue::Vector3(model.transform.scale)),
+            Property::CameraPosition => return Some(AnimatedValue::Vector3(self.camera.transform.position)),
+            Property::CameraRotation => return Some(AnimatedValue::Vector2(vec2!(self.camera.transform.pitch, self.camera.transform.yaw))),
+            Property::CameraFov => return Some(AnimatedValue::Scalar(self.camera.fov)),
+            Property::LightDirection(light) => match self.lights.get(light) {
+                Some(Light::Directional { direction }) => return Some(AnimatedValue::Vector3(*direction)),
+                _ => return None,
+            },
+            Property::LightIntensity(light) => match self.lights.get(light) {
+                Some(Light::Ambient { intensity }) => return Some(AnimatedValue::Scalar(*intensity)),
+                _ => return None,
+            },
+            Property::JointTranslation(model, joint) => return self.joint(model, joint).map(|joint| AnimatedValue::Vector3(joint.translation)),
+            Property::JointRotation(model, joint) => return self.joint(model, joint).map(|joint| AnimatedValue::Quaternion(joint.rotation)),
+            Property::JointScale(model, joint) => return self.joint(model, joint).map(|joint| AnimatedValue::Vector3(joint.scale)),
+            Property::MorphWeight(model, target) => return self.models.get(model)?.morph_weights.get(target).map(|&weight| AnimatedValue::Scalar(weight)),
+        }
+    }
+
+
+    pub fn set(&mut self, property: Property, value: AnimatedValue) {
+        match (property, value) {
+            (Property::ModelPosition(model), AnimatedValue::Vector3(position)) => if let Some(model) = self.models.get_mut(model) {
+                model.transform.position = position;
+            },
+            (Property::ModelRotation(model), AnimatedValue::Vector2(rotation)) => if let Some(model) = self.models.get_mut(model) {
+                model.transform.set_rotation(rotation.x, rotation.y);
+            },
+            (Property::ModelScale(model), AnimatedValue::Vector3(scale)) => if let Some(model) = self.models.get_mut(model) {
+                model.transform.scale = scale;
+            },
+            (Property::CameraPosition, AnimatedValue::Vector3(position)) => self.camera.transform.position = position,
+            (Property::CameraRotation, AnimatedValue::Vector2(rotation)) => self.camera.transform.set_rotation(rotation.x, rotation.y),
+            // a cubic overshoot could flip the projection
+            (Property::CameraFov, AnimatedValue::Scalar(fov)) => self.camera.fov = fov.clamp(1.0, 179.0),
+            (Property::LightDirection(light), AnimatedValue::Vector3(value)) => if let Some(Light::Directional { direction }) = self.lights.get_mut(light) {
+                *direction = value;
+            },
+            (Property::LightIntensity(light), AnimatedValue::Scalar(value)) => if let Some(Light::Ambient { intensity }) = self.lights.get_mut(light) {
+                *intensity = value.max(0.0);
+            },
+            (Property::JointTranslation(model, joint), AnimatedValue::Vector3(translation)) => if let Some(joint) = self.joint_mut(model, joint) {
+                joint.translation = translation;
+            },
+            (Property::JointRotation(model, joint), AnimatedValue::Quaternion(rotation)) => if let Some(joint) = self.joint_mut(model, joint) {
+                let length = rotation.iter().map(|c| c * c).sum::<f32>().sqrt();
+                if length > 0.0 {
+                    joint.rotation = rotation.map(|c| c / length);
+                }
+            },
+            (Property::JointScale(model, joint), AnimatedValue::Vector3(scale)) => if let Some(joint) = self.joint_mut(model, joint) {
+                joint.scale = scale;
+            },
+            (Property::MorphWeight(model, target), AnimatedValue::Scalar(value)) => if let Some(weight) = self.models.get_mut(model).and_then(|model| model.morph_weights.get_mut(target)) {
+                *weight = value;
+            },
+            _ => {}
+        }
+    }
+
+
+    fn joint(&self, model: usize, joint: usize) -> Option<&Joint> {
+        return self.models.get(model)?.skeleton.as_ref()?.joints.get(joint);
+    }
+
+
+    fn joint_mut(&mut self, model: usize, joint: usize) -> Option<&mut Joint> {
+        return self.models.get_mut(model)?.skeleton.as_mut()?.joints.get_mut(joint);
+    }
+}
+
+
+
+/// a clip playing at some weight, layers are blended together by weight
+#[derive(Debug, Clone)]
+pub struct AnimationLayer {
+    // index into `AnimationPlayer::clips`
+    pub clip: usize,
+    // seconds since the clip started, wrapped by the clip when sampling
+    pub time: f32,
+    pub speed: f32,
+    pub weight: f32,
+    fade: Option<Fade>,
+}
+
+
+
+// moves a layer's weight from `from` to `to` over `duration` seconds
+#[derive(Debug, Clone)]
+struct Fade {
+    from: f32,
+    to: f32,
+    duration: f32,
+    elapsed: f32,
+}
+
+
+
+/// plays clips on a scene. with layers weighing less than 1 in total, the rest of the weight goes to the
+/// values properties had before anything animated them
+pub struct AnimationPlayer {
+    pub clips: Vec<AnimationClip>,
+    pub layers: Vec<AnimationLayer>,
+    // multiplies every layer's speed, negative plays backwards
+    pub speed: f32,
+    pub paused: bool,
+    // values of the properties being animated from before they were, restored when nothing animates them anymore
+    rest: Vec<(Property, AnimatedValue)>,
+    // layers changed outside `update`, the scene needs posing even if no time passes
+    dirty: bool,
+}
+
+
+
+impl AnimationPlayer {
+    pub fn new() -> AnimationPlayer {
+        return AnimationPlayer { clips: Vec::new(), layers: Vec::new(), speed: 1.0, paused: false, rest: Vec::new(), dirty: false };
+    }
+
+
+    pub fn add_clip(&mut self, clip: AnimationClip) -> usize {
+        self.clips.push(clip);
+        return self.clips.len() - 1;
+    }
+
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        return self.clips.iter().position(|clip| clip.name == name);
+    }
+
+
+    /// the clip with the most weight, the one a cross fade is heading to if there is one
+    pub fn current(&self) -> Option<usize> {
+        let target_weight = |layer: &AnimationLayer| layer.fade.as_ref().map_or(layer.weight, |fade| fade.to);
+        return self.layers.iter().max_by(|a, b| target_weight(a).total_cmp(&target_weight(b))).map(|layer| layer.clip);
+    }
+
+
+    /// plays `clip` from the start on its own
+    pub fn play(&mut self, clip: usize) {
+        self.layers = vec![AnimationLayer { clip, time: 0.0, speed: 1.0, weight: 1.0, fade: None }];
+        self.dirty = true;
+    }
+
+
+    /// fades every other layer out while `clip` fades in, a clip that's already playing carries on from where it is
+    pub fn cross_fade(&mut self, clip: usize, duration: f32) {
+        if duration <= 0.0 {
+            self.play(clip);
+            return;
+        }
+        if !self.layers.iter().any(|layer| layer.clip == clip) {
+            self.layers.push(AnimationLayer { clip, time: 0.0, speed: 1.0, weight: 0.0, fade: None });
+        }
+        for layer in &mut self.layers {
+            let to = if layer.clip == clip { 1.0 } else { 0.0 };
+            layer.fade = Some(Fade { from: layer.weight, to, duration, elapsed: 0.0 });
+        }
+        self.dirty = true;
+    }
+
+
+    /// cross fades to the clip after the current one, wrapping around
+    pub fn play_next(&mut self, fade_duration: f32) {
+        if self.clips.is_empty() {
+            return;
+        }
+        let next = self.current().map_or(0, |clip| (clip + 1) % self.clips.len());
+        self.cross_fade(next, fade_duration);
+    }
+
+
+    /// sets the weight `clip` is blended with, starting it if it isn't playing. 0 stops it
+    pub fn blend(&mut self, clip: usize, weight: f32) {
+        match self.layers.iter_mut().find(|layer| layer.clip == clip) {
+            Some(layer) => {
+                layer.weight = weight.max(0.0);
+                layer.fade = None;
+            }
+            None => self.layers.push(AnimationLayer { clip, time: 0.0, speed: 1.0, weight: weight.max(0.0), fade: None }),
+        }
+        self.dirty = true;
+    }
+
+
+    /// stops every clip, the animated properties go back to how they were
+    pub fn stop(&mut self) {
+        self.layers.clear();
+        self.dirty = true;
+    }
+
+
+    /// whether the playing clips drive any light, shaders then need relighting after `update`
+    pub fn animates_lights(&self) -> bool {
+        return self.rest.iter().any(|(property, _)| matches!(property, Property::LightDirection(_) | Property::LightIntensity(_)));
+    }
+
+
+    /// advances the clips by `delta_time` and poses `targets`, returns whether anything changed
+    pub fn update(&mut self, delta_time: f32, targets: &mut AnimationTargets) -> bool {
+        let delta_time = if self.paused { 0.0 } else { delta_time };
+        let mut changed = std::mem::take(&mut self.dirty);
+
+        for layer in &mut self.layers {
+            let clip = &self.clips[layer.clip];
+            let duration = clip.duration();
+            let before = clip.wrap.apply(layer.time, duration);
+            layer.time += delta_time * self.speed * layer.speed;
+            changed |= clip.wrap.apply(layer.time, duration) != before;
+
+            if let Some(fade) = &mut layer.fade && delta_time > 0.0 {
+                fade.elapsed += delta_time;
+                let t = (fade.elapsed / fade.duration).min(1.0);
+                layer.weight = fade.from + (fade.to - fade.from) * t;
+                if t >= 1.0 {
+                    layer.fade = None;
+                }
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.pose(targets);
+        }
+        // after posing, so a layer that faded out leaves its properties at rest
+        self.layers.retain(|layer| layer.weight > 0.0 || layer.fade.is_some());
+        return changed;
+    }
+
+
+    fn pose(&mut self, targets: &mut AnimationTargets) {
+        // property, weighted sum of the layers' values, total weight
+        let mut blended: Vec<(Property, AnimatedValue, f32)> = Vec::new();
+        for layer in self.layers.iter().filter(|layer| layer.weight > 0.0) {
+            let clip = &self.clips[layer.clip];
+            let time = clip.wrap.apply(layer.time, clip.duration());
+            for channel in &clip.channels {
+                let value = channel.track.sample(time) * layer.weight;
+                match blended.iter_mut().find(|(property, _, _)| *property == channel.property) {
+                    Some((_, sum, total)) => {
+                        // q and -q are the same rotation but cancel out when summed
+                        *sum = *sum + value.aligned_with(*sum);
+                        *total += layer.weight;
+                    }
+                    None => blended.push((channel.property, value, layer.weight)),
+                }
+            }
+        }
+
+        // properties nothing animates anymore go back to rest, once, so they're free to change otherwise
+        let mut index = 0;
+        while index < self.rest.len() {
+            let (property, value) = self.rest[index];
+            if blended.iter().any(|(animated, _, _)| *animated == property) {
+                index += 1;
+            } else {
+                targets.set(property, value);
+                self.rest.swap_remove(index);
+            }
+        }
+
+        for (property, sum, total) in blended {
+            let rest = match self.rest.iter().find(|(rested, _)| *rested == property) {
+                Some((_, value)) => *value,
+                None => match targets.get(property) {
+                    Some(value) => {
+                        self.rest.push((property, value));
+                        value
+                    }
+                    // the model or light it's for doesn't exist
+                    None => continue,
+                },
+            };
+            let value = if total >= 1.0 { sum * (1.0 / total) } else { sum + rest.aligned_with(sum) * (1.0 - total) };
+            targets.set(property, value);
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use crate::types::matrix::{mat_mul, transform_normal, transform_point, transform_vector, trs_matrix, Mat4, Quaternion, IDENTITY};
+use crate::{Vec3, Vertex};
+
+
+
+/// a bone, posed by its translation, rotation and scale relative to its parent
+#[derive(Debug, Clone)]
+pub struct Joint {
+    pub name: String,
+    // index into `Skeleton::joints`, None for roots
+    pub parent: Option<usize>,
+    // what a root joint hangs from in model space, e.g. glTF nodes above the skeleton. ignored with a parent
+    pub base: Mat4,
+    // model space to the joint's space in the bind pose
+    pub inverse_bind: Mat4,
+    pub translation: Vec3,
+    pub rotation: Quaternion,
+    pub scale: Vec3,
+}
+
+
+
+impl Joint {
+    pub fn local_matrix(&self) -> Mat4 {
+        return trs_matrix(self.translation, self.rotation, self.scale);
+    }
+}
+
+
+
+/// joints deforming a model's vertices, linear blend skinning with up to four joints per vertex
+#[derive(Debug, Clone)]
+pub struct Skeleton {
+    pub joints: Vec<Joint>,
+    // joint indices, parents before their children
+    order: Vec<usize>,
+}
+
+
+
+impl Skeleton {
+    /// fails on parents out of range and on cycles
+    pub fn new(joints: Vec<Joint>) -> Result<Skeleton, String> {
+        let mut order: Vec<usize> = Vec::with_capacity(joints.len());
+        let mut placed = vec![false; joints.len()];
+        for (i, joint) in joints.iter().enumerate() {
+            if let Some(parent) = joint.parent && parent >= joints.len() {
+                return Err(format!("joint {} has parent {} of {} joints", i, parent, joints.len()));
+            }
+        }
+        // each round places the joints whose parent is placed, a round placing nothing means a cycle
+        while order.len() < joints.len() {
+            let before = order.len();
+            for (i, joint) in joints.iter().enumerate() {
+                if !placed[i] && joint.parent.is_none_or(|parent| placed[parent]) {
+                    placed[i] = true;
+                    order.push(i);
+                }
+            }
+            if order.len() == before {
+                return Err("joint hierarchy has a cycle".to_string());
+            }
+        }
+        return Ok(Skeleton { joints, order });
+    }
+
+
+    /// per joint, from bind pose model space to the current pose, what `skin_vertex` blends
+    pub fn joint_matrices(&self) -> Vec<Mat4> {
+        let mut world = vec![IDENTITY; self.joints.len()];
+        for &i in &self.order {
+            let joint = &self.joints[i];
+            let parent = match joint.parent {
+                Some(parent) => &world[parent],
+                None => &joint.base,
+            };
+            world[i] = mat_mul(parent, &joint.local_matrix());
+        }
+        return world.iter().zip(&self.joints).map(|(world, joint)| mat_mul(world, &joint.inverse_bind)).collect();
+    }
+}
+
+
+
+/// the vertex moved by its joints' matrices blended by weight, unweighted vertices are left as they are
+pub fn skin_vertex(vertex: &Vertex, joint_matrices: &[Mat4]) -> Vertex {
+    let mut matrix = [0.0; 16];
+    let mut total = 0.0;
+    for (&joint, &weight) in vertex.joints.iter().zip(&vertex.weights) {
+        if weight == 0.0 {
+            continue;
+        }
+        let Some(joint_matrix) = joint_matrices.get(joint as usize) else { continue };
+        for (blended, value) in matrix.iter_mut().zip(joint_matrix) {
+            *blended += value * weight;
+        }
+        total += weight;
+    }
+    if total == 0.0 {
+        return *vertex;
+    }
+    // weights that don't sum to one would scale the vertex towards the origin
+    matrix.iter_mut().for_each(|value| *value /= total);
+
+    return Vertex {
+        position: transform_point(&matrix, vertex.position),
+        normal: transform_normal(&matrix, vertex.normal),
+        tangent: transform_vector(&matrix, vertex.tangent).normalize(),
+        ..*vertex
+    };
+}
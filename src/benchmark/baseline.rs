@@ -0,0 +1,75 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use crate::benchmark::BenchmarkResult;
+use crate::utils::json::Json;
+
+
+
+/// earlier results to compare against, saved as json next to the project
+pub struct Baseline {
+    // "release" or "debug", numbers from different builds aren't comparable
+    pub profile: String,
+    pub entries: Vec<(String, f64)>,
+}
+
+
+
+impl Baseline {
+    /// None when there is no baseline yet
+    pub fn load(path: &str) -> Result<Option<Baseline>> {
+        if !Path::new(path).exists() {
+            return Ok(None);
+        }
+        let invalid = |message: String| Error::new(ErrorKind::InvalidData, format!("{}: {}", path, message));
+
+        let json = Json::parse(&std::fs::read_to_string(path)?).map_err(|err| invalid(err.to_string()))?;
+        let profile = json.get("profile").and_then(Json::as_str).unwrap_or("release").to_string();
+        let Some(benchmarks) = json.get("benchmarks").and_then(Json::as_array) else {
+            return Err(invalid("expected a \"benchmarks\" array".to_string()));
+        };
+
+        let mut entries: Vec<(String, f64)> = Vec::with_capacity(benchmarks.len());
+        for (i, benchmark) in benchmarks.iter().enumerate() {
+            let name = benchmark.get("name").and_then(Json::as_str);
+            let seconds = benchmark.get("seconds").and_then(Json::as_f64).filter(|seconds| *seconds > 0.0);
+            let (Some(name), Some(seconds)) = (name, seconds) else {
+                return Err(invalid(format!("benchmarks[{}] needs a name and a positive number of seconds", i)));
+            };
+            entries.push((name.to_string(), seconds));
+        }
+        return Ok(Some(Baseline { profile, entries }));
+    }
+
+
+    /// replaces the file with `results`, throughput is saved alongside for reading but only the time is compared
+    pub fn save(path: &str, results: &[BenchmarkResult]) -> Result<()> {
+        if let Some(directory) = Path::new(path).parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+
+        let benchmarks = results.iter().map(|result| Json::Object(vec![
+            ("name".to_string(), Json::String(result.name.clone())),
+            ("seconds".to_string(), Json::Number(result.seconds)),
+            ("triangles_per_second".to_string(), Json::Number(result.triangles_per_second())),
+            ("pixels_per_second".to_string(), Json::Number(result.pixels_per_second())),
+        ]));
+        let json = Json::Object(vec![
+            ("profile".to_string(), Json::String(build_profile().to_string())),
+            ("benchmarks".to_string(), Json::Array(benchmarks.collect())),
+        ]);
+        std::fs::write(path, json.to_string_pretty() + "\n")?;
+        return Ok(());
+    }
+
+
+    pub fn seconds(&self, name: &str) -> Option<f64> {
+        return self.entries.iter().find(|(entry, _)| entry == name).map(|(_, seconds)| *seconds);
+    }
+}
+
+
+
+pub fn build_profile() -> &'static str {
+    return if cfg!(debug_assertions) { "debug" } else { "release" };
+}
@@ -0,0 +1,283 @@
+mod baseline;
+mod scenes;
+
+use std::hint::black_box;
+use std::io::{Error, Result};
+use std::time::{Duration, Instant};
+
+use crate::benchmark::baseline::{build_profile, Baseline};
+use crate::benchmark::scenes::*;
+use crate::utils::file_parser::{parse_obj, read_bitmap};
+use crate::{Rasterizer, RenderTarget, Scene};
+
+
+
+pub const DEFAULT_BASELINE_PATH: &str = "./benchmarks/baseline.json";
+
+// slower than the baseline by more than this fraction counts as a regression
+pub const REGRESSION_THRESHOLD: f64 = 0.10;
+
+// time spent running a benchmark before measuring, also used to pick the iterations per sample
+const WARMUP_TIME: Duration = Duration::from_millis(50);
+const SAMPLE_TIME: Duration = Duration::from_millis(20);
+// the median sample is reported, so a few disturbed ones don't move it
+const SAMPLES: usize = 15;
+
+const OBJ_FIXTURE_SIZE: (u32, u32) = (200, 200);
+const BITMAP_FIXTURE_SIZE: (u32, u32) = (2048, 2048);
+
+
+
+#[derive(Debug, Clone)]
+pub struct BenchmarkOptions {
+    pub baseline_path: String,
+    // overwrite the baseline with this run instead of comparing against it
+    pub save_baseline: bool,
+    // only benchmarks whose name contains this run, empty runs all
+    pub filter: String,
+}
+
+
+
+/// one measured benchmark, work counts are per iteration
+#[derive(Debug, Clone)]
+pub struct BenchmarkResult {
+    // "<function>/<scene>/<width>x<height>" or "<function>/<fixture>"
+    pub name: String,
+    // median time per iteration
+    pub seconds: f64,
+    pub triangles: u64,
+    pub pixels: u64,
+}
+
+
+
+impl BenchmarkResult {
+    pub fn triangles_per_second(&self) -> f64 {
+        return self.triangles as f64 / self.seconds;
+    }
+
+
+    pub fn pixels_per_second(&self) -> f64 {
+        return self.pixels as f64 / self.seconds;
+    }
+}
+
+
+
+/// runs the rasterizer passes over every scene and resolution, then the file parsers, and compares against
+/// the saved baseline. the first run, or one with `save_baseline`, writes the baseline instead.
+/// returns an error when anything regressed past `REGRESSION_THRESHOLD`
+pub fn run_benchmarks(options: &BenchmarkOptions) -> Result<()> {
+    if cfg!(debug_assertions) {
+        eprintln!("Warning: this is a debug build, use `cargo run --release -- --benchmark` for meaningful numbers");
+    }
+    // a filtered run would drop everything it skipped from the baseline
+    if options.save_baseline && !options.filter.is_empty() {
+        return Err(Error::other("the baseline can only be saved from a run without a filter"));
+    }
+    let baseline = if options.save_baseline { None } else { Baseline::load(&options.baseline_path)? };
+    if let Some(baseline) = &baseline && baseline.profile != build_profile() {
+        eprintln!("Warning: the baseline was recorded with a {} build, this is a {} build", baseline.profile, build_profile());
+    }
+
+    let mut report = Report { baseline, results: Vec::new(), regressions: Vec::new() };
+    println!("{:<44} {:>11} {:>13} {:>13} {:>12}", "benchmark", "time", "triangles/s", "pixels/s", "vs baseline");
+
+    for scene_kind in SCENES {
+        for (width, height) in RESOLUTIONS {
+            let suffix = format!("{}/{}x{}", scene_kind.name(), width, height);
+            let names = ["geometry_setup_pass", "depth_pass", "color_pass"].map(|pass| format!("{}/{}", pass, suffix));
+            if names.iter().any(|name| name.contains(&options.filter)) {
+                let scene = scene_kind.build(width, height);
+                for result in benchmark_passes(&scene, &names) {
+                    if result.name.contains(&options.filter) {
+                        report.add(result);
+                    }
+                }
+            }
+        }
+    }
+
+    let fixture_directory = std::env::temp_dir().join(format!("rasterizer-benchmark-{}", std::process::id()));
+    std::fs::create_dir_all(&fixture_directory)?;
+    let parsers = benchmark_parsers(&fixture_directory, &options.filter, &mut report);
+    std::fs::remove_dir_all(&fixture_directory).ok();
+    parsers?;
+
+    return report.finish(options);
+}
+
+
+
+// the three passes of `Rasterizer::render`, on the scene as it is
+fn benchmark_passes(scene: &Scene, names: &[String; 3]) -> Vec<BenchmarkResult> {
+    let mut rasterizer = Rasterizer::new();
+    let mut target = RenderTarget::new(scene.settings.width, scene.settings.height);
+    let pixels = target.size as u64;
+    let input_triangles = scene.models.iter().map(|model| model.vertices.len() as u64 / 3).sum();
+
+    let geometry = measure(|| {
+        let start = Instant::now();
+        black_box(rasterizer.geometry_setup_pass(scene, &mut target));
+        return start.elapsed();
+    });
+    let triangles = rasterizer.geometry_setup_pass(scene, &mut target);
+    let raster_triangles = triangles.len() as u64;
+
+    let depth = measure(|| {
+        target.depth_buffer.fill(f32::NEG_INFINITY);
+        let start = Instant::now();
+        rasterizer.depth_pass(scene, &mut target, &triangles);
+        return start.elapsed();
+    });
+
+    // the depth buffer is left complete from the last depth pass, as color_pass expects
+    let color = measure(|| {
+        let start = Instant::now();
+        black_box(rasterizer.color_pass(scene, &mut target, &triangles));
+        return start.elapsed();
+    });
+
+    return vec![
+        BenchmarkResult { name: names[0].clone(), seconds: geometry, triangles: input_triangles, pixels: 0 },
+        BenchmarkResult { name: names[1].clone(), seconds: depth, triangles: raster_triangles, pixels },
+        BenchmarkResult { name: names[2].clone(), seconds: color, triangles: raster_triangles, pixels },
+    ];
+}
+
+
+
+// parsing from files written to a scratch directory, small enough to stay in the os file cache
+fn benchmark_parsers(directory: &std::path::Path, filter: &str, report: &mut Report) -> Result<()> {
+    let (columns, rows) = OBJ_FIXTURE_SIZE;
+    let name = format!("parse_obj/grid_{}x{}", columns, rows);
+    if name.contains(filter) {
+        let path = directory.join("grid.obj");
+        let triangles = write_obj_fixture(&path, columns, rows)?;
+        let path = path.to_string_lossy().to_string();
+        parse_obj(&path).map_err(Error::other)?;
+
+        let seconds = measure(|| {
+            let start = Instant::now();
+            black_box(parse_obj(&path).ok());
+            return start.elapsed();
+        });
+        report.add(BenchmarkResult { name, seconds, triangles, pixels: 0 });
+    }
+
+    let (width, height) = BITMAP_FIXTURE_SIZE;
+    let name = format!("read_bitmap/rgb24_{}x{}", width, height);
+    if name.contains(filter) {
+        let path = directory.join("gradient.bmp");
+        let pixels = write_bitmap_fixture(&path, width, height)?;
+        let path = path.to_string_lossy().to_string();
+        read_bitmap(&path)?;
+
+        let seconds = measure(|| {
+            let start = Instant::now();
+            black_box(read_bitmap(&path).ok());
+            return start.elapsed();
+        });
+        report.add(BenchmarkResult { name, seconds, triangles: 0, pixels });
+    }
+    return Ok(());
+}
+
+
+
+// median seconds per iteration, `iteration` runs once and returns how long the measured part took
+fn measure(mut iteration: impl FnMut() -> Duration) -> f64 {
+    let warmup_start = Instant::now();
+    let (mut warmup_time, mut warmup_iterations) = (Duration::ZERO, 0u32);
+    while warmup_start.elapsed() < WARMUP_TIME || warmup_iterations == 0 {
+        warmup_time += iteration();
+        warmup_iterations += 1;
+    }
+    let estimate = (warmup_time.as_secs_f64() / warmup_iterations as f64).max(1e-9);
+    let iterations = (SAMPLE_TIME.as_secs_f64() / estimate).ceil().max(1.0) as u32;
+
+    let mut samples: Vec<f64> = (0..SAMPLES).map(|_| {
+        let total: Duration = (0..iterations).map(|_| iteration()).sum();
+        return total.as_secs_f64() / iterations as f64;
+    }).collect();
+    samples.sort_by(f64::total_cmp);
+    return samples[SAMPLES / 2];
+}
+
+
+
+struct Report {
+    baseline: Option<Baseline>,
+    results: Vec<BenchmarkResult>,
+    // names with how much slower they got
+    regressions: Vec<(String, f64)>,
+}
+
+
+
+impl Report {
+    // prints the result as it comes in, long runs show progress
+    fn add(&mut self, result: BenchmarkResult) {
+        let change = self.baseline.as_ref().and_then(|baseline| baseline.seconds(&result.name)).map(|seconds| result.seconds / seconds - 1.0);
+        let comparison = match change {
+            Some(change) if change > REGRESSION_THRESHOLD => {
+                self.regressions.push((result.name.clone(), change));
+                format!("{:+.1}% slower", change * 100.0)
+            }
+            Some(change) => format!("{:+.1}%", change * 100.0),
+            None => "-".to_string(),
+        };
+
+        println!(
+            "{:<44} {:>11} {:>13} {:>13} {:>12}",
+            result.name,
+            format_time(result.seconds),
+            format_rate(result.triangles_per_second()),
+            format_rate(result.pixels_per_second()),
+            comparison,
+        );
+        self.results.push(result);
+    }
+
+
+    fn finish(self, options: &BenchmarkOptions) -> Result<()> {
+        if self.results.is_empty() {
+            return Err(Error::other(format!("no benchmark matches '{}'", options.filter)));
+        }
+        if self.baseline.is_none() && options.filter.is_empty() {
+            Baseline::save(&options.baseline_path, &self.results)?;
+            println!("saved the baseline to {}", options.baseline_path);
+            return Ok(());
+        }
+        if self.regressions.is_empty() {
+            return Ok(());
+        }
+
+        for (name, change) in &self.regressions {
+            eprintln!("regressed: {} is {:.1}% slower than the baseline", name, change * 100.0);
+        }
+        return Err(Error::other(format!("{} benchmarks regressed by more than {}%", self.regressions.len(), REGRESSION_THRESHOLD * 100.0)));
+    }
+}
+
+
+
+fn format_time(seconds: f64) -> String {
+    match seconds {
+        s if s >= 1.0 => return format!("{:.3} s", s),
+        s if s >= 1e-3 => return format!("{:.3} ms", s * 1e3),
+        s => return format!("{:.3} us", s * 1e6),
+    }
+}
+
+
+fn format_rate(per_second: f64) -> String {
+    match per_second {
+        r if r <= 0.0 || !r.is_finite() => return "-".to_string(),
+        r if r >= 1e9 => return format!("{:.2} G/s", r / 1e9),
+        r if r >= 1e6 => return format!("{:.2} M/s", r / 1e6),
+        r if r >= 1e3 => return format!("{:.2} K/s", r / 1e3),
+        r => return format!("{:.2} /s", r),
+    }
+}
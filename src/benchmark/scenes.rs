@@ -0,0 +1,190 @@
+use std::io::{Result, Write};
+use std::path::Path;
+
+use crate::*;
+use crate::utils::fixtures::{checker_texture, sphere_vertices, vertex};
+
+
+
+// render resolutions every scene is measured at
+pub const RESOLUTIONS: [(u32, u32); 3] = [(320, 180), (1280, 720), (1920, 1080)];
+
+pub const SCENES: [BenchmarkScene; 4] = [
+    BenchmarkScene::SmallTriangles,
+    BenchmarkScene::HugeTriangles,
+    BenchmarkScene::HeavyClipping,
+    BenchmarkScene::Overdraw,
+];
+
+const FIELD_OF_VIEW: f32 = 70.0;
+
+
+
+/// fixed workloads, each one stresses a different part of the pipeline
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BenchmarkScene {
+    // a dense wavy grid filling the screen, per-triangle setup dominates
+    SmallTriangles,
+    // a handful of textured triangles larger than the screen, per-pixel work dominates
+    HugeTriangles,
+    // the camera inside a sphere and above a large floor, nearly every triangle gets clipped
+    HeavyClipping,
+    // full screen layers drawn back to front, every pixel is written once per layer
+    Overdraw,
+}
+
+
+
+impl BenchmarkScene {
+    pub fn name(self) -> &'static str {
+        match self {
+            BenchmarkScene::SmallTriangles => return "small_triangles",
+            BenchmarkScene::HugeTriangles => return "huge_triangles",
+            BenchmarkScene::HeavyClipping => return "heavy_clipping",
+            BenchmarkScene::Overdraw => return "overdraw",
+        }
+    }
+
+
+    /// the same geometry at any resolution, only the target size changes
+    pub fn build(self, width: u32, height: u32) -> Scene {
+        let mut scene = Scene::new();
+        scene.settings.width = width;
+        scene.settings.height = height;
+        scene.camera = Camera::new(FIELD_OF_VIEW);
+
+        match self {
+            BenchmarkScene::SmallTriangles => {
+                // 256x144 quads, a bit wider than the view at z = -2
+                let mut vertices = grid_vertices(256, 144, vec3!(-2.6, -1.5, -2.0), vec3!(5.2, 0, 0), vec3!(0, 3.0, 0));
+                for vertex in vertices.iter_mut() {
+                    vertex.position.z += 0.1 * (vertex.position.x * 7.0).sin() * (vertex.position.y * 5.0).cos();
+                    vertex.color = vec3!(0.5 + 0.5 * vertex.texcoord.x, 0.5, 0.5 + 0.5 * vertex.texcoord.y);
+                }
+                scene.load_model(Model::new(vertices, Box::new(VertexColorShader::new())));
+            }
+            BenchmarkScene::HugeTriangles => {
+                for i in 0..4 {
+                    let z = -2.0 - i as f32 * 0.5;
+                    let offset = i as f32 * 0.3;
+                    let corners = [vec3!(-9.0 + offset, -6, z), vec3!(9, -6.0 + offset, z), vec3!(0, 8, z)];
+                    let texcoords = [vec2!(0, 1), vec2!(1, 1), vec2!(0.5, 0)];
+                    let vertices = (0..3).map(|j| vertex(corners[j], texcoords[j], vec3!(1, 1, 1))).collect();
+                    scene.load_model(Model::new(vertices, Box::new(TextureShader::new(checker_texture(512, 16)))));
+                }
+            }
+            BenchmarkScene::HeavyClipping => {
+                scene.settings.near_clip = 0.1;
+                scene.camera.transform.position = vec3!(0, 0.3, 0);
+                scene.camera.transform.set_rotation(10.0, 30.0);
+
+                let floor = grid_vertices(64, 64, vec3!(-20, 0, 20), vec3!(40, 0, 0), vec3!(0, 0, -40));
+                scene.load_model(Model::new(floor, Box::new(TextureShader::new(checker_texture(256, 32)))));
+                // wound to face inwards, for a camera inside it
+                let mut sphere = sphere_vertices(vec3!(0, 0.3, 0), 3.0, 24, 48);
+                for triangle in sphere.chunks_mut(3) {
+                    triangle.swap(1, 2);
+                }
+                for vertex in sphere.iter_mut() {
+                    vertex.color = vertex.normal * 0.5 + vec3!(0.5, 0.5, 0.5);
+                }
+                scene.load_model(Model::new(sphere, Box::new(VertexColorShader::new())));
+            }
+            BenchmarkScene::Overdraw => {
+                // back to front so every layer passes the depth test
+                for i in 0..16 {
+                    let z = -10.0 + i as f32 * 0.5;
+                    let extent = -z * 2.0;
+                    let shade = 0.3 + 0.7 * i as f32 / 16.0;
+                    let mut vertices = grid_vertices(1, 1, vec3!(-extent, -extent, z), vec3!(extent * 2.0, 0, 0), vec3!(0, extent * 2.0, 0));
+                    for vertex in vertices.iter_mut() {
+                        vertex.color = vec3!(shade, 0.4, 1.0 - shade);
+                    }
+                    scene.load_model(Model::new(vertices, Box::new(VertexColorShader::new())));
+                }
+            }
+        }
+        return scene;
+    }
+}
+
+
+
+/// writes a wavy grid with positions, texcoords and normals as an obj file, returns its triangle count
+pub fn write_obj_fixture(path: &Path, columns: u32, rows: u32) -> Result<u64> {
+    let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+    writeln!(out, "# benchmark fixture, {}x{} quads", columns, rows)?;
+    for row in 0..=rows {
+        for column in 0..=columns {
+            let (u, v) = (column as f32 / columns as f32, row as f32 / rows as f32);
+            let height = 0.2 * (u * 12.0).sin() * (v * 9.0).cos();
+            writeln!(out, "v {:.6} {:.6} {:.6}", u * 10.0 - 5.0, height, v * 10.0 - 5.0)?;
+            writeln!(out, "vt {:.6} {:.6}", u, v)?;
+            writeln!(out, "vn 0 1 0")?;
+        }
+    }
+    for row in 0..rows {
+        for column in 0..columns {
+            // obj indices start at 1
+            let a = row * (columns + 1) + column + 1;
+            let (b, c, d) = (a + 1, a + columns + 1, a + columns + 2);
+            writeln!(out, "f {0}/{0}/{0} {1}/{1}/{1} {2}/{2}/{2}", a, c, b)?;
+            writeln!(out, "f {0}/{0}/{0} {1}/{1}/{1} {2}/{2}/{2}", b, c, d)?;
+        }
+    }
+    out.flush()?;
+    return Ok(columns as u64 * rows as u64 * 2);
+}
+
+
+
+/// writes an uncompressed 24-bit bottom-up bitmap with a gradient, returns its pixel count
+pub fn write_bitmap_fixture(path: &Path, width: u32, height: u32) -> Result<u64> {
+    let row_size = (width * 3).div_ceil(4) * 4;
+    let pixel_offset: u32 = 14 + 40;
+    let file_size = pixel_offset + row_size * height;
+
+    let mut bytes: Vec<u8> = Vec::with_capacity(file_size as usize);
+    bytes.extend_from_slice(b"BM");
+    bytes.extend_from_slice(&file_size.to_le_bytes());
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.extend_from_slice(&pixel_offset.to_le_bytes());
+    // BITMAPINFOHEADER: size, width, height, planes, bits per pixel, BI_RGB, image size, resolution, palette
+    bytes.extend_from_slice(&40u32.to_le_bytes());
+    bytes.extend_from_slice(&(width as i32).to_le_bytes());
+    bytes.extend_from_slice(&(height as i32).to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&24u16.to_le_bytes());
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    bytes.extend_from_slice(&(row_size * height).to_le_bytes());
+    bytes.extend_from_slice(&[0; 16]);
+
+    for y in 0..height {
+        for x in 0..width {
+            bytes.extend_from_slice(&[(x * 255 / width) as u8, (y * 255 / height) as u8, ((x ^ y) & 0xFF) as u8]);
+        }
+        bytes.resize(bytes.len() + (row_size - width * 3) as usize, 0);
+    }
+    std::fs::write(path, bytes)?;
+    return Ok(width as u64 * height as u64);
+}
+
+
+
+// columns x rows quads from `origin` along `across` and `up`, front facing when `across` x `up` points at the viewer
+fn grid_vertices(columns: u32, rows: u32, origin: Vec3, across: Vec3, up: Vec3) -> Vec<Vertex> {
+    let point = |column: u32, row: u32| -> Vertex {
+        let (u, v) = (column as f32 / columns as f32, row as f32 / rows as f32);
+        return vertex(origin + across * u + up * v, vec2!(u, 1.0 - v), vec3!(1, 1, 1));
+    };
+
+    let mut vertices: Vec<Vertex> = Vec::with_capacity((columns * rows * 6) as usize);
+    for row in 0..rows {
+        for column in 0..columns {
+            let (a, b) = (point(column, row), point(column + 1, row));
+            let (c, d) = (point(column, row + 1), point(column + 1, row + 1));
+            vertices.extend_from_slice(&[a, b, d, a, d, c]);
+        }
+    }
+    return vertices;
+}
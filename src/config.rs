@@ -1,10 +1,434 @@
-use std::time::Duration;
+use std::fmt;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
 
-pub const WIDTH: u32 = 1920;
-pub const HEIGHT: u32 = 1080;
-pub const TARGET_FPS: u64 = 60;
-pub const FOV: f32 = 70.0;
-pub const FRAME_DURATION: Duration = Duration::from_micros(1_000_000 / TARGET_FPS); // ~16 ms
+use crate::benchmark::{BenchmarkOptions, DEFAULT_BASELINE_PATH};
+use crate::graphics::camera_controller::{ControllerKind, ControllerSettings};
+use crate::utils::capture::{CaptureFormat, DEFAULT_CAPTURE_DIRECTORY, DEFAULT_CAPTURE_FPS};
+use crate::utils::frame_pacing::FramePacing;
+use crate::utils::input::InputMap;
+use crate::utils::json::{Json, JsonError};
+use crate::utils::view::{PresentMode, ScaleFilter};
+use crate::Scene;
+
+pub const DEFAULT_WIDTH: u32 = 1920;
+pub const DEFAULT_HEIGHT: u32 = 1080;
+pub const DEFAULT_TARGET_FPS: u64 = 60;
+pub const DEFAULT_FOV: f32 = 70.0;
+pub const DEFAULT_CAMERA_SPEED: f32 = 4.0;
+pub const DEFAULT_MOUSE_SENSITIVITY: f32 = 100.0;
 pub const NEAR_CLIP_PLANE: f32 = -0.1; // z axis
-pub const CAMERA_SPEED: f32 = 4.0;
-pub const MOUSE_SENSITIVITY: f32 = 100.0;
+
+pub const DEFAULT_CONFIG_PATH: &str = "./config.json";
+pub const DEFAULT_SCENE_PATH: &str = "./scenes/monkey.json";
+const ENV_PREFIX: &str = "RASTERIZER_";
+
+pub const USAGE: &str = "\
+usage: rasterizer-rust [options] [scene.json]
+
+options:
+  --config <path>              config file, default ./config.json if it exists
+  --scene <path>               scene file, default ./scenes/monkey.json
+  --width <pixels>             render width, overrides the scene file
+  --height <pixels>            render height, overrides the scene file
+  --window-width <pixels>      initial window width
+  --window-height <pixels>     initial window height
+  --present-mode <mode>        stretch, fit (keeps the aspect ratio) or integer, default fit
+  --scale-filter <filter>      nearest or bilinear, default nearest
+  --follow-window              resize the render target with the window
+  --render-scale <factor>      render size relative to the window with --follow-window, default 1
+  --fov <degrees>              camera field of view, overrides the scene file
+  --hud                        start with the overlay shown, F3 toggles it
+  --frame-pacing <mode>        uncapped, fixed (at the target fps) or on_demand, default fixed
+  --target-fps <fps>
+  --camera-controller <kind>   fly, orbit, first_person, turntable or scripted (plays the scene's camera_path),
+                               default fly. F4 cycles them
+  --camera-speed <units/s>
+  --mouse-sensitivity <value>
+  --camera-smoothing <seconds> time for the camera to ease into a move, default 0
+  --capture-directory <path>   where screenshots (F12) and recordings (F9) go, default ./captures
+  --capture-format <format>    png (an image sequence) or y4m (a video stream) for recordings, default png
+  --capture-fps <fps>          frame rate recordings are simulated and played back at, default 60
+  --bind <name>=<inputs>       rebind an action or axis, can be repeated, e.g. --bind \"jump=Space, MouseRight\"
+  --benchmark                  run the benchmarks instead of opening a window, build with --release
+  --benchmark-filter <text>    only run benchmarks whose name contains the text
+  --benchmark-baseline <path>  results to compare against, written by the first run, default ./benchmarks/baseline.json
+  --save-baseline              overwrite the baseline with this run's results
+  -h, --help
+
+every option can also be set in the config file (e.g. \"window_width\": 1280)
+or the environment (e.g. RASTERIZER_WINDOW_WIDTH=1280).
+flags beat the environment, which beats the config file.
+
+actions: grab_mouse, release_mouse, orbit, pan, sprint, jump, pause, toggle_hud, next_camera, next_animation, screenshot, record.
+  inputs are comma separated chords like \"F12, Ctrl+P\": letters, digits, F1-F12, Space, Escape, Up,
+  LShift, Ctrl, MouseLeft, MouseRight, ScrollUp and so on.
+axes: move_x, move_y, move_z, look_x, look_y, zoom.
+  inputs are key pairs like \"W/S\" or mouse_x, mouse_y, scroll_x, scroll_y. \"-mouse_y\" inverts, \"scroll_y*2\" scales.
+the config file takes an object, e.g. \"bindings\": { \"jump\": \"Space\", \"look_y\": \"-mouse_y\" },
+and RASTERIZER_BIND takes several separated by ';'.";
+
+
+
+/// runtime settings, from lowest to highest priority: defaults, config file, environment, command line
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub scene_path: String,
+    // render resolution and fov, None keeps what the scene file asks for
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fov: Option<f32>,
+    pub window_width: u32,
+    pub window_height: u32,
+    pub present_mode: PresentMode,
+    pub scale_filter: ScaleFilter,
+    pub follow_window: bool,
+    pub render_scale: f32,
+    pub hud: bool,
+    pub frame_pacing: FramePacing,
+    pub target_fps: u64,
+    pub camera_controller: ControllerKind,
+    pub camera_speed: f32,
+    pub mouse_sensitivity: f32,
+    pub camera_smoothing: f32,
+    pub capture_directory: String,
+    pub capture_format: CaptureFormat,
+    pub capture_fps: u32,
+    pub input_map: InputMap,
+    pub benchmark: bool,
+    pub benchmark_filter: String,
+    pub benchmark_baseline: String,
+    pub save_baseline: bool,
+}
+
+
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(Error),
+    Json(JsonError),
+    // `source` names where the value came from, e.g. "--width" or "RASTERIZER_WIDTH"
+    Invalid { source: String, message: String },
+    HelpRequested,
+}
+
+
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "I/O error: {}", err),
+            ConfigError::Json(err) => write!(f, "config file: {}", err),
+            ConfigError::Invalid { source, message } => write!(f, "{}: {}", source, message),
+            ConfigError::HelpRequested => write!(f, "{}", USAGE),
+        }
+    }
+}
+
+
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io(err) => Some(err),
+            ConfigError::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+
+
+impl From<Error> for ConfigError {
+    fn from(err: Error) -> Self {
+        return ConfigError::Io(err);
+    }
+}
+
+
+
+impl From<JsonError> for ConfigError {
+    fn from(err: JsonError) -> Self {
+        return ConfigError::Json(err);
+    }
+}
+
+
+
+impl From<ConfigError> for Error {
+    fn from(err: ConfigError) -> Self {
+        match err {
+            ConfigError::Io(io_err) => io_err,
+            err => Error::new(ErrorKind::InvalidInput, err),
+        }
+    }
+}
+
+
+
+// config file keys, flags spell them with dashes and the environment in upper case
+const KEYS: [&str; 25] = [
+    "scene", "width", "height", "fov", "window_width", "window_height", "present_mode", "scale_filter", "follow_window", "render_scale",
+    "hud", "frame_pacing", "target_fps", "camera_controller", "camera_speed", "mouse_sensitivity", "camera_smoothing",
+    "capture_directory", "capture_format", "capture_fps", "bind", "benchmark", "benchmark_filter", "benchmark_baseline", "save_baseline",
+];
+
+// flags that mean "true" when given without a value
+const SWITCHES: [&str; 4] = ["follow_window", "hud", "benchmark", "save_baseline"];
+
+
+
+impl Config {
+    pub fn new() -> Config {
+        return Config {
+            scene_path: DEFAULT_SCENE_PATH.to_string(),
+            width: None,
+            height: None,
+            fov: None,
+            window_width: DEFAULT_WIDTH,
+            window_height: DEFAULT_HEIGHT,
+            present_mode: PresentMode::Fit,
+            scale_filter: ScaleFilter::Nearest,
+            follow_window: false,
+            render_scale: 1.0,
+            hud: false,
+            frame_pacing: FramePacing::Fixed,
+            target_fps: DEFAULT_TARGET_FPS,
+            camera_controller: ControllerKind::Fly,
+            camera_speed: DEFAULT_CAMERA_SPEED,
+            mouse_sensitivity: DEFAULT_MOUSE_SENSITIVITY,
+            camera_smoothing: 0.0,
+            capture_directory: DEFAULT_CAPTURE_DIRECTORY.to_string(),
+            capture_format: CaptureFormat::PngSequence,
+            capture_fps: DEFAULT_CAPTURE_FPS,
+            input_map: InputMap::new(),
+            benchmark: false,
+            benchmark_filter: String::new(),
+            benchmark_baseline: DEFAULT_BASELINE_PATH.to_string(),
+            save_baseline: false,
+        };
+    }
+
+
+    /// reads the process arguments and environment
+    pub fn load() -> Result<Config, ConfigError> {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        return Config::from_sources(&args, |name| std::env::var(name).ok());
+    }
+
+
+    /// `args` excludes the program name, `env` looks up an environment variable
+    pub fn from_sources(args: &[String], env: impl Fn(&str) -> Option<String>) -> Result<Config, ConfigError> {
+        let flags = parse_flags(args)?;
+        let mut config = Config::new();
+
+        // the config file's location can itself come from any source but the file
+        let explicit_path = flags.iter().find(|(key, _)| key == "config").map(|(_, value)| value.clone())
+            .or_else(|| env(&format!("{}CONFIG", ENV_PREFIX)));
+        let config_path = explicit_path.clone().unwrap_or(DEFAULT_CONFIG_PATH.to_string());
+        if explicit_path.is_some() || Path::new(&config_path).exists() {
+            config.apply_file(&config_path)?;
+        }
+
+        for key in KEYS {
+            let name = format!("{}{}", ENV_PREFIX, key.to_ascii_uppercase());
+            if let Some(value) = env(&name) {
+                config.set(key, &value, &name)?;
+            }
+        }
+
+        for (key, value) in &flags {
+            if key != "config" {
+                config.set(key, value, &format!("--{}", key.replace('_', "-")))?;
+            }
+        }
+
+        return Ok(config);
+    }
+
+
+    fn apply_file(&mut self, path: &str) -> Result<(), ConfigError> {
+        let text = std::fs::read_to_string(path)?;
+        let json = Json::parse(&text)?;
+        let Some(members) = json.as_object() else {
+            return Err(ConfigError::Invalid { source: path.to_string(), message: "expected an object".to_string() });
+        };
+
+        for (key, value) in members {
+            let source = format!("{}: {}", path, key);
+            if key == "bindings" {
+                self.apply_file_bindings(value, &source)?;
+                continue;
+            }
+            // numbers and strings are both accepted, everything goes through the same parser as flags
+            let text = match value {
+                Json::String(s) => s.clone(),
+                Json::Number(_) | Json::Bool(_) => value.to_string(),
+                _ => return Err(ConfigError::Invalid { source, message: "expected a number, string or boolean".to_string() }),
+            };
+            self.set(key, &text, &source)?;
+        }
+        return Ok(());
+    }
+
+
+    // "bindings": { "jump": "Space", "screenshot": ["F12", "Ctrl+P"] }
+    fn apply_file_bindings(&mut self, json: &Json, source: &str) -> Result<(), ConfigError> {
+        let invalid = |message: &str| ConfigError::Invalid { source: source.to_string(), message: message.to_string() };
+        let Some(bindings) = json.as_object() else {
+            return Err(invalid("expected an object of action names to inputs"));
+        };
+
+        for (name, value) in bindings {
+            let inputs = match value {
+                Json::String(s) => s.clone(),
+                Json::Array(items) => {
+                    let strings: Option<Vec<&str>> = items.iter().map(|item| item.as_str()).collect();
+                    strings.ok_or_else(|| invalid("expected a string or an array of strings"))?.join(", ")
+                }
+                _ => return Err(invalid("expected a string or an array of strings")),
+            };
+            self.set("bind", &format!("{}={}", name, inputs), &format!("{}.{}", source, name))?;
+        }
+        return Ok(());
+    }
+
+
+    fn set(&mut self, key: &str, value: &str, source: &str) -> Result<(), ConfigError> {
+        let invalid = |message: &str| ConfigError::Invalid { source: source.to_string(), message: format!("{} '{}'", message, value) };
+        let positive = |value: &str| -> Result<u32, ConfigError> {
+            return value.parse().ok().filter(|&n| n > 0).ok_or_else(|| invalid("expected a positive integer, got"));
+        };
+        let number = |value: &str| -> Result<f32, ConfigError> {
+            return value.parse().ok().filter(|n: &f32| n.is_finite() && *n > 0.0).ok_or_else(|| invalid("expected a positive number, got"));
+        };
+
+        match key {
+            "scene" => self.scene_path = value.to_string(),
+            "width" => self.width = Some(positive(value)?),
+            "height" => self.height = Some(positive(value)?),
+            "fov" => {
+                let fov = number(value)?;
+                if fov >= 180.0 {
+                    return Err(invalid("expected degrees below 180, got"));
+                }
+                self.fov = Some(fov);
+            }
+            "window_width" => self.window_width = positive(value)?,
+            "window_height" => self.window_height = positive(value)?,
+            "present_mode" => self.present_mode = PresentMode::parse(value).ok_or_else(|| invalid("expected stretch, fit or integer, got"))?,
+            "scale_filter" => self.scale_filter = ScaleFilter::parse(value).ok_or_else(|| invalid("expected nearest or bilinear, got"))?,
+            "follow_window" => self.follow_window = value.parse().map_err(|_| invalid("expected true or false, got"))?,
+            "render_scale" => self.render_scale = number(value)?,
+            "hud" => self.hud = value.parse().map_err(|_| invalid("expected true or false, got"))?,
+            "frame_pacing" => self.frame_pacing = FramePacing::parse(value).ok_or_else(|| invalid("expected uncapped, fixed or on_demand, got"))?,
+            "target_fps" => self.target_fps = positive(value)? as u64,
+            "camera_controller" => {
+                self.camera_controller = ControllerKind::parse(value).ok_or_else(|| invalid("expected fly, orbit, first_person, turntable or scripted, got"))?;
+            }
+            "camera_speed" => self.camera_speed = number(value)?,
+            "mouse_sensitivity" => self.mouse_sensitivity = number(value)?,
+            "capture_directory" => self.capture_directory = value.to_string(),
+            "capture_format" => self.capture_format = CaptureFormat::parse(value).ok_or_else(|| invalid("expected png or y4m, got"))?,
+            "capture_fps" => self.capture_fps = positive(value)?,
+            "bind" => {
+                for binding in value.split(';').map(str::trim).filter(|binding| !binding.is_empty()) {
+                    let Some((name, inputs)) = binding.split_once('=') else {
+                        return Err(invalid("expected name=inputs, got"));
+                    };
+                    self.input_map.rebind(name.trim(), inputs)
+                        .map_err(|err| ConfigError::Invalid { source: source.to_string(), message: err.to_string() })?;
+                }
+            }
+            "benchmark" => self.benchmark = value.parse().map_err(|_| invalid("expected true or false, got"))?,
+            "benchmark_filter" => self.benchmark_filter = value.to_string(),
+            "benchmark_baseline" => self.benchmark_baseline = value.to_string(),
+            "save_baseline" => self.save_baseline = value.parse().map_err(|_| invalid("expected true or false, got"))?,
+            "camera_smoothing" => {
+                self.camera_smoothing = value.parse().ok().filter(|n: &f32| n.is_finite() && *n >= 0.0)
+                    .ok_or_else(|| invalid("expected zero or a positive number of seconds, got"))?;
+            }
+            _ => return Err(ConfigError::Invalid { source: source.to_string(), message: format!("unknown setting '{}'", key) }),
+        }
+        return Ok(());
+    }
+
+
+    pub fn controller_settings(&self) -> ControllerSettings {
+        let mut settings = ControllerSettings::new();
+        settings.speed = self.camera_speed;
+        settings.sensitivity = self.mouse_sensitivity;
+        settings.smoothing = self.camera_smoothing;
+        return settings;
+    }
+
+
+    /// what `--benchmark` runs against and whether it saves a new baseline
+    pub fn benchmark_options(&self) -> BenchmarkOptions {
+        return BenchmarkOptions {
+            baseline_path: self.benchmark_baseline.clone(),
+            save_baseline: self.save_baseline,
+            filter: self.benchmark_filter.clone(),
+        };
+    }
+
+
+    /// overrides the scene's resolution and fov where set and gives it the configured camera controller
+    pub fn apply(&self, scene: &mut Scene) {
+        if let Some(width) = self.width {
+            scene.settings.width = width;
+        }
+        if let Some(height) = self.height {
+            scene.settings.height = height;
+        }
+        if let Some(fov) = self.fov {
+            scene.camera.fov = fov;
+        }
+        scene.set_controller(self.camera_controller.create(&self.controller_settings(), scene.camera_path.as_ref()));
+    }
+}
+
+
+
+// (key, value) pairs in command line order, keys use the config file's spelling
+fn parse_flags(args: &[String]) -> Result<Vec<(String, String)>, ConfigError> {
+    let mut flags: Vec<(String, String)> = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            return Err(ConfigError::HelpRequested);
+        }
+
+        let Some(flag) = arg.strip_prefix("--") else {
+            if arg.starts_with('-') {
+                return Err(ConfigError::Invalid { source: arg.clone(), message: "unknown flag".to_string() });
+            }
+            // a bare argument is the scene
+            flags.push(("scene".to_string(), arg.clone()));
+            continue;
+        };
+
+        // both "--width 1280" and "--width=1280"
+        let (name, inline_value) = match flag.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (flag, None),
+        };
+        let key = name.replace('-', "_");
+        if key != "config" && !KEYS.contains(&key.as_str()) {
+            return Err(ConfigError::Invalid { source: arg.clone(), message: "unknown flag".to_string() });
+        }
+
+        let value = match inline_value {
+            Some(value) => value,
+            None if SWITCHES.contains(&key.as_str()) => "true".to_string(),
+            None => match args.next() {
+                Some(value) => value.clone(),
+                None => return Err(ConfigError::Invalid { source: arg.clone(), message: "missing value".to_string() }),
+            },
+        };
+        flags.push((key, value));
+    }
+
+    return Ok(flags);
+}
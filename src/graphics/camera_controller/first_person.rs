@@ -0,0 +1,101 @@
+use crate::graphics::camera_controller::*;
+use crate::utils::view::View;
+use crate::{vec2, vec3, Camera, Vec2, Vec3};
+
+
+
+/// walking on a flat floor: the move axes on the ground plane, gravity and a jump action, mouse look while captured
+pub struct FirstPersonController {
+    pub settings: ControllerSettings,
+    pub sprint_multiplier: f32,
+    pub pitch_limit: f32,
+    // camera height above the floor
+    pub eye_height: f32,
+    // world y of the floor, attach puts it under the camera so it starts out standing where it is
+    pub floor_height: f32,
+    // units per second squared
+    pub gravity: f32,
+    // upward speed a jump starts with
+    pub jump_speed: f32,
+    look: Option<Vec2>,
+    // horizontal only, falling and jumping use `vertical_velocity`
+    velocity: Vec3,
+    vertical_velocity: f32,
+}
+
+
+
+impl FirstPersonController {
+    pub fn new(settings: ControllerSettings) -> FirstPersonController {
+        return FirstPersonController {
+            settings,
+            sprint_multiplier: 1.8,
+            pitch_limit: 89.0,
+            eye_height: 1.7,
+            floor_height: -1.7,
+            gravity: 18.0,
+            jump_speed: 6.0,
+            look: None,
+            velocity: vec3!(0, 0, 0),
+            vertical_velocity: 0.0,
+        };
+    }
+}
+
+
+
+impl CameraController for FirstPersonController {
+    fn name(&self) -> &'static str {
+        return "first_person";
+    }
+
+
+    fn attach(&mut self, camera: &Camera, focus: Vec3) {
+        self.look = Some(vec2!(camera.transform.pitch, camera.transform.yaw));
+        self.floor_height = camera.transform.position.y - self.eye_height;
+        self.velocity = vec3!(0, 0, 0);
+        self.vertical_velocity = 0.0;
+    }
+
+
+    fn update(&mut self, camera: &mut Camera, view: &mut View, delta_time: f32) -> bool {
+        let k = smoothing_factor(self.settings.smoothing, delta_time);
+        let transform = &mut camera.transform;
+
+        let look_delta = mouse_look(view, self.settings.sensitivity);
+        let mut look = self.look.unwrap_or(vec2!(transform.pitch, transform.yaw));
+        look.x = (look.x + look_delta.y).clamp(-self.pitch_limit, self.pitch_limit);
+        look.y -= look_delta.x;
+        self.look = Some(look);
+        let turned = ease_rotation(transform, look, k);
+
+        // walking ignores pitch, looking at your feet doesn't slow you down
+        let yaw = transform.yaw.to_radians();
+        let forward = vec3!(-yaw.sin(), 0, -yaw.cos());
+        let right = vec3!(yaw.cos(), 0, -yaw.sin());
+        let direction = forward * view.axis("move_z") + right * view.axis("move_x");
+
+        let mut speed = self.settings.speed;
+        if view.action_held("sprint") { speed *= self.sprint_multiplier }
+        ease_velocity(&mut self.velocity, direction.normalize() * speed, k, speed);
+        transform.position += self.velocity * delta_time;
+
+        let eye_level = self.floor_height + self.eye_height;
+        let grounded = transform.position.y <= eye_level && self.vertical_velocity <= 0.0;
+        if grounded && view.action_held("jump") {
+            self.vertical_velocity = self.jump_speed;
+        }
+
+        let airborne = !grounded || self.vertical_velocity > 0.0;
+        if airborne {
+            self.vertical_velocity -= self.gravity * delta_time;
+            transform.position.y += self.vertical_velocity * delta_time;
+        }
+        if transform.position.y <= eye_level {
+            transform.position.y = eye_level;
+            self.vertical_velocity = 0.0;
+        }
+
+        return turned || self.velocity.length() > 0.0 || airborne;
+    }
+}
@@ -0,0 +1,67 @@
+use crate::graphics::camera_controller::*;
+use crate::utils::view::View;
+use crate::{vec2, vec3, Camera, Vec2, Vec3};
+
+
+
+/// free flight: move_z along the view direction, move_x sideways and move_y straight up and down,
+/// mouse look while captured
+pub struct FlyController {
+    pub settings: ControllerSettings,
+    pub sprint_multiplier: f32,
+    pub pitch_limit: f32,
+    // (pitch, yaw) the view is easing towards, picked up from the camera on the first frame
+    look: Option<Vec2>,
+    velocity: Vec3,
+}
+
+
+
+impl FlyController {
+    pub fn new(settings: ControllerSettings) -> FlyController {
+        return FlyController {
+            settings,
+            sprint_multiplier: 2.8,
+            pitch_limit: 85.0,
+            look: None,
+            velocity: vec3!(0, 0, 0),
+        };
+    }
+}
+
+
+
+impl CameraController for FlyController {
+    fn name(&self) -> &'static str {
+        return "fly";
+    }
+
+
+    fn attach(&mut self, camera: &Camera, focus: Vec3) {
+        self.look = Some(vec2!(camera.transform.pitch, camera.transform.yaw));
+        self.velocity = vec3!(0, 0, 0);
+    }
+
+
+    fn update(&mut self, camera: &mut Camera, view: &mut View, delta_time: f32) -> bool {
+        let k = smoothing_factor(self.settings.smoothing, delta_time);
+        let transform = &mut camera.transform;
+
+        let look_delta = mouse_look(view, self.settings.sensitivity);
+        let mut look = self.look.unwrap_or(vec2!(transform.pitch, transform.yaw));
+        look.x = (look.x + look_delta.y).clamp(-self.pitch_limit, self.pitch_limit);
+        look.y -= look_delta.x;
+        self.look = Some(look);
+        let turned = ease_rotation(transform, look, k);
+
+        let (cam_right, cam_up, cam_fwd) = transform.basis_vectors;
+        let direction = cam_right * view.axis("move_x") - cam_fwd * view.axis("move_z") + vec3!(0, 1, 0) * view.axis("move_y");
+
+        let mut speed = self.settings.speed;
+        if view.action_held("sprint") { speed *= self.sprint_multiplier }
+        ease_velocity(&mut self.velocity, direction.normalize() * speed, k, speed);
+
+        transform.position += self.velocity * delta_time;
+        return turned || self.velocity.length() > 0.0;
+    }
+}
@@ -0,0 +1,178 @@
+pub mod first_person;
+pub mod fly;
+pub mod orbit;
+pub mod scripted;
+
+pub use first_person::*;
+pub use fly::*;
+pub use orbit::*;
+pub use scripted::*;
+
+use crate::config::{DEFAULT_CAMERA_SPEED, DEFAULT_MOUSE_SENSITIVITY};
+use crate::utils::view::View;
+use crate::{vec2, Camera, Transform, Vec2, Vec3};
+
+
+
+// below this smoothed motion counts as stopped, so on-demand pacing can go idle
+pub(crate) const REST_EPSILON: f32 = 1e-3;
+
+
+
+/// moves a camera from the view's input actions and axes, `Scene` holds one and runs it every frame
+pub trait CameraController {
+    fn name(&self) -> &'static str;
+
+
+    /// called when the controller takes over `camera`, `focus` is roughly what the scene is centred on
+    fn attach(&mut self, camera: &Camera, focus: Vec3) {}
+
+
+    /// returns whether the camera moved
+    fn update(&mut self, camera: &mut Camera, view: &mut View, delta_time: f32) -> bool;
+}
+
+
+
+/// tuning shared by the interactive controllers
+#[derive(Debug, Clone)]
+pub struct ControllerSettings {
+    // units per second
+    pub speed: f32,
+    // degrees per window width of mouse movement
+    pub sensitivity: f32,
+    // seconds for movement and look to ease towards the input, 0 follows it exactly
+    pub smoothing: f32,
+}
+
+
+
+impl ControllerSettings {
+    pub fn new() -> ControllerSettings {
+        return ControllerSettings {
+            speed: DEFAULT_CAMERA_SPEED,
+            sensitivity: DEFAULT_MOUSE_SENSITIVITY,
+            smoothing: 0.0,
+        };
+    }
+}
+
+
+
+/// the controllers that can be picked from the config or cycled at runtime
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ControllerKind {
+    Fly,
+    Orbit,
+    FirstPerson,
+    // an orbit camera that circles its target on its own
+    Turntable,
+    // plays the scene's camera path, a fly camera when there isn't one
+    Scripted,
+}
+
+
+
+impl ControllerKind {
+    pub fn parse(name: &str) -> Option<ControllerKind> {
+        match name {
+            "fly" => return Some(ControllerKind::Fly),
+            "orbit" => return Some(ControllerKind::Orbit),
+            "first_person" | "first-person" | "fps" => return Some(ControllerKind::FirstPerson),
+            "turntable" => return Some(ControllerKind::Turntable),
+            "scripted" => return Some(ControllerKind::Scripted),
+            _ => return None,
+        }
+    }
+
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ControllerKind::Fly => return "fly",
+            ControllerKind::Orbit => return "orbit",
+            ControllerKind::FirstPerson => return "first_person",
+            ControllerKind::Turntable => return "turntable",
+            ControllerKind::Scripted => return "scripted",
+        }
+    }
+
+
+    /// the scripted controller is only in the cycle when there's a camera path to play
+    pub fn next(self, has_path: bool) -> ControllerKind {
+        match self {
+            ControllerKind::Fly => return ControllerKind::Orbit,
+            ControllerKind::Orbit => return ControllerKind::FirstPerson,
+            ControllerKind::FirstPerson => return ControllerKind::Turntable,
+            ControllerKind::Turntable if has_path => return ControllerKind::Scripted,
+            ControllerKind::Turntable | ControllerKind::Scripted => return ControllerKind::Fly,
+        }
+    }
+
+
+    pub fn create(self, settings: &ControllerSettings, path: Option<&CameraPath>) -> Box<dyn CameraController> {
+        match self {
+            ControllerKind::Fly => return Box::new(FlyController::new(settings.clone())),
+            ControllerKind::Orbit => return Box::new(OrbitController::new(settings.clone())),
+            ControllerKind::FirstPerson => return Box::new(FirstPersonController::new(settings.clone())),
+            ControllerKind::Turntable => {
+                let mut orbit = OrbitController::new(settings.clone());
+                orbit.auto_rotate = DEFAULT_TURNTABLE_SPEED;
+                return Box::new(orbit);
+            }
+            ControllerKind::Scripted => match path {
+                Some(path) => return Box::new(ScriptedController::from_path(path)),
+                None => return Box::new(FlyController::new(settings.clone())),
+            },
+        }
+    }
+}
+
+
+
+/// fraction of the way to its target a smoothed value covers this frame, independent of frame rate
+pub fn smoothing_factor(smoothing: f32, delta_time: f32) -> f32 {
+    if smoothing <= 0.0 {
+        return 1.0;
+    }
+    return 1.0 - (-delta_time / smoothing).exp();
+}
+
+
+
+// the grab_mouse action captures the cursor and release_mouse lets it go, returns the look delta in degrees while captured
+pub(crate) fn mouse_look(view: &mut View, sensitivity: f32) -> Vec2 {
+    if view.action_pressed("grab_mouse") { view.set_mouse_grab(true);}
+    if view.action_held("release_mouse") { view.set_mouse_grab(false);}
+
+    if !view.is_focused || view.width == 0 {
+        return vec2!(0, 0);
+    }
+    return (vec2!(view.axis("look_x"), view.axis("look_y")) / view.width as f32) * sensitivity;
+}
+
+
+
+// eases the rotation towards `look` (pitch, yaw), snapping once it's close enough to call the camera still.
+// returns whether it turned
+pub(crate) fn ease_rotation(transform: &mut Transform, look: Vec2, k: f32) -> bool {
+    let mut pitch = transform.pitch + (look.x - transform.pitch) * k;
+    let mut yaw = transform.yaw + (look.y - transform.yaw) * k;
+    if (look.x - pitch).abs() < REST_EPSILON && (look.y - yaw).abs() < REST_EPSILON {
+        (pitch, yaw) = (look.x, look.y);
+    }
+    if pitch == transform.pitch && yaw == transform.yaw {
+        return false;
+    }
+    transform.set_rotation(pitch, yaw);
+    return true;
+}
+
+
+
+// eases `velocity` towards `target` the same way
+pub(crate) fn ease_velocity(velocity: &mut Vec3, target: Vec3, k: f32, speed: f32) {
+    *velocity += (target - *velocity) * k;
+    if (target - *velocity).length() < REST_EPSILON * speed {
+        *velocity = target;
+    }
+}
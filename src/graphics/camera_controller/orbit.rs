@@ -0,0 +1,171 @@
+use crate::graphics::camera_controller::*;
+use crate::utils::view::View;
+use crate::{vec2, vec3, Camera, Vec2, Vec3};
+
+
+
+/// degrees per second the turntable controller circles at
+pub const DEFAULT_TURNTABLE_SPEED: f32 = 20.0;
+
+// how far attach puts the target when the camera is already sitting on the focus point
+const DEFAULT_DISTANCE: f32 = 5.0;
+
+// degrees per second from the left and right keys
+const KEY_ORBIT_SPEED: f32 = 90.0;
+
+
+
+/// circles a target: dragging with the orbit action held orbits, with pan held pans, and the zoom axis zooms.
+/// move_x orbits and move_z zooms too, and a fling keeps spinning for `inertia` seconds after letting go
+pub struct OrbitController {
+    pub settings: ControllerSettings,
+    pub target: Vec3,
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    // degrees, positive looks down on the target
+    pub pitch: f32,
+    pub yaw: f32,
+    pub pitch_limit: f32,
+    // fraction of the distance one wheel line zooms by
+    pub zoom_step: f32,
+    // degrees per second of yaw applied while not dragging, 0 is off
+    pub auto_rotate: f32,
+    // seconds for a fling to slow to about a third, 0 stops dead on release
+    pub inertia: f32,
+    // where the camera is now, easing towards the fields above with `settings.smoothing`
+    current: Option<OrbitPose>,
+    // (pitch, yaw) degrees per second from the last drag
+    spin: Vec2,
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct OrbitPose {
+    target: Vec3,
+    distance: f32,
+    pitch: f32,
+    yaw: f32,
+}
+
+
+
+impl OrbitController {
+    pub fn new(settings: ControllerSettings) -> OrbitController {
+        return OrbitController {
+            settings,
+            target: vec3!(0, 0, 0),
+            distance: DEFAULT_DISTANCE,
+            min_distance: 0.05,
+            max_distance: 1000.0,
+            pitch: 0.0,
+            yaw: 0.0,
+            pitch_limit: 89.0,
+            zoom_step: 0.1,
+            auto_rotate: 0.0,
+            inertia: 0.3,
+            current: None,
+            spin: vec2!(0, 0),
+        };
+    }
+
+
+    fn pose(&self) -> OrbitPose {
+        return OrbitPose { target: self.target, distance: self.distance, pitch: self.pitch, yaw: self.yaw };
+    }
+}
+
+
+
+impl CameraController for OrbitController {
+    fn name(&self) -> &'static str {
+        return if self.auto_rotate != 0.0 { "turntable" } else { "orbit" };
+    }
+
+
+    /// keeps the camera where it is and turns it to face `focus`
+    fn attach(&mut self, camera: &Camera, focus: Vec3) {
+        let mut offset = camera.transform.position - focus;
+        if offset.length() < self.min_distance {
+            offset = camera.transform.forward() * -DEFAULT_DISTANCE;
+        }
+        let direction = offset.normalize();
+
+        self.target = camera.transform.position - offset;
+        self.distance = offset.length().clamp(self.min_distance, self.max_distance);
+        self.pitch = direction.y.clamp(-1.0, 1.0).asin().to_degrees().clamp(-self.pitch_limit, self.pitch_limit);
+        self.yaw = direction.x.atan2(direction.z).to_degrees();
+        self.current = Some(self.pose());
+        self.spin = vec2!(0, 0);
+    }
+
+
+    fn update(&mut self, camera: &mut Camera, view: &mut View, delta_time: f32) -> bool {
+        // a fly or first person controller may have left the cursor captured
+        if view.is_focused {
+            view.set_mouse_grab(false);
+        }
+        if self.current.is_none() {
+            self.attach(camera, camera.transform.position + camera.transform.forward() * DEFAULT_DISTANCE);
+        }
+
+        let mouse = view.mouse_delta();
+        let look_scale = if view.width > 0 { self.settings.sensitivity / view.width as f32 } else { 0.0 };
+
+        if view.action_held("orbit") {
+            let orbit = mouse * look_scale;
+            self.pitch += orbit.y;
+            self.yaw -= orbit.x;
+            if delta_time > 0.0 {
+                self.spin = vec2!(orbit.y, -orbit.x) / delta_time;
+            }
+        } else {
+            self.pitch += self.spin.x * delta_time;
+            self.yaw += (self.spin.y + self.auto_rotate) * delta_time;
+            self.spin = if self.inertia > 0.0 { self.spin * (-delta_time / self.inertia).exp() } else { vec2!(0, 0) };
+            if self.spin.dot(self.spin) < REST_EPSILON {
+                self.spin = vec2!(0, 0);
+            }
+        }
+        self.yaw -= view.axis("move_x") * KEY_ORBIT_SPEED * delta_time;
+        self.pitch = self.pitch.clamp(-self.pitch_limit, self.pitch_limit);
+
+        if view.action_held("pan") {
+            // one pixel moves the target one pixel's worth of the view at its distance
+            let view_height = 2.0 * self.distance * (camera.fov.to_radians() / 2.0).tan();
+            let pan_scale = view_height / view.height.max(1) as f32;
+            let (cam_right, cam_up, cam_fwd) = camera.transform.basis_vectors;
+            self.target += cam_up * mouse.y * pan_scale - cam_right * mouse.x * pan_scale;
+        }
+
+        let zoom = view.axis("zoom") + view.axis("move_z") * delta_time * 10.0;
+        self.distance = (self.distance * (1.0 - self.zoom_step).powf(zoom)).clamp(self.min_distance, self.max_distance);
+
+        let k = smoothing_factor(self.settings.smoothing, delta_time);
+        let goal = self.pose();
+        let mut current = self.current.unwrap_or(goal);
+        current.target += (goal.target - current.target) * k;
+        current.distance += (goal.distance - current.distance) * k;
+        current.pitch += (goal.pitch - current.pitch) * k;
+        current.yaw += (goal.yaw - current.yaw) * k;
+        if (goal.target - current.target).length() < REST_EPSILON * goal.distance
+            && (goal.distance - current.distance).abs() < REST_EPSILON * goal.distance
+            && (goal.pitch - current.pitch).abs() < REST_EPSILON
+            && (goal.yaw - current.yaw).abs() < REST_EPSILON
+        {
+            current = goal;
+        }
+        self.current = Some(current);
+
+        let (pitch, yaw) = (current.pitch.to_radians(), current.yaw.to_radians());
+        let position = current.target + vec3!(yaw.sin() * pitch.cos(), pitch.sin(), yaw.cos() * pitch.cos()) * current.distance;
+        let transform = &mut camera.transform;
+        if transform.position == position && transform.pitch == current.pitch && transform.yaw == current.yaw {
+            return false;
+        }
+        transform.position = position;
+        transform.set_rotation(current.pitch, current.yaw);
+        return true;
+    }
+}
@@ -0,0 +1,166 @@
+use crate::graphics::camera_controller::*;
+use crate::utils::view::View;
+use crate::{Camera, Vec3};
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraWaypoint {
+    // seconds from the start of the path
+    pub time: f32,
+    pub position: Vec3,
+    pub pitch: f32,
+    pub yaw: f32,
+}
+
+
+
+/// waypoints from a scene file, what `ControllerKind::Scripted` builds its controller from
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraPath {
+    pub waypoints: Vec<CameraWaypoint>,
+    pub looping: bool,
+}
+
+
+
+/// plays the camera along a path of waypoints, ignoring input apart from the pause action.
+/// positions and angles follow a catmull-rom spline through the waypoints, a looping path should end where it starts
+pub struct ScriptedController {
+    // sorted by time
+    pub waypoints: Vec<CameraWaypoint>,
+    pub looping: bool,
+    pub playback_speed: f32,
+    pub playing: bool,
+    pub time: f32,
+}
+
+
+
+impl ScriptedController {
+    pub fn new(mut waypoints: Vec<CameraWaypoint>) -> ScriptedController {
+        waypoints.sort_by(|a, b| a.time.total_cmp(&b.time));
+        return ScriptedController {
+            waypoints,
+            looping: true,
+            playback_speed: 1.0,
+            playing: true,
+            time: 0.0,
+        };
+    }
+
+
+    pub fn from_path(path: &CameraPath) -> ScriptedController {
+        let mut controller = ScriptedController::new(path.waypoints.clone());
+        controller.looping = path.looping;
+        return controller;
+    }
+
+
+    /// seconds from the first waypoint to the last
+    pub fn duration(&self) -> f32 {
+        let (Some(first), Some(last)) = (self.waypoints.first(), self.waypoints.last()) else {
+            return 0.0;
+        };
+        return last.time - first.time;
+    }
+
+
+    /// the camera pose at `time` seconds into the path, None without waypoints
+    pub fn sample(&self, time: f32) -> Option<CameraWaypoint> {
+        let first = *self.waypoints.first()?;
+        let last = *self.waypoints.last()?;
+        let duration = last.time - first.time;
+        if duration <= 0.0 {
+            return Some(first);
+        }
+
+        let time = if self.looping {
+            first.time + (time - first.time).rem_euclid(duration)
+        } else {
+            time.clamp(first.time, last.time)
+        };
+
+        // the segment [i, i + 1] containing `time`
+        let count = self.waypoints.len();
+        let i = self.waypoints.partition_point(|waypoint| waypoint.time <= time).clamp(1, count - 1) - 1;
+        let (p1, p2) = (self.waypoints[i], self.waypoints[i + 1]);
+        let t = if p2.time > p1.time { (time - p1.time) / (p2.time - p1.time) } else { 0.0 };
+
+        // outer control points, repeating the ends of an open path and wrapping around a looping one.
+        // a looping path ends on the pose it started with, so wrapping skips over that duplicate
+        let last_index = count as isize - 1;
+        let neighbour = |index: isize| -> CameraWaypoint {
+            if self.looping && count > 2 {
+                if index < 0 {
+                    return self.waypoints[(index + last_index) as usize];
+                }
+                if index > last_index {
+                    return self.waypoints[(index - last_index) as usize];
+                }
+            }
+            return self.waypoints[index.clamp(0, last_index) as usize];
+        };
+        let p0 = neighbour(i as isize - 1);
+        let p3 = neighbour(i as isize + 2);
+
+        return Some(CameraWaypoint {
+            time,
+            position: catmull_rom(p0.position, p1.position, p2.position, p3.position, t),
+            pitch: catmull_rom_scalar(p0.pitch, p1.pitch, p2.pitch, p3.pitch, t),
+            yaw: catmull_rom_scalar(p0.yaw, p1.yaw, p2.yaw, p3.yaw, t),
+        });
+    }
+}
+
+
+
+impl CameraController for ScriptedController {
+    fn name(&self) -> &'static str {
+        return "scripted";
+    }
+
+
+    fn attach(&mut self, camera: &Camera, focus: Vec3) {
+        self.time = self.waypoints.first().map_or(0.0, |waypoint| waypoint.time);
+    }
+
+
+    fn update(&mut self, camera: &mut Camera, view: &mut View, delta_time: f32) -> bool {
+        if view.action_pressed("pause") {
+            self.playing = !self.playing;
+        }
+        let Some(pose) = self.sample(self.time) else {
+            return false;
+        };
+        if self.playing {
+            self.time += delta_time * self.playback_speed;
+        }
+
+        let transform = &mut camera.transform;
+        if transform.position == pose.position && transform.pitch == pose.pitch && transform.yaw == pose.yaw {
+            return false;
+        }
+        transform.position = pose.position;
+        transform.set_rotation(pose.pitch, pose.yaw);
+        return true;
+    }
+}
+
+
+
+fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+    return Vec3 {
+        x: catmull_rom_scalar(p0.x, p1.x, p2.x, p3.x, t),
+        y: catmull_rom_scalar(p0.y, p1.y, p2.y, p3.y, t),
+        z: catmull_rom_scalar(p0.z, p1.z, p2.z, p3.z, t),
+    };
+}
+
+
+
+fn catmull_rom_scalar(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    return 0.5 * (2.0 * p1 + (p2 - p0) * t + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2 + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3);
+}
@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use crate::graphics::rasterizer::RenderStats;
+use crate::graphics::text::{draw_text, fill_rect, text_size, LINE_HEIGHT};
+use crate::utils::frame_pacing::{FrameStats, FRAME_HISTORY};
+use crate::{RenderTarget, Scene};
+
+
+
+const TEXT_COLOR: u32 = 0xFFFFFFFF;
+const BACKGROUND_COLOR: u32 = 0xA0000000;
+const GRAPH_COLOR: u32 = 0xFF40D040;
+const SLOW_FRAME_COLOR: u32 = 0xFFE04040;
+const GUIDE_COLOR: u32 = 0x80FFFFFF;
+
+const GRAPH_HEIGHT: u32 = 40;
+const PADDING: u32 = 4;
+
+// guide lines on the frame-time graph
+const FRAME_BUDGET_60: Duration = Duration::from_micros(16_667);
+const FRAME_BUDGET_30: Duration = Duration::from_micros(33_333);
+
+
+
+/// overlay with frame timings, a frame-time graph, triangle counts, the camera and the render mode
+pub struct Hud {
+    pub visible: bool,
+    // pixel size of the text, 0 picks one from the target height
+    pub scale: u32,
+}
+
+
+
+impl Hud {
+    pub fn new() -> Hud {
+        return Hud { visible: false, scale: 0 };
+    }
+
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+
+    /// draws into the top left corner of `target`, call it after the scene has been rendered
+    pub fn draw(&self, target: &mut RenderTarget, scene: &Scene, frame_stats: &FrameStats, render_stats: &RenderStats, render_mode: &str) {
+        if !self.visible {
+            return;
+        }
+        let scale = if self.scale > 0 { self.scale } else { (target.height / 540).max(1) };
+
+        let camera = &scene.camera;
+        let position = camera.transform.position;
+        let milliseconds = |d: Duration| d.as_secs_f64() * 1000.0;
+        let text = format!(
+            "{:.1} fps  {:.2} ms avg  {:.2} ms p99  {:.2} ms max\n\
+             \n\
+             tris {} drawn / {} visible / {} total  points {}\n\
+             camera {:.2} {:.2} {:.2}  yaw {:.1} pitch {:.1}  fov {:.0}  {}\n\
+             {}",
+            frame_stats.fps(),
+            milliseconds(frame_stats.average_frame_time()),
+            milliseconds(frame_stats.percentile_frame_time(0.99)),
+            milliseconds(frame_stats.max_frame_time()),
+            render_stats.drawn_triangles,
+            render_stats.visible_triangles,
+            render_stats.triangles,
+            render_stats.points,
+            position.x,
+            position.y,
+            position.z,
+            camera.transform.yaw,
+            camera.transform.pitch,
+            camera.fov,
+            scene.controller.name(),
+            render_mode,
+        );
+
+        // the empty second line leaves room for the graph
+        let (text_width, text_height) = text_size(&text, scale);
+        let graph_width = FRAME_HISTORY as u32 * scale;
+        let graph_height = GRAPH_HEIGHT * scale;
+        let line_height = LINE_HEIGHT * scale;
+        let padding = PADDING * scale;
+
+        let panel_width = text_width.max(graph_width) + padding * 2;
+        let panel_height = text_height + graph_height - line_height + padding * 3;
+        fill_rect(target, 0, 0, panel_width, panel_height, BACKGROUND_COLOR);
+
+        let (first_line, rest) = text.split_once("\n\n").unwrap_or((&text, ""));
+        draw_text(target, padding as i32, padding as i32, first_line, TEXT_COLOR, scale);
+
+        let graph_top = padding * 2 + line_height;
+        self.draw_graph(target, frame_stats, padding, graph_top, graph_width, graph_height, scale);
+
+        draw_text(target, padding as i32, (graph_top + graph_height + padding) as i32, rest, TEXT_COLOR, scale);
+    }
+
+
+    // one bar per frame, newest on the right, scaled so 30 fps always fits
+    fn draw_graph(&self, target: &mut RenderTarget, frame_stats: &FrameStats, x: u32, y: u32, width: u32, height: u32, scale: u32) {
+        let longest = frame_stats.frame_times().max().unwrap_or_default().max(FRAME_BUDGET_30);
+        let bar_height = |d: Duration| ((d.as_secs_f64() / longest.as_secs_f64()) * height as f64).round() as u32;
+
+        let samples = frame_stats.frame_times().count() as u32;
+        let start = x + width - samples * scale;
+        for (i, frame_time) in frame_stats.frame_times().enumerate() {
+            let bar = bar_height(frame_time).clamp(1, height);
+            let color = if frame_time > FRAME_BUDGET_60 { SLOW_FRAME_COLOR } else { GRAPH_COLOR };
+            fill_rect(target, (start + i as u32 * scale) as i32, (y + height - bar) as i32, scale, bar, color);
+        }
+
+        for budget in [FRAME_BUDGET_60, FRAME_BUDGET_30] {
+            let guide_y = y + height - bar_height(budget).min(height);
+            fill_rect(target, x as i32, guide_y as i32, width, scale.div_ceil(2), GUIDE_COLOR);
+        }
+    }
+}
@@ -1,4 +1,7 @@
 pub mod camera;
+pub mod camera_controller;
+pub mod hud;
 pub mod render_target;
 pub mod rasterizer;
-pub mod scene;
\ No newline at end of file
+pub mod scene;
+pub mod text;
\ No newline at end of file
@@ -1,4 +1,7 @@
-use crate::{graphics::camera::Camera, types::vertex::Vertex, vec2, vec3, Model, RenderTarget, Scene, Transform, Vec2, Vec3, Random};
+use crate::animation::{skin_vertex, Skeleton};
+use crate::types::matrix::Mat4;
+use crate::types::morph::morph_vertices;
+use crate::{graphics::camera::Camera, types::vertex::Vertex, types::shader::Fragment, vec2, vec3, Model, RenderSettings, RenderTarget, Scene, Transform, Vec2, Vec3, Random};
 use std::simd::{f32x8, u32x8, Simd, Mask, prelude::SimdPartialEq, prelude::SimdPartialOrd};
 
 
@@ -13,6 +16,21 @@ pub struct Rasterizer {
     cached_aspect: f32,
     cached_near: f32,
     cached_far: f32,
+
+    pub stats: RenderStats,
+}
+
+
+
+/// counts from the last `render` call
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderStats {
+    pub triangles: u32,
+    // left after clipping and backface culling, clipped triangles can split in two
+    pub visible_triangles: u32,
+    // covered at least one pixel in the color pass
+    pub drawn_triangles: u32,
+    pub points: u32,
 }
 
 
@@ -30,6 +48,8 @@ impl Rasterizer {
             cached_aspect: -1.0,
             cached_near: -1.0,
             cached_far: -1.0,
+
+            stats: RenderStats::default(),
         };
     }
 
@@ -37,15 +57,21 @@ impl Rasterizer {
     // TODO: separate into geometry, depth and color pass
     pub fn render(&mut self, render_target: &mut RenderTarget, scene: &Scene){
         // clear buffers
-        render_target.color_buffer.fill(0);
+        render_target.color_buffer.fill(scene.settings.background_argb());
         render_target.depth_buffer.fill(f32::NEG_INFINITY);
 
         let triangles: Vec<RasterTriangle> = self.geometry_setup_pass(scene, render_target);
-        self.depth_pass(render_target, &triangles);
+        self.depth_pass(scene, render_target, &triangles);
 
-        self.color_pass(scene, render_target, &triangles);
-        
-        // println!("Tri count: {}", num_tris);
+        let drawn_triangles = self.color_pass(scene, render_target, &triangles);
+        self.point_pass(scene, render_target);
+
+        self.stats = RenderStats {
+            triangles: scene.models.iter().map(|model| model.vertices.len() as u32 / 3).sum(),
+            visible_triangles: triangles.len() as u32,
+            drawn_triangles,
+            points: scene.point_clouds.iter().map(|cloud| cloud.points.len() as u32).sum(),
+        };
     }
 
 
@@ -54,43 +80,50 @@ impl Rasterizer {
 
 
     // Pass 1: Prepare all visible triangles for rasterization
-    fn geometry_setup_pass(&mut self, scene: &Scene, render_target: &mut RenderTarget) -> Vec<RasterTriangle> {
+    pub(crate) fn geometry_setup_pass(&mut self, scene: &Scene, render_target: &mut RenderTarget) -> Vec<RasterTriangle> {
         
         let mut raster_triangles: Vec<RasterTriangle> = Vec::new();
         let mut clipped_poly_buffer: Vec<Vertex> = Vec::with_capacity(10); // Capacity can be tuned
 
         for (model_index, model) in scene.models.iter().enumerate() {
-            for i in (0..model.vertices.len()).step_by(3) {
-                let v0 = &model.vertices[i];
-                let v1 = &model.vertices[i + 1];
-                let v2 = &model.vertices[i + 2];
-
-                clipped_poly_buffer.clear();
-
-                self.frustum_cull(&scene.camera, model, render_target, v0, v1, v2, &mut clipped_poly_buffer);
-
-                if !clipped_poly_buffer.is_empty() {
-
-                    self.final_triangles.clear();
-
-                    triangulate_convex_polygon(&clipped_poly_buffer, &mut self.final_triangles);
-
-                    for triangle in &self.final_triangles {
-                        let tri_data: Option<RasterTriangle> = process_screen_tri(
-                            &triangle[0], 
-                            &triangle[1], 
-                            &triangle[2], 
-                            render_target, 
-                            &scene.camera, 
-                            model_index as u32
-                        );
-                        if let Some(val) = tri_data {
-                            raster_triangles.push(val);
+            let joint_matrices = model.skeleton.as_ref().map(Skeleton::joint_matrices).unwrap_or_default();
+            // blend shapes apply first, skinning then moves the morphed mesh
+            let morphed = morph_vertices(&model.vertices, &model.morph_targets, &model.morph_weights);
+            let vertices = morphed.as_deref().unwrap_or(&model.vertices);
+            for (shader_index, vertex_range) in model.draw_ranges() {
+                for i in vertex_range.step_by(3) {
+                    let v0 = &vertices[i];
+                    let v1 = &vertices[i + 1];
+                    let v2 = &vertices[i + 2];
+
+                    clipped_poly_buffer.clear();
+
+                    self.frustum_cull(&scene.camera, &scene.settings, model, render_target, v0, v1, v2, &joint_matrices, &mut clipped_poly_buffer);
+
+                    if !clipped_poly_buffer.is_empty() {
+
+                        self.final_triangles.clear();
+
+                        triangulate_convex_polygon(&clipped_poly_buffer, &mut self.final_triangles);
+
+                        for triangle in &self.final_triangles {
+                            let tri_data: Option<RasterTriangle> = process_screen_tri(
+                                &triangle[0], 
+                                &triangle[1], 
+                                &triangle[2], 
+                                render_target, 
+                                &scene.camera, 
+                                model_index as u32,
+                                shader_index,
+                            );
+                            if let Some(val) = tri_data {
+                                raster_triangles.push(val);
+                            }
+                            // else backface culled, discard
                         }
-                        // else backface culled, discard
                     }
-                }
                 
+                }
             }
         }
 
@@ -100,37 +133,35 @@ impl Rasterizer {
     fn frustum_cull(
         &mut self, 
         camera: &Camera, 
+        settings: &RenderSettings,
         model: &Model, 
         render_target: 
         &RenderTarget, 
         v0: &Vertex, 
         v1: &Vertex, 
         v2: &Vertex,
+        // from the model's skeleton, empty for models without one
+        joint_matrices: &[Mat4],
         output_polygon: &mut Vec<Vertex>,
     ) {
 
         let aspect = render_target.width as f32 / render_target.height as f32;
-        let near_clip = 0.001;
-        let far_clip = 40.0;
+        let near_clip = settings.near_clip;
+        let far_clip = settings.far_clip;
         let frustum_planes = build_frustum_planes(camera.fov, aspect, near_clip, far_clip);
 
 
         self.poly_buffer1.clear();
-        self.poly_buffer1.push(Vertex {
-            position: camera.transform.to_local_point(model.transform.to_world_point(v0.position)),
-            texcoord: v0.texcoord,
-            normal: camera.transform.to_local_vector(model.transform.to_world_vector(v0.normal)),
-        });
-        self.poly_buffer1.push(Vertex {
-            position: camera.transform.to_local_point(model.transform.to_world_point(v1.position)),
-            texcoord: v1.texcoord,
-            normal: camera.transform.to_local_vector(model.transform.to_world_vector(v1.normal)),
-        });
-        self.poly_buffer1.push(Vertex {
-            position: camera.transform.to_local_point(model.transform.to_world_point(v2.position)),
-            texcoord: v2.texcoord,
-            normal: camera.transform.to_local_vector(model.transform.to_world_vector(v2.normal)),
-        });
+        for vertex in [v0, v1, v2] {
+            // skinning moves vertices within the model, before the model and view transforms
+            let vertex = if joint_matrices.is_empty() { *vertex } else { skin_vertex(vertex, joint_matrices) };
+            self.poly_buffer1.push(Vertex {
+                position: camera.transform.to_local_point(model.transform.to_world_point(vertex.position)),
+                normal: camera.transform.to_local_vector(model.transform.to_world_normal(vertex.normal)),
+                tangent: camera.transform.to_local_vector(model.transform.to_world_vector(vertex.tangent)),
+                ..vertex
+            });
+        }
 
         let mut input_poly = &mut self.poly_buffer1;
         let mut output_poly = &mut self.poly_buffer2;
@@ -152,12 +183,11 @@ impl Rasterizer {
 
 
 
-    fn depth_pass(&self, fb: &mut RenderTarget, triangles: &[RasterTriangle]) {
-        let mut tri_count: i32 = 0;
+    // alpha tested shaders run here too, cut out fragments mustn't hide what's behind them
+    pub(crate) fn depth_pass(&self, scene: &Scene, fb: &mut RenderTarget, triangles: &[RasterTriangle]) {
         for tri in triangles {
-
-
-            let mut tri_active = false;
+            let shader = scene.models[tri.model_index as usize].shader(tri.shader_index);
+            let alpha_cutoff = shader.alpha_cutoff();
 
 
             // optimize for barycentric coordinate calculation
@@ -208,14 +238,16 @@ impl Rasterizer {
                         let new_depth_mask = mask & depth.simd_gt(simd_current_depth);
 
                         if new_depth_mask.any() {
-                            tri_active = true;
-                            
+                            let lanes = alpha_cutoff.map(|_| FragmentLanes::new(tri, simd_w0, simd_w1, simd_w2, depth));
                             for i in 0..8 {
                                 if new_depth_mask.test(i) {
                                     let current_x = x + i as u32;
                                     if current_x <= tri.max_x {
                                         let index = index_start + i;
 
+                                        if let Some(cutoff) = alpha_cutoff && let Some(lanes) = &lanes && alpha(shader.pixel_color(&lanes.fragment(i))) < cutoff {
+                                            continue;
+                                        }
                                         fb.depth_buffer[index] = depth[i];
 
                                     }
@@ -229,28 +261,23 @@ impl Rasterizer {
                     simd_w2 += step_w2;
                 }
             }
-
-            if tri_active == true {
-                tri_count += 1;
-            }
         }
-        println!("total_tris: {tri_count}")
-
     }
 
 
-    fn color_pass(&self, scene: &Scene, fb: &mut RenderTarget, triangles: &[RasterTriangle]) {
+    // returns how many triangles covered at least one pixel
+    pub(crate) fn color_pass(&self, scene: &Scene, fb: &mut RenderTarget, triangles: &[RasterTriangle]) -> u32 {
 
-        let mut rng = Random::new(654);
         let simd_one = f32x8::splat(1.0);
 
-        let mut tri_count: i32 = 0;
+        let mut tri_count: u32 = 0;
         for tri in triangles {
 
-            let color = rng.random_argb();
             let mut tri_active = false;
 
             let model = &scene.models[tri.model_index as usize]; // Get the corresponding model
+            let shader = model.shader(tri.shader_index);
+            let alpha_cutoff = shader.alpha_cutoff();
 
             let step_w0 = tri.simd_dy0 * f32x8::splat(8.0);
             let step_w1 = tri.simd_dy1 * f32x8::splat(8.0);
@@ -285,19 +312,7 @@ impl Rasterizer {
                         if color_mask.any() {
 
                             tri_active = true;
-                            let u_over_z = (simd_w0 * tri.simd_u1_over_z + simd_w1 * tri.simd_u2_over_z + simd_w2 * tri.simd_u3_over_z) * tri.simd_inv_area;
-                            let v_over_z = (simd_w0 * tri.simd_v1_over_z + simd_w1 * tri.simd_v2_over_z + simd_w2 * tri.simd_v3_over_z) * tri.simd_inv_area;
-
-                            let nx_over_z = (simd_w0 * tri.simd_nx1_over_z + simd_w1 * tri.simd_nx2_over_z + simd_w2 * tri.simd_nx3_over_z) * tri.simd_inv_area;
-                            let ny_over_z = (simd_w0 * tri.simd_ny1_over_z + simd_w1 * tri.simd_ny2_over_z + simd_w2 * tri.simd_ny3_over_z) * tri.simd_inv_area;
-                            let nz_over_z = (simd_w0 * tri.simd_nz1_over_z + simd_w1 * tri.simd_nz2_over_z + simd_w2 * tri.simd_nz3_over_z) * tri.simd_inv_area;
-
-                            let tex_u = u_over_z * depth;
-                            let tex_v = v_over_z * depth;
-
-                            let normal_x = nx_over_z * depth;
-                            let normal_y = ny_over_z * depth;
-                            let normal_z = nz_over_z * depth;
+                            let lanes = FragmentLanes::new(tri, simd_w0, simd_w1, simd_w2, depth);
 
                             for i in 0..8 {
                                 if color_mask.test(i) {
@@ -305,15 +320,10 @@ impl Rasterizer {
                                     if current_x <= tri.max_x {
                                         let index = index_start + i;
 
-                                       
-
-                                        // --- DEBUG: visualize normals as colors ---
-                                        let normal = vec3!(normal_x[i], normal_y[i], normal_z[i]).normalize();
-                                        let r = ((normal.x * 0.5 + 0.5) * 255.0).round() as u32;
-                                        let g = ((normal.y * 0.5 + 0.5) * 255.0).round() as u32;
-                                        let b = ((normal.z * 0.5 + 0.5) * 255.0).round() as u32;
-                                        let normal_color = (0xFF << 24) | (r << 16) | (g << 8) | b;
-
+                                        let color = shader.pixel_color(&lanes.fragment(i));
+                                        if let Some(cutoff) = alpha_cutoff && alpha(color) < cutoff {
+                                            continue;
+                                        }
                                         fb.color_buffer[index] = color;
                                     }
                                 }
@@ -325,11 +335,49 @@ impl Rasterizer {
                     simd_w2 += step_w2;
                 }
             }
-            if tri_active == true {
+            if tri_active {
                 tri_count += 1;
             }
         }
-        println!("color_tris: {tri_count}")
+        return tri_count;
+    }
+
+
+    // points are depth tested against the finished triangles and write depth themselves
+    fn point_pass(&self, scene: &Scene, fb: &mut RenderTarget) {
+        let camera = &scene.camera;
+        let near_clip = scene.settings.near_clip;
+        let far_clip = scene.settings.far_clip;
+
+        for cloud in &scene.point_clouds {
+            let half_size = (cloud.point_size * 0.5).max(0.5);
+
+            for point in &cloud.points {
+                let view_position = camera.transform.to_local_point(cloud.transform.to_world_point(point.position));
+                if view_position.z > -near_clip || view_position.z < -far_clip {
+                    continue;
+                }
+
+                let screen = vertex_to_screen(view_position, fb, camera);
+                let min_x = (screen.x - half_size).round().max(0.0) as i64;
+                let max_x = ((screen.x + half_size).round() as i64).min(fb.width as i64);
+                let min_y = (screen.y - half_size).round().max(0.0) as i64;
+                let max_y = ((screen.y + half_size).round() as i64).min(fb.height as i64);
+
+                let to_byte = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u32;
+                let color = 0xFF000000 | (to_byte(point.color.x) << 16) | (to_byte(point.color.y) << 8) | to_byte(point.color.z);
+
+                for y in min_y..max_y {
+                    for x in min_x..max_x {
+                        let index = (y as u32 * fb.width + x as u32) as usize;
+                        if view_position.z > fb.depth_buffer[index] {
+                            fb.depth_buffer[index] = view_position.z;
+                            fb.color_buffer[index] = color;
+                        }
+                    }
+                }
+            }
+        }
     }
 
 }
@@ -346,6 +394,107 @@ fn edge_simd(ax: f32x8, ay: f32x8, bx: f32x8, by: f32x8, cx: f32x8, cy: f32x8) -
 }
 
 
+
+// perspective correct fragment inputs for 8 pixels, pulled out lane by lane
+struct FragmentLanes {
+    tex_u: f32x8,
+    tex_v: f32x8,
+    normal_x: f32x8,
+    normal_y: f32x8,
+    normal_z: f32x8,
+    tangent_x: f32x8,
+    tangent_y: f32x8,
+    tangent_z: f32x8,
+    color_r: f32x8,
+    color_g: f32x8,
+    color_b: f32x8,
+    tex_u_dx: f32x8,
+    tex_u_dy: f32x8,
+    tex_v_dx: f32x8,
+    tex_v_dy: f32x8,
+    bitangent_sign: f32,
+}
+
+impl FragmentLanes {
+    #[inline]
+    fn new(tri: &RasterTriangle, simd_w0: f32x8, simd_w1: f32x8, simd_w2: f32x8, depth: f32x8) -> Self {
+        let u_over_z = (simd_w0 * tri.simd_u1_over_z + simd_w1 * tri.simd_u2_over_z + simd_w2 * tri.simd_u3_over_z) * tri.simd_inv_area;
+        let v_over_z = (simd_w0 * tri.simd_v1_over_z + simd_w1 * tri.simd_v2_over_z + simd_w2 * tri.simd_v3_over_z) * tri.simd_inv_area;
+
+        let nx_over_z = (simd_w0 * tri.simd_nx1_over_z + simd_w1 * tri.simd_nx2_over_z + simd_w2 * tri.simd_nx3_over_z) * tri.simd_inv_area;
+        let ny_over_z = (simd_w0 * tri.simd_ny1_over_z + simd_w1 * tri.simd_ny2_over_z + simd_w2 * tri.simd_ny3_over_z) * tri.simd_inv_area;
+        let nz_over_z = (simd_w0 * tri.simd_nz1_over_z + simd_w1 * tri.simd_nz2_over_z + simd_w2 * tri.simd_nz3_over_z) * tri.simd_inv_area;
+
+        let tx_over_z = (simd_w0 * tri.simd_tx1_over_z + simd_w1 * tri.simd_tx2_over_z + simd_w2 * tri.simd_tx3_over_z) * tri.simd_inv_area;
+        let ty_over_z = (simd_w0 * tri.simd_ty1_over_z + simd_w1 * tri.simd_ty2_over_z + simd_w2 * tri.simd_ty3_over_z) * tri.simd_inv_area;
+        let tz_over_z = (simd_w0 * tri.simd_tz1_over_z + simd_w1 * tri.simd_tz2_over_z + simd_w2 * tri.simd_tz3_over_z) * tri.simd_inv_area;
+
+        let r_over_z = (simd_w0 * tri.simd_r1_over_z + simd_w1 * tri.simd_r2_over_z + simd_w2 * tri.simd_r3_over_z) * tri.simd_inv_area;
+        let g_over_z = (simd_w0 * tri.simd_g1_over_z + simd_w1 * tri.simd_g2_over_z + simd_w2 * tri.simd_g3_over_z) * tri.simd_inv_area;
+        let b_over_z = (simd_w0 * tri.simd_b1_over_z + simd_w1 * tri.simd_b2_over_z + simd_w2 * tri.simd_b3_over_z) * tri.simd_inv_area;
+
+        let tex_u = u_over_z * depth;
+        let tex_v = v_over_z * depth;
+
+        let normal_x = nx_over_z * depth;
+        let normal_y = ny_over_z * depth;
+        let normal_z = nz_over_z * depth;
+
+        let tangent_x = tx_over_z * depth;
+        let tangent_y = ty_over_z * depth;
+        let tangent_z = tz_over_z * depth;
+
+        let color_r = r_over_z * depth;
+        let color_g = g_over_z * depth;
+        let color_b = b_over_z * depth;
+
+        // quotient rule on (u/z) / (1/z)
+        let tex_u_dx = (tri.simd_u_over_z_dx - tex_u * tri.simd_inv_z_dx) * depth;
+        let tex_u_dy = (tri.simd_u_over_z_dy - tex_u * tri.simd_inv_z_dy) * depth;
+        let tex_v_dx = (tri.simd_v_over_z_dx - tex_v * tri.simd_inv_z_dx) * depth;
+        let tex_v_dy = (tri.simd_v_over_z_dy - tex_v * tri.simd_inv_z_dy) * depth;
+
+        return FragmentLanes {
+            tex_u,
+            tex_v,
+            normal_x,
+            normal_y,
+            normal_z,
+            tangent_x,
+            tangent_y,
+            tangent_z,
+            color_r,
+            color_g,
+            color_b,
+            tex_u_dx,
+            tex_u_dy,
+            tex_v_dx,
+            tex_v_dy,
+            bitangent_sign: tri.bitangent_sign,
+        };
+    }
+
+
+    #[inline]
+    fn fragment(&self, i: usize) -> Fragment {
+        return Fragment {
+            texcoord: vec2!(self.tex_u[i], self.tex_v[i]),
+            normal: vec3!(self.normal_x[i], self.normal_y[i], self.normal_z[i]).normalize(),
+            texcoord_dx: vec2!(self.tex_u_dx[i], self.tex_v_dx[i]),
+            texcoord_dy: vec2!(self.tex_u_dy[i], self.tex_v_dy[i]),
+            tangent: vec3!(self.tangent_x[i], self.tangent_y[i], self.tangent_z[i]).normalize(),
+            bitangent_sign: self.bitangent_sign,
+            color: vec3!(self.color_r[i], self.color_g[i], self.color_b[i]),
+        };
+    }
+}
+
+
+fn alpha(color: u32) -> f32 {
+    return (color >> 24) as f32 / 255.0;
+}
+
+
 #[derive(Debug, Clone, Copy)]
 struct Plane {
     normal: Vec3,
@@ -375,6 +524,7 @@ fn process_screen_tri(
     fb: &mut RenderTarget,
     cam: &Camera,
     model_index:u32,
+    shader_index: u32,
     // self might be needed if vertex_to_screen or edge are methods on it
 ) -> Option<RasterTriangle> {
     // project vertices to screen space
@@ -420,6 +570,24 @@ fn process_screen_tri(
     let n2_over_z = v2.normal * inv_z2;
     let n3_over_z = v3.normal * inv_z3;
 
+    let t1_over_z = v1.tangent * inv_z1;
+    let t2_over_z = v2.tangent * inv_z2;
+    let t3_over_z = v3.tangent * inv_z3;
+
+    let c1_over_z = v1.color * inv_z1;
+    let c2_over_z = v2.color * inv_z2;
+    let c3_over_z = v3.color * inv_z3;
+
+    // attributes are affine in screen space, so their gradients are constant per triangle
+    let gradient = |a1: f32, a2: f32, a3: f32| -> (f32, f32) {
+        let dx = ((v3_2d.y - v2_2d.y) * a1 + (v1_2d.y - v3_2d.y) * a2 + (v2_2d.y - v1_2d.y) * a3) * inv_area;
+        let dy = ((v2_2d.x - v3_2d.x) * a1 + (v3_2d.x - v1_2d.x) * a2 + (v1_2d.x - v2_2d.x) * a3) * inv_area;
+        return (dx, dy);
+    };
+    let (inv_z_dx, inv_z_dy) = gradient(inv_z1, inv_z2, inv_z3);
+    let (u_over_z_dx, u_over_z_dy) = gradient(uv1_over_z.x, uv2_over_z.x, uv3_over_z.x);
+    let (v_over_z_dx, v_over_z_dy) = gradient(uv1_over_z.y, uv2_over_z.y, uv3_over_z.y);
+
     let simd_x = f32x8::from_array([
         min_x as f32 + 0.5, min_x as f32 + 1.5, min_x as f32 + 2.5, min_x as f32 + 3.5,
         min_x as f32 + 4.5, min_x as f32 + 5.5, min_x as f32 + 6.5, min_x as f32 + 7.5
@@ -427,9 +595,9 @@ fn process_screen_tri(
 
     // 5. Pre-splat all values for SIMD
     Some(RasterTriangle {
-        min_x: min_x as u32,
+        min_x,
         max_x: max_x as u32,
-        min_y: min_y as u32,
+        min_y,
         max_y: max_y as u32,
         v1_2d,
         v2_2d,
@@ -458,7 +626,33 @@ fn process_screen_tri(
         simd_nx3_over_z: f32x8::splat(n3_over_z.x),
         simd_ny3_over_z: f32x8::splat(n3_over_z.y),
         simd_nz3_over_z: f32x8::splat(n3_over_z.z),
-        model_index
+        simd_tx1_over_z: f32x8::splat(t1_over_z.x),
+        simd_ty1_over_z: f32x8::splat(t1_over_z.y),
+        simd_tz1_over_z: f32x8::splat(t1_over_z.z),
+        simd_tx2_over_z: f32x8::splat(t2_over_z.x),
+        simd_ty2_over_z: f32x8::splat(t2_over_z.y),
+        simd_tz2_over_z: f32x8::splat(t2_over_z.z),
+        simd_tx3_over_z: f32x8::splat(t3_over_z.x),
+        simd_ty3_over_z: f32x8::splat(t3_over_z.y),
+        simd_tz3_over_z: f32x8::splat(t3_over_z.z),
+        bitangent_sign: v1.bitangent_sign,
+        simd_r1_over_z: f32x8::splat(c1_over_z.x),
+        simd_g1_over_z: f32x8::splat(c1_over_z.y),
+        simd_b1_over_z: f32x8::splat(c1_over_z.z),
+        simd_r2_over_z: f32x8::splat(c2_over_z.x),
+        simd_g2_over_z: f32x8::splat(c2_over_z.y),
+        simd_b2_over_z: f32x8::splat(c2_over_z.z),
+        simd_r3_over_z: f32x8::splat(c3_over_z.x),
+        simd_g3_over_z: f32x8::splat(c3_over_z.y),
+        simd_b3_over_z: f32x8::splat(c3_over_z.z),
+        simd_inv_z_dx: f32x8::splat(inv_z_dx),
+        simd_inv_z_dy: f32x8::splat(inv_z_dy),
+        simd_u_over_z_dx: f32x8::splat(u_over_z_dx),
+        simd_u_over_z_dy: f32x8::splat(u_over_z_dy),
+        simd_v_over_z_dx: f32x8::splat(v_over_z_dx),
+        simd_v_over_z_dy: f32x8::splat(v_over_z_dy),
+        model_index,
+        shader_index,
     })
 }
 
@@ -510,10 +704,40 @@ pub struct RasterTriangle {
     pub simd_ny3_over_z: f32x8,
     pub simd_nz3_over_z: f32x8,
 
+    pub simd_tx1_over_z: f32x8,
+    pub simd_ty1_over_z: f32x8,
+    pub simd_tz1_over_z: f32x8,
+    pub simd_tx2_over_z: f32x8,
+    pub simd_ty2_over_z: f32x8,
+    pub simd_tz2_over_z: f32x8,
+    pub simd_tx3_over_z: f32x8,
+    pub simd_ty3_over_z: f32x8,
+    pub simd_tz3_over_z: f32x8,
+    pub bitangent_sign: f32,
+
+    pub simd_r1_over_z: f32x8,
+    pub simd_g1_over_z: f32x8,
+    pub simd_b1_over_z: f32x8,
+    pub simd_r2_over_z: f32x8,
+    pub simd_g2_over_z: f32x8,
+    pub simd_b2_over_z: f32x8,
+    pub simd_r3_over_z: f32x8,
+    pub simd_g3_over_z: f32x8,
+    pub simd_b3_over_z: f32x8,
+
+    // Pre-splatted screen space gradients of 1/z, u/z and v/z, for texcoord derivatives
+    pub simd_inv_z_dx: f32x8,
+    pub simd_inv_z_dy: f32x8,
+    pub simd_u_over_z_dx: f32x8,
+    pub simd_u_over_z_dy: f32x8,
+    pub simd_v_over_z_dx: f32x8,
+    pub simd_v_over_z_dy: f32x8,
+
     // Optional: Triangle ID for debugging or advanced techniques
     // pub triangle_id: u32,
     pub model_index: u32,
-    // You could add material_index, etc., as needed
+    // submesh shader within the model, or MODEL_SHADER
+    pub shader_index: u32,
 }
 
 
@@ -570,6 +794,13 @@ fn clip_polygon_against_plane(input_poly: &[Vertex], output_poly: &mut Vec<Verte
                 position: prev_v.position + (curr_v.position - prev_v.position) * t,
                 texcoord: prev_v.texcoord + (curr_v.texcoord - prev_v.texcoord) * t,
                 normal: prev_v.normal + (curr_v.normal - prev_v.normal) * t,
+                tangent: prev_v.tangent + (curr_v.tangent - prev_v.tangent) * t,
+                // constant across a triangle, see `generate_tangents`
+                bitangent_sign: prev_v.bitangent_sign,
+                color: prev_v.color + (curr_v.color - prev_v.color) * t,
+                // clipping happens in view space, after skinning
+                joints: [0; 4],
+                weights: [0.0; 4],
             };
             output_poly.push(intersection);
         }
@@ -585,7 +816,7 @@ fn clip_polygon_against_plane(input_poly: &[Vertex], output_poly: &mut Vec<Verte
 
 
 
-fn triangulate_convex_polygon(polygon: &Vec<Vertex>, triangles: &mut Vec<[Vertex; 3]>) {
+fn triangulate_convex_polygon(polygon: &[Vertex], triangles: &mut Vec<[Vertex; 3]>) {
     if polygon.len() < 3 {
         return;
     }
@@ -19,6 +19,16 @@ impl RenderTarget {
     }
 
 
+    /// reallocates both buffers, their contents are cleared
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        self.size = (width * height) as usize;
+        self.color_buffer = vec![0; self.size];
+        self.depth_buffer = vec![f32::NEG_INFINITY; self.size];
+    }
+
+
     pub fn set_pixel(&mut self, x: u32, y: u32, color: u32) {
         let index = (y * self.width + x) as usize;
         self.color_buffer[index] = color;
@@ -1,18 +1,65 @@
-use crate::config::MOUSE_SENSITIVITY;
-use crate::config::WIDTH;
+use crate::animation::{AnimationPlayer, AnimationTargets};
+use crate::config::{DEFAULT_HEIGHT, DEFAULT_WIDTH};
+use crate::graphics::camera_controller::{CameraController, CameraPath, ControllerSettings, FlyController};
 use crate::utils::view::View;
-use winit::event::MouseButton;
-use winit::keyboard::KeyCode;
 use crate::Model;
+use crate::PointCloud;
 use crate::Camera;
-use crate::CAMERA_SPEED;
-use crate::{vec2, vec3, Vec3};
+use crate::Light;
+use crate::SceneSource;
+use crate::shader_lighting;
+use crate::{vec2, vec3, MipFilter, Vec3};
 
 
 
 pub struct Scene {
     pub camera: Camera,
+    // other viewpoints, e.g. extra cameras from a scene file
+    pub cameras: Vec<Camera>,
     pub models: Vec<Model>,
+    pub point_clouds: Vec<PointCloud>,
+    pub lights: Vec<Light>,
+    pub settings: RenderSettings,
+    // files the models and point clouds came from, used when saving the scene
+    pub sources: Vec<SceneSource>,
+    // moves `camera` from input, replace it with `set_controller`
+    pub controller: Box<dyn CameraController>,
+    // waypoints the scripted controller plays, from the scene file
+    pub camera_path: Option<CameraPath>,
+    // clips that move the models, camera and lights, advanced by `update`
+    pub animation: AnimationPlayer,
+}
+
+
+
+pub struct RenderSettings {
+    pub width: u32,
+    pub height: u32,
+    pub background: Vec3, // linear rgb
+    pub near_clip: f32,
+    pub far_clip: f32,
+    pub mip_filter: MipFilter,
+}
+
+
+
+impl RenderSettings {
+    pub fn new() -> RenderSettings {
+        return RenderSettings {
+            width: DEFAULT_WIDTH,
+            height: DEFAULT_HEIGHT,
+            background: vec3!(0, 0, 0),
+            near_clip: 0.001,
+            far_clip: 40.0,
+            mip_filter: MipFilter::Box,
+        };
+    }
+
+
+    pub fn background_argb(&self) -> u32 {
+        let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u32;
+        return 0xFF000000 | channel(self.background.x) << 16 | channel(self.background.y) << 8 | channel(self.background.z);
+    }
 }
 
 
@@ -21,7 +68,15 @@ impl Scene {
     pub fn new() -> Scene {
         return Scene { 
             camera: Camera::new(90.0),
+            cameras: Vec::new(),
             models: Vec::new(),
+            point_clouds: Vec::new(),
+            lights: Vec::new(),
+            settings: RenderSettings::new(),
+            sources: Vec::new(),
+            controller: Box::new(FlyController::new(ControllerSettings::new())),
+            camera_path: None,
+            animation: AnimationPlayer::new(),
         }
     }
 
@@ -31,42 +86,80 @@ impl Scene {
     }
 
 
-    pub fn update(&mut self, delta_time: f32, view: &mut View){
+    pub fn load_point_cloud(&mut self, point_cloud: PointCloud){
+        self.point_clouds.push(point_cloud);
+    }
+
 
+    /// returns whether anything moved, on-demand rendering only needs another frame if so
+    pub fn update(&mut self, delta_time: f32, view: &mut View) -> bool {
 
-        self.handle_input(view, delta_time);
 
-        // 10 deg per sec
-        self.models[0].transform.yaw += 10.0 * delta_time;
-        self.models[0].transform.pitch += 10.0 * delta_time;
-        // model.transform.position.x += 1.0 * delta_time;
+        let mut changed = self.handle_input(view, delta_time);
+        changed |= self.animate(delta_time);
+        return changed;
     }
 
 
-    fn handle_input(&mut self, view: &mut View, delta_time: f32){
-        if view.mouse_pressed(MouseButton::Left) { view.set_mouse_grab(true);}
-        if view.key_held(KeyCode::Escape) { view.set_mouse_grab(false);}
-    
-        if view.is_focused {
-            let mouse_delta = (view.mouse_delta() / WIDTH as f32) * MOUSE_SENSITIVITY;
-            let pitch = (self.camera.transform.pitch + mouse_delta.y)
-                .clamp(-85.0, 85.0);
-            let yaw = self.camera.transform.yaw - mouse_delta.x;
-            self.camera.transform.set_rotation(pitch, yaw);
-        }   
-    
-        let mut camera_delta = vec3!(0,0,0);
-        let (cam_right, cam_up, cam_fwd) = self.camera.transform.basis_vectors;
-    
-        if view.key_held(KeyCode::KeyW) {camera_delta -= cam_fwd}
-        if view.key_held(KeyCode::KeyA) {camera_delta -= cam_right}
-        if view.key_held(KeyCode::KeyS) {camera_delta += cam_fwd}
-        if view.key_held(KeyCode::KeyD) {camera_delta+= cam_right}
-
-        let mut sprint = 1.0;
-        if view.key_held(KeyCode::ShiftLeft) {sprint *= 2.8}
-    
-        self.camera.transform.position += camera_delta.normalize() * CAMERA_SPEED * delta_time * sprint;
+    /// advances the animation clips, returns whether they moved anything
+    pub fn animate(&mut self, delta_time: f32) -> bool {
+        // checked before too, stopping puts lights back without animating them anymore
+        let lights_animated = self.animation.animates_lights();
+        let mut targets = AnimationTargets { models: &mut self.models, camera: &mut self.camera, lights: &mut self.lights };
+        let animated = self.animation.update(delta_time, &mut targets);
+        if animated && (lights_animated || self.animation.animates_lights()) {
+            self.apply_lighting();
+        }
+        return animated;
+    }
+
+
+    /// hands the lights to every shader again, after they changed
+    pub fn apply_lighting(&mut self) {
+        let (light_direction, ambient) = shader_lighting(&self.lights);
+        for model in &mut self.models {
+            model.shader.set_lighting(light_direction, ambient);
+            for submesh in &mut model.submeshes {
+                submesh.shader.set_lighting(light_direction, ambient);
+            }
+        }
+    }
+
+
+    /// rebuilds every texture's mips with the filter from the settings
+    pub fn apply_mip_filter(&mut self) {
+        let filter = self.settings.mip_filter;
+        for model in &mut self.models {
+            model.shader.set_mip_filter(filter);
+            for submesh in &mut model.submeshes {
+                submesh.shader.set_mip_filter(filter);
+            }
+        }
+    }
+
+
+    fn handle_input(&mut self, view: &mut View, delta_time: f32) -> bool {
+        return self.controller.update(&mut self.camera, view, delta_time);
+    }
+
+
+    /// hands the camera to `controller`, which picks up from wherever the camera is now
+    pub fn set_controller(&mut self, mut controller: Box<dyn CameraController>) {
+        controller.attach(&self.camera, self.focus_point());
+        self.controller = controller;
+    }
+
+
+    /// the average position of the models and point clouds, or a little in front of the camera in an empty scene
+    pub fn focus_point(&self) -> Vec3 {
+        let positions: Vec<Vec3> = self.models.iter().map(|model| model.transform.position)
+            .chain(self.point_clouds.iter().map(|cloud| cloud.transform.position))
+            .collect();
+        if positions.is_empty() {
+            return self.camera.transform.position + self.camera.transform.forward() * 5.0;
+        }
+        let sum = positions.iter().fold(vec3!(0, 0, 0), |sum, &position| sum + position);
+        return sum / positions.len() as f32;
     }
 }
 
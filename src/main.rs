@@ -2,69 +2,154 @@
 #![allow(unused_imports)]
 #![allow(unused_mut)]
 #![allow(unused_variables)]
+#![allow(clippy::needless_return)]
+#![allow(clippy::too_many_arguments)]
 #![feature(portable_simd)]
 
 use std::io::{Result};
 use std::time::{Duration, Instant};
 
+use animation::*;
+use benchmark::*;
 use config::*;
 use graphics::render_target::*;
 use graphics::rasterizer::*;
 use graphics::camera::*;
+use graphics::camera_controller::*;
+use graphics::hud::*;
 use graphics::scene::*;
+use types::light::*;
+use types::material::*;
 use types::model::*;
+use types::morph::*;
+use types::normals::*;
+use types::point_cloud::*;
 use types::transform::*;
 use types::texture::*;
 use types::shader::*;
+use types::tangents::*;
 use types::vec2::*;
 use types::vec3::*;
 use types::vertex::*;
 use utils::file_parser::*;
+use utils::frame_pacing::*;
+use utils::json::*;
+use utils::low_discrepancy::*;
 use utils::random::*;
 use utils::view::*;
 
+mod animation;
+mod benchmark;
 mod graphics;
 mod types;
 mod utils;
 mod config;
+#[cfg(test)]
+mod tests;
 
 
 
 
 fn main() -> Result<()> {
 
-    let mut cube = parse_obj("./models/monkey.obj");
-    // let cube_texture = read_bitmap("./models/uv_grid.001.bmp").expect("Failed to read texture bitmap");
-    // cube.shader = Box::new(TextureShader::new(cube_texture));
-    // let mut monkey = parse_obj("./models/monkey.obj");
-    
-    let mut scene = Scene::new();
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(ConfigError::HelpRequested) => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Err(err) => return Err(err.into()),
+    };
+    if config.benchmark {
+        return run_benchmarks(&config.benchmark_options());
+    }
+
+    let scene_file = parse_scene(&config.scene_path)?;
+    for warning in &scene_file.warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    let mut scene = scene_file.scene;
+    config.apply(&mut scene);
     let mut rasterizer = Rasterizer::new();
 
-    let mut render_target: RenderTarget = RenderTarget::new(WIDTH, HEIGHT);
-    let mut view = View::new(1920, 1080); // window size, not render res
+    let mut render_target: RenderTarget = RenderTarget::new(scene.settings.width, scene.settings.height);
+    let mut view = View::new(config.window_width, config.window_height); // window size, not render res
+    view.pacer = FramePacer::new(config.frame_pacing, config.target_fps);
+    view.present_mode = config.present_mode;
+    view.scale_filter = config.scale_filter;
+    view.follow_window = config.follow_window;
+    view.render_scale = config.render_scale;
+    view.input_map = config.input_map.clone();
+    view.capture.directory = config.capture_directory.clone().into();
+    view.capture.format = config.capture_format;
+    view.capture.fps = config.capture_fps;
+
+    let mut hud = Hud::new();
+    hud.visible = config.hud;
+    let mut controller_kind = config.camera_controller;
+    let controller_settings = config.controller_settings();
 
     let mut last_time = Instant::now();
-
-    cube.transform.position.z = -5.0;
-    // monkey.transform.position.z = -5.0;
-    scene.load_model(cube);
-    // scene.load_model(monkey);
-    scene.camera.fov = 70.0;
+    let mut last_report = Instant::now();
     
 
     view.run(move |view| {
 
         let frame_start = Instant::now();
-        let delta_time = (frame_start - last_time).as_secs_f32();
+        // after waiting for input the time since the last frame was spent idle, nothing should catch up on it
+        if view.pacer.woke_from_idle() {
+            last_time = frame_start;
+        }
+        // recordings step time by a fixed amount per frame so they play back at true speed however slow rendering is
+        let delta_time = view.capture.frame_delta().unwrap_or((frame_start - last_time).as_secs_f32());
         last_time = frame_start;
         
-        scene.update(delta_time, view);
+        if scene.update(delta_time, view) {
+            view.request_frame();
+        }
+        if view.action_pressed("toggle_hud") {
+            hud.toggle();
+        }
+        if view.action_pressed("next_camera") {
+            controller_kind = controller_kind.next(scene.camera_path.is_some());
+            scene.set_controller(controller_kind.create(&controller_settings, scene.camera_path.as_ref()));
+            view.request_frame();
+        }
+        if view.action_pressed("next_animation") {
+            scene.animation.play_next(CROSS_FADE_TIME);
+            view.request_frame();
+        }
+
+        view.sync_render_target(&mut render_target);
         rasterizer.render(&mut render_target, &scene);
+        view.capture_frame(&render_target);
+
+        let pacing = match view.pacer.mode {
+            FramePacing::Fixed => format!("fixed {} fps", view.pacer.target_fps),
+            mode => mode.name().to_string(),
+        };
+        let render_mode = format!(
+            "{}x{} {} {}, {}",
+            render_target.width,
+            render_target.height,
+            view.present_mode.name(),
+            view.scale_filter.name(),
+            pacing,
+        );
+        hud.draw(&mut render_target, &scene, &view.pacer.stats, &rasterizer.stats, &render_mode);
         view.draw(&render_target);
 
-        let elapsed = frame_start.elapsed();
-        println!("Frame time: {} ms", elapsed.as_millis());
+        if last_report.elapsed() >= Duration::from_secs(1) {
+            let stats = &view.pacer.stats;
+            println!(
+                "{:.1} fps, frame time {:.2} ms avg, {:.2} ms 99th percentile, {:.2} ms max",
+                stats.fps(),
+                stats.average_frame_time().as_secs_f64() * 1000.0,
+                stats.percentile_frame_time(0.99).as_secs_f64() * 1000.0,
+                stats.max_frame_time().as_secs_f64() * 1000.0,
+            );
+            last_report = Instant::now();
+        }
 
     });
 
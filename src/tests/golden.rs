@@ -0,0 +1,294 @@
+//! golden-image tests: reference scenes are rendered headlessly and compared to the pngs in tests/golden.
+//! a failed comparison writes the actual image and a diff to target/golden. after an intended change
+//! to the output, `GOLDEN_BLESS=1 cargo test golden` rewrites the goldens from the current renderer
+
+use std::f32::consts::PI;
+use std::path::{Path, PathBuf};
+
+use crate::*;
+use crate::utils::fixtures::{checker_texture, sphere_vertices, vertex};
+
+
+
+// per channel difference that still counts as the same color, absorbs rounding changes
+const CHANNEL_TOLERANCE: u32 = 2;
+// fraction of pixels allowed past the tolerance, a few edge pixels flip when coverage rounding changes
+const MAX_DIFFERING_FRACTION: f32 = 0.002;
+
+const BLESS_VARIABLE: &str = "GOLDEN_BLESS";
+
+// diff image colors: matching pixels are a dimmed copy of the golden, differing ones red by how far off they are
+const DIFF_DIM: f32 = 0.25;
+
+
+
+#[test]
+fn golden_triangles() {
+    check_golden("triangles", triangles_scene());
+}
+
+
+#[test]
+fn golden_cube() {
+    check_golden("cube", cube_scene());
+}
+
+
+#[test]
+fn golden_lit_sphere() {
+    check_golden("lit_sphere", lit_sphere_scene());
+}
+
+
+#[test]
+fn golden_checker_floor() {
+    check_golden("checker_floor", checker_floor_scene());
+}
+
+
+#[test]
+fn golden_near_clip() {
+    check_golden("near_clip", near_clip_scene());
+}
+
+
+#[test]
+fn golden_point_cloud() {
+    check_golden("point_cloud", point_cloud_scene());
+}
+
+
+
+// intersecting vertex colored triangles, depth has to be resolved per pixel
+fn triangles_scene() -> Scene {
+    let mut scene = new_scene(160, 120, vec3!(0, 0, 0), vec3!(0, 0, -1));
+    scene.settings.background = vec3!(0.05, 0.05, 0.1);
+
+    let triangles = [
+        ([vec3!(-1.6, -1.0, -3.0), vec3!(1.2, -0.6, -5.0), vec3!(-0.4, 1.3, -4.0)], vec3!(1, 0.2, 0.2)),
+        ([vec3!(-1.2, -0.9, -5.0), vec3!(1.6, -1.1, -3.0), vec3!(0.5, 1.2, -4.0)], vec3!(0.2, 1, 0.3)),
+        ([vec3!(-1.5, 0.4, -4.2), vec3!(-0.3, -1.3, -3.8), vec3!(1.5, 0.6, -4.0)], vec3!(0.3, 0.4, 1)),
+    ];
+    let mut vertices: Vec<Vertex> = Vec::new();
+    for (corners, color) in triangles {
+        for (i, corner) in corners.into_iter().enumerate() {
+            // a gradient across each triangle checks the perspective correct interpolation
+            let shade = 0.4 + 0.3 * i as f32;
+            vertices.push(vertex(corner, vec2!(0, 0), color * shade));
+        }
+    }
+    scene.load_model(Model::new(vertices, Box::new(VertexColorShader::new())));
+    return scene;
+}
+
+
+// a rotated cube with a color per face, checks backface culling. the odd size leaves partial simd rows
+fn cube_scene() -> Scene {
+    let mut scene = new_scene(157, 99, vec3!(0, 0, 0), vec3!(0, 0, -1));
+
+    let mut cube = Model::new(cube_vertices(1.0), Box::new(VertexColorShader::new()));
+    cube.transform.position = vec3!(0, 0, -2.2);
+    cube.transform.set_rotation(30.0, 40.0);
+    scene.load_model(cube);
+    return scene;
+}
+
+
+// a smooth sphere lit through a flat normal map, checks normal and tangent interpolation
+fn lit_sphere_scene() -> Scene {
+    let mut scene = new_scene(160, 120, vec3!(0, 0.5, 2.5), vec3!(0, 0, 0));
+    scene.settings.background = vec3!(0.2, 0.2, 0.25);
+
+    let mut vertices = sphere_vertices(vec3!(0, 0, 0), 1.0, 12, 24);
+    generate_tangents(&mut vertices);
+    let mut shader = NormalMapShader::new(Texture::new(vec![0xFF8080FF], 1, 1));
+    shader.diffuse_texture = Some(checker_texture(64, 8));
+    scene.load_model(Model::new(vertices, Box::new(shader)));
+    return scene;
+}
+
+
+// a textured floor running off towards the far plane, checks mip selection and perspective correct texcoords
+fn checker_floor_scene() -> Scene {
+    let mut scene = new_scene(160, 120, vec3!(0, 1, 0), vec3!(0, 0.2, -6));
+    scene.settings.background = vec3!(0.5, 0.7, 0.9);
+
+    let vertices = quad_vertices([vec3!(-8, 0, 2), vec3!(8, 0, 2), vec3!(8, 0, -30), vec3!(-8, 0, -30)]);
+    scene.load_model(Model::new(vertices, Box::new(TextureShader::new(checker_texture(256, 16)))));
+    return scene;
+}
+
+
+// a wall the camera stands in front of at an angle and a floor under the camera, both cut by the near plane
+fn near_clip_scene() -> Scene {
+    let mut scene = new_scene(160, 120, vec3!(0, 0, 0), vec3!(0.6, -0.2, -1));
+    scene.settings.near_clip = 0.5;
+
+    let mut wall = quad_vertices([vec3!(-2, -2, 0.5), vec3!(2, -2, -4), vec3!(2, 2, -4), vec3!(-2, 2, 0.5)]);
+    for (i, vertex) in wall.iter_mut().enumerate() {
+        vertex.color = [vec3!(1, 0.8, 0.2), vec3!(0.2, 0.8, 1)][i % 2];
+    }
+    scene.load_model(Model::new(wall, Box::new(VertexColorShader::new())));
+
+    let floor = quad_vertices([vec3!(-3, -1, 3), vec3!(3, -1, 3), vec3!(3, -1, -3), vec3!(-3, -1, -3)]);
+    scene.load_model(Model::new(floor, Box::new(TextureShader::new(checker_texture(64, 8)))));
+    return scene;
+}
+
+
+// a colored helix of points, some of them behind the camera
+fn point_cloud_scene() -> Scene {
+    let mut scene = new_scene(160, 120, vec3!(0, 0, 1), vec3!(0, 0, -3));
+
+    let points: Vec<Vertex> = (0..400).map(|i| {
+        let t = i as f32 / 400.0;
+        let angle = t * 6.0 * PI;
+        let position = vec3!(angle.cos() * 1.2, angle.sin() * 1.2, 2.0 - t * 10.0);
+        return vertex(position, vec2!(0, 0), vec3!(t, 1.0 - t, 0.5 + 0.5 * angle.sin()));
+    }).collect();
+
+    let mut cloud = PointCloud::new(points);
+    cloud.point_size = 3.0;
+    scene.load_point_cloud(cloud);
+    return scene;
+}
+
+
+
+fn new_scene(width: u32, height: u32, camera_position: Vec3, look_at: Vec3) -> Scene {
+    let mut scene = Scene::new();
+    scene.settings.width = width;
+    scene.settings.height = height;
+    scene.camera = Camera::new(70.0);
+    scene.camera.transform.position = camera_position;
+    scene.camera.transform.look_at(look_at);
+    return scene;
+}
+
+
+// two triangles, corners counter-clockwise seen from the front, texcoords 0..1 from the first corner
+fn quad_vertices(corners: [Vec3; 4]) -> Vec<Vertex> {
+    let texcoords = [vec2!(0, 1), vec2!(1, 1), vec2!(1, 0), vec2!(0, 0)];
+    let normal = (corners[1] - corners[0]).cross(corners[3] - corners[0]).normalize();
+    return [0, 1, 2, 0, 2, 3].into_iter().map(|i| {
+        let mut vertex = vertex(corners[i], texcoords[i], vec3!(1, 1, 1));
+        vertex.normal = normal;
+        return vertex;
+    }).collect();
+}
+
+
+fn cube_vertices(size: f32) -> Vec<Vertex> {
+    let h = size / 2.0;
+    let faces = [
+        ([vec3!(-h, -h, h), vec3!(h, -h, h), vec3!(h, h, h), vec3!(-h, h, h)], vec3!(1, 0.3, 0.3)),
+        ([vec3!(h, -h, -h), vec3!(-h, -h, -h), vec3!(-h, h, -h), vec3!(h, h, -h)], vec3!(0.3, 1, 0.3)),
+        ([vec3!(h, -h, h), vec3!(h, -h, -h), vec3!(h, h, -h), vec3!(h, h, h)], vec3!(0.3, 0.3, 1)),
+        ([vec3!(-h, -h, -h), vec3!(-h, -h, h), vec3!(-h, h, h), vec3!(-h, h, -h)], vec3!(1, 1, 0.3)),
+        ([vec3!(-h, h, h), vec3!(h, h, h), vec3!(h, h, -h), vec3!(-h, h, -h)], vec3!(0.3, 1, 1)),
+        ([vec3!(-h, -h, -h), vec3!(h, -h, -h), vec3!(h, -h, h), vec3!(-h, -h, h)], vec3!(1, 0.3, 1)),
+    ];
+    let mut vertices: Vec<Vertex> = Vec::new();
+    for (corners, color) in faces {
+        for mut vertex in quad_vertices(corners) {
+            // darker towards the first corner so each face shows its orientation
+            vertex.color = color * (0.5 + 0.5 * vertex.texcoord.x.max(1.0 - vertex.texcoord.y));
+            vertices.push(vertex);
+        }
+    }
+    return vertices;
+}
+
+
+
+struct Comparison {
+    differing: usize,
+    max_difference: u32,
+    diff: Vec<u32>,
+}
+
+
+
+fn compare(expected: &[u32], actual: &[u32]) -> Comparison {
+    let mut comparison = Comparison { differing: 0, max_difference: 0, diff: Vec::with_capacity(actual.len()) };
+    for (&expected, &actual) in expected.iter().zip(actual) {
+        let difference = [16, 8, 0].iter().map(|shift| ((expected >> shift & 0xFF) as i32 - (actual >> shift & 0xFF) as i32).unsigned_abs()).max().unwrap();
+        comparison.max_difference = comparison.max_difference.max(difference);
+
+        if difference > CHANNEL_TOLERANCE {
+            comparison.differing += 1;
+            comparison.diff.push(0xFF000000 | (128 + difference / 2) << 16);
+        } else {
+            let dim = |shift: u32| (((expected >> shift & 0xFF) as f32 * DIFF_DIM) as u32) << shift;
+            comparison.diff.push(0xFF000000 | dim(16) | dim(8) | dim(0));
+        }
+    }
+    return comparison;
+}
+
+
+
+fn check_golden(name: &str, scene: Scene) {
+    let (width, height) = (scene.settings.width, scene.settings.height);
+    let mut target = RenderTarget::new(width, height);
+    Rasterizer::new().render(&mut target, &scene);
+
+    let golden_path = golden_directory().join(format!("{}.png", name));
+    if blessing() {
+        std::fs::create_dir_all(golden_directory()).unwrap();
+        std::fs::write(&golden_path, encode_png(&target.color_buffer, width, height)).unwrap();
+        println!("blessed {}", golden_path.display());
+        return;
+    }
+
+    let golden = match std::fs::read(&golden_path) {
+        Ok(bytes) => decode_png(&bytes).unwrap_or_else(|err| panic!("{}: {}", golden_path.display(), err)),
+        Err(err) => panic!("{}: {}, run with {}=1 to create it", golden_path.display(), err, BLESS_VARIABLE),
+    };
+
+    let output = output_directory();
+    std::fs::create_dir_all(&output).unwrap();
+    let actual_path = output.join(format!("{}.actual.png", name));
+
+    if (golden.width, golden.height) != (width, height) {
+        std::fs::write(&actual_path, encode_png(&target.color_buffer, width, height)).unwrap();
+        panic!("{}: rendered {}x{} but the golden is {}x{}, actual image in {}", name, width, height, golden.width, golden.height, actual_path.display());
+    }
+
+    let comparison = compare(&golden.data, &target.color_buffer);
+    let allowed = (MAX_DIFFERING_FRACTION * (width * height) as f32) as usize;
+    if comparison.differing <= allowed {
+        return;
+    }
+
+    let diff_path = output.join(format!("{}.diff.png", name));
+    std::fs::write(&actual_path, encode_png(&target.color_buffer, width, height)).unwrap();
+    std::fs::write(&diff_path, encode_png(&comparison.diff, width, height)).unwrap();
+    panic!(
+        "{}: {} pixels differ by more than {} (at most {} allowed), largest difference {}\n  actual: {}\n  diff:   {}\nif the change is intended, run with {}=1 to bless it",
+        name,
+        comparison.differing,
+        CHANNEL_TOLERANCE,
+        allowed,
+        comparison.max_difference,
+        actual_path.display(),
+        diff_path.display(),
+        BLESS_VARIABLE,
+    );
+}
+
+
+
+fn blessing() -> bool {
+    return std::env::var(BLESS_VARIABLE).is_ok_and(|value| !value.is_empty() && value != "0");
+}
+
+
+fn golden_directory() -> PathBuf {
+    return Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden");
+}
+
+
+fn output_directory() -> PathBuf {
+    return Path::new(env!("CARGO_MANIFEST_DIR")).join("target").join("golden");
+}
@@ -0,0 +1,28 @@
+use crate::types::shader::{DEFAULT_AMBIENT, DEFAULT_LIGHT_DIRECTION};
+use crate::Vec3;
+
+
+
+/// light declared by a scene, shaders are given it when they're built.
+/// shading happens in view space, so directions are relative to the camera
+#[derive(Debug, Clone, Copy)]
+pub enum Light {
+    // direction towards the light
+    Directional { direction: Vec3 },
+    Ambient { intensity: f32 },
+}
+
+
+
+/// shaders light with the first directional light and the first ambient term
+pub fn shader_lighting(lights: &[Light]) -> (Vec3, f32) {
+    let direction = lights.iter().find_map(|light| match light {
+        Light::Directional { direction } => Some(direction.normalize()),
+        _ => None,
+    });
+    let ambient = lights.iter().find_map(|light| match light {
+        Light::Ambient { intensity } => Some(*intensity),
+        _ => None,
+    });
+    return (direction.unwrap_or(DEFAULT_LIGHT_DIRECTION), ambient.unwrap_or(DEFAULT_AMBIENT));
+}
@@ -0,0 +1,47 @@
+use crate::{vec3, Vec3};
+
+
+
+/// surface description from an MTL file or glTF material, texture maps are resolved file paths
+#[derive(Debug, Clone)]
+pub struct Material {
+    pub name: String,
+    pub ambient: Vec3,  // Ka
+    pub diffuse: Vec3,  // Kd
+    pub specular: Vec3, // Ks
+    pub shininess: f32, // Ns
+    pub opacity: f32,   // d, or 1 - Tr
+    pub illum: u32,
+    pub emissive: Vec3,  // Ke
+    pub metallic: f32,   // Pm
+    pub roughness: f32,  // Pr
+    pub diffuse_map: Option<String>, // map_Kd
+    pub bump_map: Option<String>,    // map_Bump / bump, a grayscale height map
+    pub bump_multiplier: f32,        // -bm on the bump map, scales its slopes
+    pub normal_map: Option<String>,  // norm, a tangent-space normal map, used over the bump map
+    pub alpha_map: Option<String>,   // map_d
+}
+
+
+
+impl Material {
+    pub fn new(name: &str) -> Material {
+        return Material {
+            name: name.to_string(),
+            ambient: vec3!(0, 0, 0),
+            diffuse: vec3!(1, 1, 1),
+            specular: vec3!(0, 0, 0),
+            shininess: 0.0,
+            opacity: 1.0,
+            illum: 1,
+            emissive: vec3!(0, 0, 0),
+            metallic: 0.0,
+            roughness: 1.0,
+            diffuse_map: None,
+            bump_map: None,
+            bump_multiplier: 1.0,
+            normal_map: None,
+            alpha_map: None,
+        };
+    }
+}
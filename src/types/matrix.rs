@@ -0,0 +1,119 @@
+use crate::{vec3, Vec3};
+
+
+
+/// column-major like glTF, m[column * 4 + row]
+pub type Mat4 = [f32; 16];
+
+pub const IDENTITY: Mat4 = [1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0];
+
+/// (x, y, z, w), glTF's order
+pub type Quaternion = [f32; 4];
+
+pub const NO_ROTATION: Quaternion = [0.0, 0.0, 0.0, 1.0];
+
+
+
+/// translation * rotation * scale
+pub fn trs_matrix(translation: Vec3, rotation: Quaternion, scale: Vec3) -> Mat4 {
+    let [x, y, z, w] = rotation;
+    let columns = [
+        [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y + z * w), 2.0 * (x * z - y * w)],
+        [2.0 * (x * y - z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z + x * w)],
+        [2.0 * (x * z + y * w), 2.0 * (y * z - x * w), 1.0 - 2.0 * (x * x + y * y)],
+    ];
+    let scale = [scale.x, scale.y, scale.z];
+
+    let mut matrix = IDENTITY;
+    for (column, axis) in columns.iter().enumerate() {
+        for row in 0..3 {
+            matrix[column * 4 + row] = axis[row] * scale[column];
+        }
+    }
+    matrix[12..15].copy_from_slice(&[translation.x, translation.y, translation.z]);
+    return matrix;
+}
+
+
+
+/// inverse of `trs_matrix`, shear is lost. a mirroring matrix comes out with a negative x scale
+pub fn decompose(m: &Mat4) -> (Vec3, Quaternion, Vec3) {
+    let mut scale = vec3!(column(m, 0).length(), column(m, 1).length(), column(m, 2).length());
+    if determinant3(m) < 0.0 {
+        scale.x = -scale.x;
+    }
+    if scale.x == 0.0 || scale.y == 0.0 || scale.z == 0.0 {
+        return (column(m, 3), NO_ROTATION, scale);
+    }
+    let (a, b, c) = (column(m, 0) / scale.x, column(m, 1) / scale.y, column(m, 2) / scale.z);
+
+    // Shepperd's method, from the largest of the diagonal combinations to stay accurate
+    let trace = a.x + b.y + c.z;
+    let rotation = if trace > 0.0 {
+        let s = (trace + 1.0).sqrt() * 2.0;
+        [(b.z - c.y) / s, (c.x - a.z) / s, (a.y - b.x) / s, s / 4.0]
+    } else if a.x > b.y && a.x > c.z {
+        let s = (1.0 + a.x - b.y - c.z).sqrt() * 2.0;
+        [s / 4.0, (b.x + a.y) / s, (c.x + a.z) / s, (b.z - c.y) / s]
+    } else if b.y > c.z {
+        let s = (1.0 + b.y - a.x - c.z).sqrt() * 2.0;
+        [(b.x + a.y) / s, s / 4.0, (c.y + b.z) / s, (c.x - a.z) / s]
+    } else {
+        let s = (1.0 + c.z - a.x - b.y).sqrt() * 2.0;
+        [(c.x + a.z) / s, (c.y + b.z) / s, s / 4.0, (a.y - b.x) / s]
+    };
+    return (column(m, 3), rotation, scale);
+}
+
+
+
+pub fn mat_mul(a: &Mat4, b: &Mat4) -> Mat4 {
+    let mut result = [0.0; 16];
+    for column in 0..4 {
+        for row in 0..4 {
+            result[column * 4 + row] = (0..4).map(|k| a[k * 4 + row] * b[column * 4 + k]).sum();
+        }
+    }
+    return result;
+}
+
+
+
+pub fn column(m: &Mat4, index: usize) -> Vec3 {
+    return vec3!(m[index * 4], m[index * 4 + 1], m[index * 4 + 2]);
+}
+
+
+
+pub fn transform_vector(m: &Mat4, v: Vec3) -> Vec3 {
+    return column(m, 0) * v.x + column(m, 1) * v.y + column(m, 2) * v.z;
+}
+
+
+
+pub fn transform_point(m: &Mat4, p: Vec3) -> Vec3 {
+    return transform_vector(m, p) + column(m, 3);
+}
+
+
+
+pub fn determinant3(m: &Mat4) -> f32 {
+    return column(m, 0).dot(column(m, 1).cross(column(m, 2)));
+}
+
+
+
+/// inverse transpose via the cofactor matrix, keeps normals perpendicular under non-uniform scale
+pub fn transform_normal(m: &Mat4, n: Vec3) -> Vec3 {
+    return cofactor_transform(m, n).normalize();
+}
+
+
+
+/// `transform_normal` before normalizing, linear so it also works on normal offsets
+pub fn cofactor_transform(m: &Mat4, n: Vec3) -> Vec3 {
+    let (a, b, c) = (column(m, 0), column(m, 1), column(m, 2));
+    let cofactor = b.cross(c) * n.x + c.cross(a) * n.y + a.cross(b) * n.z;
+    let sign = if determinant3(m) < 0.0 { -1.0 } else { 1.0 };
+    return cofactor * sign;
+}
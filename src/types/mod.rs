@@ -1,7 +1,14 @@
+pub mod light;
+pub mod material;
+pub mod matrix;
 pub mod model;
+pub mod morph;
+pub mod normals;
+pub mod point_cloud;
 pub mod transform;
 pub mod texture;
 pub mod shader;
+pub mod tangents;
 pub mod vertex;
 pub mod vec2;
 pub mod vec3;
\ No newline at end of file
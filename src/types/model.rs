@@ -1,9 +1,72 @@
-use crate::{Vertex, Shader, Texture, Transform, Vec2, Vec3};
+use std::ops::Range;
+
+use crate::types::morph::MorphTarget;
+use crate::{Skeleton, Vertex, Shader, Texture, Transform, Vec2, Vec3};
+
+
+// shader index meaning "use the model's own shader"
+pub const MODEL_SHADER: u32 = u32::MAX;
 
 
 pub struct Model {
     pub vertices: Vec<Vertex>,
-    pub colors: Vec<u32>,
     pub transform: Transform,
-    pub shader: Box<dyn Shader>    
+    pub shader: Box<dyn Shader>,
+    // contiguous vertex ranges with their own shader, empty means the whole model uses `shader`
+    pub submeshes: Vec<SubMesh>,
+    // deforms the vertices by their joints before `transform` applies
+    pub skeleton: Option<Skeleton>,
+    // blend shapes added to the vertices before skinning, weighted by `morph_weights` which has one per target
+    pub morph_targets: Vec<MorphTarget>,
+    pub morph_weights: Vec<f32>,
+}
+
+
+pub struct SubMesh {
+    pub material: String,
+    pub vertices: Range<usize>,
+    pub shader: Box<dyn Shader>,
+}
+
+
+
+impl Model {
+    /// drawn whole with `shader`, at the origin and without skinning or morphs
+    pub fn new(vertices: Vec<Vertex>, shader: Box<dyn Shader>) -> Model {
+        return Model {
+            vertices,
+            transform: Transform::new(),
+            shader,
+            submeshes: Vec::new(),
+            skeleton: None,
+            morph_targets: Vec::new(),
+            morph_weights: Vec::new(),
+        };
+    }
+
+
+    /// vertex ranges paired with the shader index used to draw them
+    pub fn draw_ranges(&self) -> Vec<(u32, Range<usize>)> {
+        if self.submeshes.is_empty() {
+            return vec![(MODEL_SHADER, 0..self.vertices.len())];
+        }
+        return self.submeshes
+            .iter()
+            .enumerate()
+            .map(|(i, submesh)| (i as u32, submesh.vertices.clone()))
+            .collect();
+    }
+
+
+    pub fn morph_target(&self, name: &str) -> Option<usize> {
+        return self.morph_targets.iter().position(|target| target.name == name);
+    }
+
+
+    pub fn shader(&self, shader_index: u32) -> &dyn Shader {
+        match self.submeshes.get(shader_index as usize) {
+            Some(submesh) => return submesh.shader.as_ref(),
+            None => return self.shader.as_ref(),
+        }
+    }
 }
@@ -0,0 +1,37 @@
+use crate::{Vec3, Vertex};
+
+
+
+/// a blend shape, offsets for every vertex of a model that are added scaled by the target's weight
+#[derive(Debug, Clone)]
+pub struct MorphTarget {
+    pub name: String,
+    // one per model vertex
+    pub positions: Vec<Vec3>,
+    // one per model vertex, empty when the target leaves normals alone
+    pub normals: Vec<Vec3>,
+}
+
+
+
+/// `vertices` with every target added at its weight, None when all weights are zero and the vertices stay as they are
+pub fn morph_vertices(vertices: &[Vertex], targets: &[MorphTarget], weights: &[f32]) -> Option<Vec<Vertex>> {
+    let active: Vec<(&MorphTarget, f32)> = targets.iter().zip(weights).filter(|(_, weight)| **weight != 0.0).map(|(target, &weight)| (target, weight)).collect();
+    if active.is_empty() {
+        return None;
+    }
+
+    let mut morphed = vertices.to_vec();
+    for &(target, weight) in &active {
+        for (vertex, &delta) in morphed.iter_mut().zip(&target.positions) {
+            vertex.position += delta * weight;
+        }
+        for (vertex, &delta) in morphed.iter_mut().zip(&target.normals) {
+            vertex.normal += delta * weight;
+        }
+    }
+    if active.iter().any(|(target, _)| !target.normals.is_empty()) {
+        morphed.iter_mut().for_each(|vertex| vertex.normal = vertex.normal.normalize());
+    }
+    return Some(morphed);
+}
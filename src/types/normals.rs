@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use crate::{Model, Vertex, Vec3, vec3};
+
+
+// crease angle used when a mesh carries no smoothing information, in degrees
+pub const DEFAULT_SMOOTHING_ANGLE: f32 = 60.0;
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NormalMode {
+    /// every triangle gets its face normal
+    Flat,
+    /// neighbouring faces within `angle_threshold` degrees of each other are blended
+    Smooth { angle_threshold: f32, weighting: NormalWeighting },
+}
+
+
+
+/// how much each face contributes to a smoothed vertex normal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NormalWeighting {
+    Uniform,
+    Area,
+    // angle of the face at the shared corner, keeps normals stable under retriangulation
+    Angle,
+}
+
+
+
+impl Model {
+    pub fn generate_normals(&mut self, mode: NormalMode) {
+        generate_normals(&mut self.vertices, None, mode);
+    }
+}
+
+
+
+/// replaces the normals of a triangle list, `smoothing_groups` holds one entry per triangle.
+/// with smoothing groups only faces sharing a non-zero group are blended, group 0 is flat
+pub fn generate_normals(vertices: &mut [Vertex], smoothing_groups: Option<&[u32]>, mode: NormalMode) {
+    let num_triangles = vertices.len() / 3;
+
+    let mut face_normals: Vec<Vec3> = Vec::with_capacity(num_triangles);
+    let mut face_areas: Vec<f32> = Vec::with_capacity(num_triangles);
+    for triangle in vertices.chunks_exact(3) {
+        let cross = (triangle[1].position - triangle[0].position).cross(triangle[2].position - triangle[0].position);
+        face_normals.push(cross.normalize());
+        face_areas.push(cross.length() * 0.5);
+    }
+
+    let (angle_threshold, weighting) = match mode {
+        NormalMode::Flat => {
+            for (i, vertex) in vertices[..num_triangles * 3].iter_mut().enumerate() {
+                vertex.normal = face_normals[i / 3];
+            }
+            return;
+        }
+        NormalMode::Smooth { angle_threshold, weighting } => (angle_threshold, weighting),
+    };
+    let min_cos = angle_threshold.clamp(0.0, 180.0).to_radians().cos();
+
+    // corners sharing a position, compared bitwise so exported duplicates weld exactly
+    let mut shared_corners: HashMap<[u32; 3], Vec<usize>> = HashMap::new();
+    for (corner, vertex) in vertices[..num_triangles * 3].iter().enumerate() {
+        let p = vertex.position;
+        shared_corners.entry([p.x.to_bits(), p.y.to_bits(), p.z.to_bits()]).or_default().push(corner);
+    }
+
+    let corner_weight = |corner: usize| -> f32 {
+        match weighting {
+            NormalWeighting::Uniform => return 1.0,
+            NormalWeighting::Area => return face_areas[corner / 3],
+            NormalWeighting::Angle => return corner_angle(vertices, corner),
+        }
+    };
+
+    let mut smoothed: Vec<Vec3> = vec![vec3!(0, 0, 0); num_triangles * 3];
+    for corners in shared_corners.values() {
+        for &corner in corners {
+            let face = corner / 3;
+            let group = smoothing_groups.map(|groups| groups[face]);
+
+            let mut sum = vec3!(0, 0, 0);
+            for &other in corners {
+                let other_face = other / 3;
+                let blend = other_face == face || (
+                    group.is_none_or(|group| group != 0 && smoothing_groups.unwrap()[other_face] == group)
+                    && face_normals[face].dot(face_normals[other_face]) >= min_cos
+                );
+                if blend {
+                    sum += face_normals[other_face] * corner_weight(other);
+                }
+            }
+
+            // degenerate faces and zero weights fall back to the face normal
+            smoothed[corner] = if sum.length() > 0.0 { sum.normalize() } else { face_normals[face] };
+        }
+    }
+
+    for (vertex, normal) in vertices.iter_mut().zip(smoothed) {
+        vertex.normal = normal;
+    }
+}
+
+
+
+// interior angle of the triangle at `corner`, in radians
+pub(crate) fn corner_angle(vertices: &[Vertex], corner: usize) -> f32 {
+    let base = corner - corner % 3;
+    let p = vertices[corner].position;
+    let a = (vertices[base + (corner % 3 + 1) % 3].position - p).normalize();
+    let b = (vertices[base + (corner % 3 + 2) % 3].position - p).normalize();
+    return a.dot(b).clamp(-1.0, 1.0).acos();
+}
@@ -0,0 +1,19 @@
+use crate::{Transform, Vertex};
+
+
+
+/// unconnected points drawn as screen aligned squares, colored by `Vertex::color`
+pub struct PointCloud {
+    pub points: Vec<Vertex>,
+    pub transform: Transform,
+    // edge length of each point in pixels
+    pub point_size: f32,
+}
+
+
+
+impl PointCloud {
+    pub fn new(points: Vec<Vertex>) -> PointCloud {
+        return PointCloud { points, transform: Transform::new(), point_size: 2.0 };
+    }
+}
\ No newline at end of file
@@ -1,13 +1,44 @@
-
 use core::str;
 
-use crate::{Texture, Vec2, Vec3};
+use crate::{vec3, Material, MipFilter, Texture, Vec2, Vec3};
+
+
+/// per-pixel inputs interpolated by the color pass
+#[derive(Debug, Clone, Copy)]
+pub struct Fragment {
+    pub texcoord: Vec2,
+    pub normal: Vec3,
+    // screen space texcoord derivatives, for mip selection
+    pub texcoord_dx: Vec2,
+    pub texcoord_dy: Vec2,
+    pub tangent: Vec3,
+    pub bitangent_sign: f32,
+    pub color: Vec3,
+}
+
+
+
+// view space direction towards the light used by shaders that need one, up and to the left of the camera
+pub const DEFAULT_LIGHT_DIRECTION: Vec3 = Vec3 { x: -0.4, y: 0.5, z: 0.77 };
+pub const DEFAULT_AMBIENT: f32 = 0.15;
+
 
 
 pub trait Shader {
 
 
-    fn pixel_color(&self, texcoord: Vec2, normal: Vec3) -> u32;
+    fn pixel_color(&self, fragment: &Fragment) -> u32;
+
+    /// lit shaders take the scene's lighting, called again when animation changes the lights
+    fn set_lighting(&mut self, light_direction: Vec3, ambient: f32) {}
+
+    /// rebuilds the mip chains of the shader's textures with `filter`
+    fn set_mip_filter(&mut self, filter: MipFilter) {}
+
+    /// fragments with a lower alpha are discarded, without one alpha is ignored and everything draws opaque
+    fn alpha_cutoff(&self) -> Option<f32> {
+        return None;
+    }
 }
 
 
@@ -22,23 +53,214 @@ impl TextureShader {
 }
 
 impl Shader for TextureShader{
+    fn set_mip_filter(&mut self, filter: MipFilter) {
+        self.texture.generate_mipmaps(filter);
+    }
+
 
-    fn pixel_color(&self, texcoord: Vec2, normal: Vec3) -> u32 {
-        return self.texture.sample(texcoord.x, texcoord.y);
+    fn pixel_color(&self, fragment: &Fragment) -> u32 {
+        let lod = self.texture.lod(fragment.texcoord_dx, fragment.texcoord_dy);
+        return self.texture.sample_trilinear(fragment.texcoord.x, fragment.texcoord.y, lod);
     }
 }
 
 
-pub struct SolidShader;
+/// plain white with lambert shading from the interpolated normal, what loaders give models without a material
+pub struct SolidShader {
+    pub light_direction: Vec3,
+    pub ambient: f32,
+}
 
 impl SolidShader {
+    pub fn new() -> Self {
+        return Self { light_direction: DEFAULT_LIGHT_DIRECTION, ambient: DEFAULT_AMBIENT };
+    }
+}
+
+impl Shader for SolidShader {
+    fn set_lighting(&mut self, light_direction: Vec3, ambient: f32) {
+        self.light_direction = light_direction;
+        self.ambient = ambient;
+    }
+
+
+    fn pixel_color(&self, fragment: &Fragment) -> u32 {
+        let shade = lambert(fragment.normal.normalize(), self.light_direction, self.ambient);
+        return pack_rgb(vec3!(1, 1, 1) * shade, 1.0);
+    }
+}
+
+
+/// interpolated vertex colors, e.g. for scanned meshes
+pub struct VertexColorShader;
+
+impl VertexColorShader {
     pub fn new() -> Self {
         return Self;
     }
 }
 
-impl Shader for SolidShader{
-    fn pixel_color(&self, texcoord: Vec2, normal: Vec3) -> u32 {
-        return 0xFFFFFFFF;
+impl Shader for VertexColorShader {
+    fn pixel_color(&self, fragment: &Fragment) -> u32 {
+        return pack_rgb(fragment.color, 1.0);
+    }
+}
+
+
+/// lambert shading with normals from a tangent-space normal map, over an optional diffuse map
+pub struct NormalMapShader {
+    pub normal_map: Texture,
+    pub diffuse_texture: Option<Texture>,
+    pub light_direction: Vec3,
+    pub ambient: f32,
+}
+
+impl NormalMapShader {
+    pub fn new(normal_map: Texture) -> Self {
+        return Self { normal_map, diffuse_texture: None, light_direction: DEFAULT_LIGHT_DIRECTION, ambient: DEFAULT_AMBIENT };
+    }
+}
+
+impl Shader for NormalMapShader {
+    fn set_lighting(&mut self, light_direction: Vec3, ambient: f32) {
+        self.light_direction = light_direction;
+        self.ambient = ambient;
+    }
+
+
+    fn set_mip_filter(&mut self, filter: MipFilter) {
+        self.normal_map.generate_mipmaps(filter);
+        if let Some(texture) = &mut self.diffuse_texture {
+            texture.generate_mipmaps(filter);
+        }
+    }
+
+
+    fn pixel_color(&self, fragment: &Fragment) -> u32 {
+        let mut color = vec3!(1, 1, 1);
+        if let Some(texture) = &self.diffuse_texture {
+            let lod = texture.lod(fragment.texcoord_dx, fragment.texcoord_dy);
+            color = channels_rgb(texture.sample_trilinear(fragment.texcoord.x, fragment.texcoord.y, lod));
+        }
+
+        let normal = perturb_normal(fragment, &self.normal_map);
+        color = color * lambert(normal, self.light_direction, self.ambient);
+        return pack_rgb(color, 1.0);
+    }
+}
+
+
+/// diffuse color times the vertex color and an optional diffuse map, alpha from opacity and an optional alpha map.
+/// lit with the interpolated normal, perturbed by the tangent-space normal texture if there is one
+pub struct MaterialShader {
+    pub material: Material,
+    pub diffuse_texture: Option<Texture>,
+    pub normal_texture: Option<Texture>,
+    pub alpha_texture: Option<Texture>,
+    // alpha test threshold, there is no blending so alpha does nothing without one
+    pub alpha_cutoff: Option<f32>,
+    pub light_direction: Vec3,
+    pub ambient: f32,
+}
+
+impl MaterialShader {
+    pub fn new(material: Material) -> Self {
+        return Self {
+            material,
+            diffuse_texture: None,
+            normal_texture: None,
+            alpha_texture: None,
+            alpha_cutoff: None,
+            light_direction: DEFAULT_LIGHT_DIRECTION,
+            ambient: DEFAULT_AMBIENT,
+        };
+    }
+}
+
+impl Shader for MaterialShader {
+    fn set_lighting(&mut self, light_direction: Vec3, ambient: f32) {
+        self.light_direction = light_direction;
+        self.ambient = ambient;
     }
-}
\ No newline at end of file
+
+
+    fn alpha_cutoff(&self) -> Option<f32> {
+        return self.alpha_cutoff;
+    }
+
+
+    fn set_mip_filter(&mut self, filter: MipFilter) {
+        for texture in [&mut self.diffuse_texture, &mut self.normal_texture, &mut self.alpha_texture].into_iter().flatten() {
+            texture.generate_mipmaps(filter);
+        }
+    }
+
+
+    fn pixel_color(&self, fragment: &Fragment) -> u32 {
+        let u = fragment.texcoord.x;
+        let v = fragment.texcoord.y;
+
+        let mut color = self.material.diffuse * fragment.color;
+        let mut alpha = self.material.opacity;
+
+        if let Some(texture) = &self.diffuse_texture {
+            let lod = texture.lod(fragment.texcoord_dx, fragment.texcoord_dy);
+            let texel = texture.sample_trilinear(u, v, lod);
+            color = color * channels_rgb(texel);
+            alpha *= ((texel >> 24) & 0xFF) as f32 / 255.0;
+        }
+
+        // alpha maps are grayscale, any color channel will do
+        if let Some(texture) = &self.alpha_texture {
+            let lod = texture.lod(fragment.texcoord_dx, fragment.texcoord_dy);
+            alpha *= channels_rgb(texture.sample_trilinear(u, v, lod)).x;
+        }
+
+        let normal = match &self.normal_texture {
+            Some(texture) => perturb_normal(fragment, texture),
+            None => fragment.normal.normalize(),
+        };
+        color = color * lambert(normal, self.light_direction, self.ambient);
+
+        return pack_rgb(color, alpha);
+    }
+}
+
+
+/// view space normal from a tangent-space normal map, green pointing along +v as MikkTSpace bakers write it
+pub fn perturb_normal(fragment: &Fragment, normal_map: &Texture) -> Vec3 {
+    let normal = fragment.normal;
+    // interpolation skews the frame, re-orthogonalize before use
+    let tangent = (fragment.tangent - normal * normal.dot(fragment.tangent)).normalize();
+    if tangent.length() == 0.0 {
+        return normal;
+    }
+    let bitangent = normal.cross(tangent) * fragment.bitangent_sign;
+
+    let lod = normal_map.lod(fragment.texcoord_dx, fragment.texcoord_dy);
+    let texel = channels_rgb(normal_map.sample_trilinear(fragment.texcoord.x, fragment.texcoord.y, lod));
+    let sample = texel * 2.0 - vec3!(1, 1, 1);
+
+    return (tangent * sample.x + bitangent * sample.y + normal * sample.z).normalize();
+}
+
+
+fn lambert(normal: Vec3, light_direction: Vec3, ambient: f32) -> f32 {
+    let diffuse = normal.dot(light_direction.normalize()).max(0.0);
+    return ambient + (1.0 - ambient) * diffuse;
+}
+
+
+fn pack_rgb(color: Vec3, alpha: f32) -> u32 {
+    let to_byte = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u32;
+    return (to_byte(alpha) << 24) | (to_byte(color.x) << 16) | (to_byte(color.y) << 8) | to_byte(color.z);
+}
+
+
+fn channels_rgb(color: u32) -> Vec3 {
+    return Vec3 {
+        x: ((color >> 16) & 0xFF) as f32 / 255.0,
+        y: ((color >> 8) & 0xFF) as f32 / 255.0,
+        z: (color & 0xFF) as f32 / 255.0,
+    };
+}
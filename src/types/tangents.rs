@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+use crate::types::normals::corner_angle;
+use crate::{Model, Vertex, Vec3, vec3};
+
+
+
+impl Model {
+    pub fn generate_tangents(&mut self) {
+        generate_tangents(&mut self.vertices);
+    }
+}
+
+
+
+/// per-vertex tangents for a triangle list, following MikkTSpace so baked normal maps line up.
+/// normals must already be set, faces with mirrored uvs get a bitangent sign of -1
+pub fn generate_tangents(vertices: &mut [Vertex]) {
+    let num_corners = vertices.len() / 3 * 3;
+
+    // one tangent direction and handedness per face
+    let mut face_tangents: Vec<Vec3> = Vec::with_capacity(num_corners / 3);
+    let mut face_signs: Vec<f32> = Vec::with_capacity(num_corners / 3);
+    for triangle in vertices[..num_corners].chunks_exact(3) {
+        let edge1 = triangle[1].position - triangle[0].position;
+        let edge2 = triangle[2].position - triangle[0].position;
+
+        // texcoords are stored top row first, normal maps are baked with v pointing up
+        let du1 = triangle[1].texcoord.x - triangle[0].texcoord.x;
+        let dv1 = triangle[0].texcoord.y - triangle[1].texcoord.y;
+        let du2 = triangle[2].texcoord.x - triangle[0].texcoord.x;
+        let dv2 = triangle[0].texcoord.y - triangle[2].texcoord.y;
+
+        let signed_uv_area = du1 * dv2 - du2 * dv1;
+        let sign = if signed_uv_area < 0.0 { -1.0 } else { 1.0 };
+        face_tangents.push((edge1 * dv2 - edge2 * dv1) * sign);
+        face_signs.push(sign);
+    }
+
+    // corners are welded when position, normal, texcoord and handedness all match
+    let mut shared_tangents: HashMap<[u32; 9], Vec3> = HashMap::new();
+    let corner_key = |vertex: &Vertex, sign: f32| -> [u32; 9] {
+        let (p, n, t) = (vertex.position, vertex.normal, vertex.texcoord);
+        return [
+            p.x.to_bits(), p.y.to_bits(), p.z.to_bits(),
+            n.x.to_bits(), n.y.to_bits(), n.z.to_bits(),
+            t.x.to_bits(), t.y.to_bits(), sign.to_bits(),
+        ];
+    };
+
+    for (corner, vertex) in vertices[..num_corners].iter().enumerate() {
+        let face = corner / 3;
+        let normal = vertex.normal.normalize();
+        let tangent = face_tangents[face];
+        let projected = (tangent - normal * normal.dot(tangent)).normalize();
+
+        let sum = shared_tangents.entry(corner_key(vertex, face_signs[face])).or_insert(vec3!(0, 0, 0));
+        *sum += projected * corner_angle(vertices, corner);
+    }
+
+    for (corner, vertex) in vertices[..num_corners].iter_mut().enumerate() {
+        let face = corner / 3;
+        let normal = vertex.normal.normalize();
+        let sum = shared_tangents[&corner_key(vertex, face_signs[face])];
+
+        let tangent = (sum - normal * normal.dot(sum)).normalize();
+        // degenerate uvs still need a frame orthogonal to the normal
+        vertex.tangent = if tangent.length() > 0.0 { tangent } else { perpendicular(normal) };
+        vertex.bitangent_sign = face_signs[face];
+    }
+}
+
+
+
+fn perpendicular(normal: Vec3) -> Vec3 {
+    let axis = if normal.x.abs() < 0.9 { vec3!(1, 0, 0) } else { vec3!(0, 1, 0) };
+    return (axis - normal * normal.dot(axis)).normalize();
+}
@@ -1,16 +1,62 @@
-use crate::{Vec2, Vec3}; 
+use std::ops::Range;
+
+use crate::{Vec2, Vec3, vec2};
 use crate::Transform;
 
+#[derive(Clone)]
 pub struct Texture {
-    pub data: Vec<u32>, 
+    pub data: Vec<u32>,
     pub width: u32,
     pub height: u32,
+    // levels 1..n of the mip chain, level 0 is `data` itself
+    pub mips: Vec<MipLevel>,
+}
+
+
+#[derive(Clone)]
+pub struct MipLevel {
+    pub data: Vec<u32>,
+    pub width: u32,
+    pub height: u32,
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MipFilter {
+    Box,
+    Kaiser,
+}
+
+
+
+impl MipFilter {
+    /// the name scene files use for the filter
+    pub fn name(self) -> &'static str {
+        match self {
+            MipFilter::Box => return "box",
+            MipFilter::Kaiser => return "kaiser",
+        }
+    }
+
+
+    pub fn from_name(name: &str) -> Option<MipFilter> {
+        match name {
+            "box" => return Some(MipFilter::Box),
+            "kaiser" => return Some(MipFilter::Kaiser),
+            _ => return None,
+        }
+    }
 }
 
+
+
 impl Texture {
     pub fn new(data: Vec<u32>, width: u32, height: u32) -> Self {
         assert_eq!(data.len(), (width * height) as usize, "Texture data length does not match dimensions.");
-        Texture { data, width, height }
+        let mut texture = Texture { data, width, height, mips: Vec::new() };
+        texture.generate_mipmaps(MipFilter::Box);
+        return texture;
     }
 
 
@@ -27,4 +73,277 @@ impl Texture {
         let index = (y * self.width + x) as usize;
         self.data[index]
     }
-}
\ No newline at end of file
+
+
+    /// number of levels including the base level
+    pub fn mip_count(&self) -> usize {
+        return self.mips.len() + 1;
+    }
+
+
+    pub fn level(&self, level: usize) -> (&[u32], u32, u32) {
+        if level == 0 || self.mips.is_empty() {
+            return (&self.data, self.width, self.height);
+        }
+        let mip = &self.mips[(level - 1).min(self.mips.len() - 1)];
+        return (&mip.data, mip.width, mip.height);
+    }
+
+
+    /// tangent-space normal map from a grayscale height map, the slopes between neighbouring texels scaled by `strength`.
+    /// rows grow with v, so green points along +v as `perturb_normal` expects
+    pub fn height_to_normal_map(&self, strength: f32) -> Texture {
+        let height_at = |x: i32, y: i32| -> f32 {
+            let x = x.clamp(0, self.width as i32 - 1) as u32;
+            let y = y.clamp(0, self.height as i32 - 1) as u32;
+            // grayscale, any channel will do
+            return ((self.data[(y * self.width + x) as usize] >> 16) & 0xFF) as f32 / 255.0;
+        };
+        let to_byte = |c: f32| ((c * 0.5 + 0.5).clamp(0.0, 1.0) * 255.0).round() as u32;
+
+        let mut data = Vec::with_capacity(self.data.len());
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                let du = (height_at(x + 1, y) - height_at(x - 1, y)) * 0.5 * strength;
+                let dv = (height_at(x, y + 1) - height_at(x, y - 1)) * 0.5 * strength;
+                let normal = Vec3 { x: -du, y: -dv, z: 1.0 }.normalize();
+                data.push(0xFF000000 | (to_byte(normal.x) << 16) | (to_byte(normal.y) << 8) | to_byte(normal.z));
+            }
+        }
+        return Texture::new(data, self.width, self.height);
+    }
+
+
+    /// rebuilds levels 1..n, halving each axis until both reach 1
+    pub fn generate_mipmaps(&mut self, filter: MipFilter) {
+        self.mips.clear();
+
+        let mut width = self.width;
+        let mut height = self.height;
+        while width > 1 || height > 1 {
+            let (src, src_width, src_height) = self.level(self.mips.len());
+            let dst_width = (width / 2).max(1);
+            let dst_height = (height / 2).max(1);
+
+            let data = match filter {
+                MipFilter::Box => downsample_box(src, src_width, src_height, dst_width, dst_height),
+                MipFilter::Kaiser => downsample_kaiser(src, src_width, src_height, dst_width, dst_height),
+            };
+
+            self.mips.push(MipLevel { data, width: dst_width, height: dst_height });
+            width = dst_width;
+            height = dst_height;
+        }
+    }
+
+
+    /// mip level of detail from screen space texcoord derivatives
+    pub fn lod(&self, texcoord_dx: Vec2, texcoord_dy: Vec2) -> f32 {
+        let size = vec2!(self.width, self.height);
+        let texel_dx = texcoord_dx * size;
+        let texel_dy = texcoord_dy * size;
+        let rho_sq = texel_dx.dot(texel_dx).max(texel_dy.dot(texel_dy));
+        if rho_sq <= 0.0 || !rho_sq.is_finite() {
+            return 0.0;
+        }
+
+        // log2(sqrt(x)) == 0.5 * log2(x)
+        return 0.5 * rho_sq.log2();
+    }
+
+
+    pub fn sample_bilinear(&self, u: f32, v: f32, level: usize) -> u32 {
+        let (data, width, height) = self.level(level);
+
+        // texel centers sit at half-integers
+        let x = u.clamp(0.0, 1.0) * width as f32 - 0.5;
+        let y = v.clamp(0.0, 1.0) * height as f32 - 0.5;
+
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let fx = x - x0;
+        let fy = y - y0;
+
+        let x0 = (x0 as i32).clamp(0, width as i32 - 1) as u32;
+        let y0 = (y0 as i32).clamp(0, height as i32 - 1) as u32;
+        let x1 = (x0 + 1).min(width - 1);
+        let y1 = (y0 + 1).min(height - 1);
+
+        let c00 = data[(y0 * width + x0) as usize];
+        let c10 = data[(y0 * width + x1) as usize];
+        let c01 = data[(y1 * width + x0) as usize];
+        let c11 = data[(y1 * width + x1) as usize];
+
+        let top = lerp_argb(c00, c10, fx);
+        let bottom = lerp_argb(c01, c11, fx);
+        return lerp_argb(top, bottom, fy);
+    }
+
+
+    /// bilinear on the two nearest levels, blended by the fractional lod
+    pub fn sample_trilinear(&self, u: f32, v: f32, lod: f32) -> u32 {
+        let max_level = (self.mip_count() - 1) as f32;
+        let lod = lod.clamp(0.0, max_level);
+
+        let lower = lod.floor();
+        let blend = lod - lower;
+        let lower = lower as usize;
+
+        let near = self.sample_bilinear(u, v, lower);
+        if blend <= 0.0 {
+            return near;
+        }
+        let far = self.sample_bilinear(u, v, lower + 1);
+        return lerp_argb(near, far, blend);
+    }
+}
+
+
+
+fn unpack_argb(color: u32) -> [f32; 4] {
+    return [
+        ((color >> 24) & 0xFF) as f32,
+        ((color >> 16) & 0xFF) as f32,
+        ((color >> 8) & 0xFF) as f32,
+        (color & 0xFF) as f32,
+    ];
+}
+
+
+
+fn pack_argb(channels: [f32; 4]) -> u32 {
+    let [a, r, g, b] = channels.map(|c| c.round().clamp(0.0, 255.0) as u32);
+    return (a << 24) | (r << 16) | (g << 8) | b;
+}
+
+
+
+fn lerp_argb(a: u32, b: u32, t: f32) -> u32 {
+    let ca = unpack_argb(a);
+    let cb = unpack_argb(b);
+    let mut out = [0.0; 4];
+    for i in 0..4 {
+        out[i] = ca[i] + (cb[i] - ca[i]) * t;
+    }
+    return pack_argb(out);
+}
+
+
+
+// 2x2 average, with an odd source edge the last row/column averages 3 texels so none are dropped
+fn downsample_box(src: &[u32], src_width: u32, src_height: u32, dst_width: u32, dst_height: u32) -> Vec<u32> {
+    let mut dst = vec![0u32; (dst_width * dst_height) as usize];
+
+    for y in 0..dst_height {
+        let rows = box_taps(y, dst_height, src_height);
+        for x in 0..dst_width {
+            let columns = box_taps(x, dst_width, src_width);
+
+            let mut sum = [0.0; 4];
+            for sy in rows.clone() {
+                for sx in columns.clone() {
+                    let c = unpack_argb(src[(sy * src_width + sx) as usize]);
+                    for i in 0..4 {
+                        sum[i] += c[i];
+                    }
+                }
+            }
+
+            let count = (rows.len() * columns.len()) as f32;
+            dst[(y * dst_width + x) as usize] = pack_argb(sum.map(|c| c / count));
+        }
+    }
+
+    return dst;
+}
+
+
+
+// source rows or columns averaged into destination `index`, the last one also takes the odd one out
+fn box_taps(index: u32, dst_size: u32, src_size: u32) -> Range<u32> {
+    let start = (index * 2).min(src_size - 1);
+    let end = if index + 1 == dst_size { src_size } else { (index * 2 + 2).min(src_size) };
+    return start..end;
+}
+
+
+
+const KAISER_ALPHA: f32 = 4.0;
+const KAISER_RADIUS: i32 = 3;
+
+// modified bessel function of the first kind, order 0
+fn bessel_i0(x: f32) -> f32 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half_x = x * 0.5;
+    for k in 1..20 {
+        term *= (half_x / k as f32) * (half_x / k as f32);
+        sum += term;
+    }
+    return sum;
+}
+
+
+
+// kaiser windowed sinc weights for a 2:1 reduction, taps at source texel offsets
+fn kaiser_weights() -> Vec<f32> {
+    let taps = (2 * KAISER_RADIUS) as usize;
+    let mut weights = Vec::with_capacity(taps);
+    let denom = bessel_i0(KAISER_ALPHA);
+
+    for i in 0..taps {
+        // distance from the destination center, in source texels
+        let offset = i as f32 - KAISER_RADIUS as f32 + 0.5;
+        let t = offset / KAISER_RADIUS as f32;
+        let window = bessel_i0(KAISER_ALPHA * (1.0 - t * t).max(0.0).sqrt()) / denom;
+
+        let s = offset * 0.5 * std::f32::consts::PI;
+        let sinc = if s.abs() < 1e-6 { 1.0 } else { s.sin() / s };
+        weights.push(sinc * window);
+    }
+
+    let total: f32 = weights.iter().sum();
+    return weights.iter().map(|w| w / total).collect();
+}
+
+
+
+// separable kaiser filter, horizontal then vertical, clamped at the edges
+fn downsample_kaiser(src: &[u32], src_width: u32, src_height: u32, dst_width: u32, dst_height: u32) -> Vec<u32> {
+    let weights = kaiser_weights();
+    let src_channels: Vec<[f32; 4]> = src.iter().map(|&c| unpack_argb(c)).collect();
+
+    let mut horizontal = vec![[0.0f32; 4]; (dst_width * src_height) as usize];
+    for y in 0..src_height {
+        for x in 0..dst_width {
+            let mut sum = [0.0; 4];
+            for (i, w) in weights.iter().enumerate() {
+                let sx = if dst_width == src_width { x as i32 } else { x as i32 * 2 + i as i32 - KAISER_RADIUS + 1 };
+                let sx = sx.clamp(0, src_width as i32 - 1) as u32;
+                let c = src_channels[(y * src_width + sx) as usize];
+                for ch in 0..4 {
+                    sum[ch] += c[ch] * w;
+                }
+            }
+            horizontal[(y * dst_width + x) as usize] = sum;
+        }
+    }
+
+    let mut dst = vec![0u32; (dst_width * dst_height) as usize];
+    for y in 0..dst_height {
+        for x in 0..dst_width {
+            let mut sum = [0.0; 4];
+            for (i, w) in weights.iter().enumerate() {
+                let sy = if dst_height == src_height { y as i32 } else { y as i32 * 2 + i as i32 - KAISER_RADIUS + 1 };
+                let sy = sy.clamp(0, src_height as i32 - 1) as u32;
+                let c = horizontal[(sy * dst_width + x) as usize];
+                for ch in 0..4 {
+                    sum[ch] += c[ch] * w;
+                }
+            }
+            dst[(y * dst_width + x) as usize] = pack_argb(sum);
+        }
+    }
+
+    return dst;
+}
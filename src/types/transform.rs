@@ -9,6 +9,8 @@ pub struct Transform {
     pub yaw: f32,
     pub pitch: f32,
     pub position: Vec3,
+    // per axis, applied before the rotation. cameras ignore it
+    pub scale: Vec3,
     pub basis_vectors: (Vec3, Vec3, Vec3),
     pub inverse_basis_vectors: (Vec3, Vec3, Vec3)
 }
@@ -21,6 +23,7 @@ impl Transform {
             yaw: 0.0, 
             pitch: 0.0, 
             position: vec3!(0,0,0),
+            scale: vec3!(1,1,1),
             basis_vectors: get_basis_vectors(0.0, 0.0),
             inverse_basis_vectors: get_inverse_basis_vectors(0.0, 0.0),
         }
@@ -35,25 +38,46 @@ impl Transform {
     }
 
 
+    /// turns the view direction (-z) towards `target`, without roll
+    pub fn look_at(&mut self, target: Vec3) {
+        let direction = (target - self.position).normalize();
+        let pitch = (-direction.y).clamp(-1.0, 1.0).asin().to_degrees();
+        let yaw = (-direction.x).atan2(-direction.z).to_degrees();
+        self.set_rotation(pitch, yaw);
+    }
+
+
+    /// the direction the camera looks, -z in local space
+    pub fn forward(&self) -> Vec3 {
+        return self.basis_vectors.2 * -1.0;
+    }
+
+
     pub fn to_world_point(&self, point: Vec3) -> Vec3 {
         let (ihat, jhat, khat) = self.basis_vectors;
-        return transform_vector(ihat, jhat, khat, point) + self.position;
+        return transform_vector(ihat, jhat, khat, point * self.scale) + self.position;
     }
 
 
     pub fn to_local_point(&self, world_point: Vec3) -> Vec3{
         let (ihat, jhat, khat) = self.inverse_basis_vectors;
-        return transform_vector(ihat, jhat, khat, world_point - self.position);
+        return transform_vector(ihat, jhat, khat, world_point - self.position) / self.scale;
     }
 
     pub fn to_world_vector(&self, local_vector: Vec3) -> Vec3 {
         let (ihat, jhat, khat) = self.basis_vectors;
-        return transform_vector(ihat, jhat, khat, local_vector);
+        return transform_vector(ihat, jhat, khat, local_vector * self.scale);
+    }
+
+    /// normals scale inversely so they stay perpendicular to the surface under non-uniform scale, not normalized
+    pub fn to_world_normal(&self, local_normal: Vec3) -> Vec3 {
+        let (ihat, jhat, khat) = self.basis_vectors;
+        return transform_vector(ihat, jhat, khat, local_normal / self.scale);
     }
 
     pub fn to_local_vector(&self, world_vector: Vec3) -> Vec3 {
         let (ihat, jhat, khat) = self.inverse_basis_vectors;
-        return transform_vector(ihat, jhat, khat, world_vector);
+        return transform_vector(ihat, jhat, khat, world_vector) / self.scale;
     }
 }
 
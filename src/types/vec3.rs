@@ -115,6 +115,11 @@ impl Vec3 {
     }
 
 
+    pub fn length(self) -> f32 {
+        return self.dot(self).sqrt();
+    }
+
+
     pub fn normalize(self) -> Self {
         let len = (self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
         if len > 0.0 {
@@ -6,4 +6,12 @@ pub struct Vertex {
     pub position: Vec3,
     pub texcoord: Vec2,
     pub normal: Vec3,
+    // MikkTSpace tangent frame, bitangent = cross(normal, tangent) * bitangent_sign
+    pub tangent: Vec3,
+    pub bitangent_sign: f32,
+    // linear rgb in 0..1, white unless the file had vertex colors
+    pub color: Vec3,
+    // skinning influences, indices into the model's skeleton. all zero weights leave the vertex unskinned
+    pub joints: [u16; 4],
+    pub weights: [f32; 4],
 }
\ No newline at end of file
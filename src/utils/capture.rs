@@ -0,0 +1,237 @@
+use std::fs::File;
+use std::io::{BufWriter, Error, ErrorKind, Result, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::graphics::render_target::RenderTarget;
+use crate::utils::file_parser::encode_png;
+
+
+
+pub const DEFAULT_CAPTURE_DIRECTORY: &str = "./captures";
+pub const DEFAULT_CAPTURE_FPS: u32 = 60;
+
+
+
+/// what a recording is written as
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CaptureFormat {
+    // frame-000000.png, frame-000001.png, ... in a directory per recording
+    PngSequence,
+    // one uncompressed YUV4MPEG2 stream, 4:2:0 full range, playable with ffplay/mpv or fed to ffmpeg
+    Y4m,
+}
+
+
+
+impl CaptureFormat {
+    pub fn parse(name: &str) -> Option<CaptureFormat> {
+        match name {
+            "png" => return Some(CaptureFormat::PngSequence),
+            "y4m" => return Some(CaptureFormat::Y4m),
+            _ => return None,
+        }
+    }
+
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CaptureFormat::PngSequence => return "png",
+            CaptureFormat::Y4m => return "y4m",
+        }
+    }
+}
+
+
+
+/// screenshots and recordings of the render target, into timestamped files under `directory`.
+/// while recording, `frame_delta` gives the fixed time step to simulate so the video plays back at true speed
+pub struct Capture {
+    pub directory: PathBuf,
+    pub format: CaptureFormat,
+    pub fps: u32,
+    recording: Option<Recording>,
+}
+
+
+
+struct Recording {
+    path: PathBuf,
+    frames: u64,
+    width: u32,
+    height: u32,
+    // only for y4m, png sequences write a file per frame
+    stream: Option<BufWriter<File>>,
+}
+
+
+
+impl Capture {
+    pub fn new() -> Capture {
+        return Capture {
+            directory: PathBuf::from(DEFAULT_CAPTURE_DIRECTORY),
+            format: CaptureFormat::PngSequence,
+            fps: DEFAULT_CAPTURE_FPS,
+            recording: None,
+        };
+    }
+
+
+    pub fn is_recording(&self) -> bool {
+        return self.recording.is_some();
+    }
+
+
+    /// the simulated frame time while recording
+    pub fn frame_delta(&self) -> Option<f32> {
+        return self.recording.as_ref().map(|_| 1.0 / self.fps.max(1) as f32);
+    }
+
+
+    /// saves `target` as a png, returns where it went
+    pub fn screenshot(&self, target: &RenderTarget) -> Result<PathBuf> {
+        std::fs::create_dir_all(&self.directory)?;
+        let path = self.directory.join(format!("screenshot-{}.png", timestamp()));
+        std::fs::write(&path, encode_png(&target.color_buffer, target.width, target.height))?;
+        return Ok(path);
+    }
+
+
+    /// starts a new recording of frames the size of `target`, returns the file or directory it writes to
+    pub fn start_recording(&mut self, target: &RenderTarget) -> Result<PathBuf> {
+        self.stop_recording()?;
+        std::fs::create_dir_all(&self.directory)?;
+
+        let name = format!("recording-{}", timestamp());
+        let (path, stream) = match self.format {
+            CaptureFormat::PngSequence => {
+                let path = self.directory.join(name);
+                std::fs::create_dir_all(&path)?;
+                (path, None)
+            }
+            CaptureFormat::Y4m => {
+                let path = self.directory.join(format!("{}.y4m", name));
+                let mut stream = BufWriter::new(File::create(&path)?);
+                writeln!(stream, "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C420jpeg XCOLORRANGE=FULL", target.width, target.height, self.fps.max(1))?;
+                (path, Some(stream))
+            }
+        };
+
+        self.recording = Some(Recording { path: path.clone(), frames: 0, width: target.width, height: target.height, stream });
+        return Ok(path);
+    }
+
+
+    /// finishes the current recording, returns its path and frame count
+    pub fn stop_recording(&mut self) -> Result<Option<(PathBuf, u64)>> {
+        let Some(recording) = self.recording.take() else {
+            return Ok(None);
+        };
+        if let Some(mut stream) = recording.stream {
+            stream.flush()?;
+        }
+        return Ok(Some((recording.path, recording.frames)));
+    }
+
+
+    /// appends `target` to the recording, if there is one. a y4m stream can't change size,
+    /// so resizing the target mid-recording is an error that ends it
+    pub fn record_frame(&mut self, target: &RenderTarget) -> Result<()> {
+        let Some(recording) = &mut self.recording else {
+            return Ok(());
+        };
+
+        let result = match &mut recording.stream {
+            Some(_) if (target.width, target.height) != (recording.width, recording.height) => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("render target resized to {}x{} during a {}x{} y4m recording", target.width, target.height, recording.width, recording.height),
+            )),
+            Some(stream) => write_y4m_frame(stream, target),
+            None => {
+                let path = recording.path.join(format!("frame-{:06}.png", recording.frames));
+                std::fs::write(path, encode_png(&target.color_buffer, target.width, target.height))
+            }
+        };
+
+        if result.is_err() {
+            self.recording = None;
+            return result;
+        }
+        recording.frames += 1;
+        return Ok(());
+    }
+}
+
+
+
+// one FRAME of full range BT.601 4:2:0, chroma averaged over each 2x2 block
+fn write_y4m_frame(stream: &mut impl Write, target: &RenderTarget) -> Result<()> {
+    let (width, height) = (target.width as usize, target.height as usize);
+    let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+    let rgb = |pixel: u32| ((pixel >> 16 & 0xFF) as f32, (pixel >> 8 & 0xFF) as f32, (pixel & 0xFF) as f32);
+
+    let mut luma: Vec<u8> = Vec::with_capacity(width * height);
+    for &pixel in &target.color_buffer {
+        let (r, g, b) = rgb(pixel);
+        luma.push((0.299 * r + 0.587 * g + 0.114 * b).round() as u8);
+    }
+
+    let mut cb: Vec<u8> = Vec::with_capacity(chroma_width * chroma_height);
+    let mut cr: Vec<u8> = Vec::with_capacity(chroma_width * chroma_height);
+    for cy in 0..chroma_height {
+        for cx in 0..chroma_width {
+            let (mut r, mut g, mut b, mut count) = (0.0, 0.0, 0.0, 0.0);
+            for y in cy * 2..(cy * 2 + 2).min(height) {
+                for x in cx * 2..(cx * 2 + 2).min(width) {
+                    let (pr, pg, pb) = rgb(target.color_buffer[y * width + x]);
+                    (r, g, b, count) = (r + pr, g + pg, b + pb, count + 1.0);
+                }
+            }
+            let (r, g, b) = (r / count, g / count, b / count);
+            cb.push((128.0 - 0.168736 * r - 0.331264 * g + 0.5 * b).round().clamp(0.0, 255.0) as u8);
+            cr.push((128.0 + 0.5 * r - 0.418688 * g - 0.081312 * b).round().clamp(0.0, 255.0) as u8);
+        }
+    }
+
+    stream.write_all(b"FRAME\n")?;
+    stream.write_all(&luma)?;
+    stream.write_all(&cb)?;
+    stream.write_all(&cr)?;
+    return Ok(());
+}
+
+
+
+// UTC "20261019-142305-123", sorts in capture order
+fn timestamp() -> String {
+    let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((seconds / 86400) as i64);
+    let time_of_day = seconds % 86400;
+    return format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}-{:03}",
+        year,
+        month,
+        day,
+        time_of_day / 3600,
+        time_of_day / 60 % 60,
+        time_of_day % 60,
+        since_epoch.subsec_millis(),
+    );
+}
+
+
+
+// days since 1970-01-01 to (year, month, day), Howard Hinnant's algorithm
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    return (year, month, day);
+}
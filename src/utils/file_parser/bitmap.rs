@@ -0,0 +1,372 @@
+use crate::Texture;
+use super::error::{ByteReader, ImageError};
+
+
+
+const BI_RGB: u32 = 0;
+const BI_RLE8: u32 = 1;
+const BI_RLE4: u32 = 2;
+const BI_BITFIELDS: u32 = 3;
+const BI_ALPHABITFIELDS: u32 = 6;
+
+const FILE_HEADER_SIZE: usize = 14;
+
+// BITMAPCOREHEADER, BITMAPINFOHEADER, V2, V3, V4 and V5
+const SUPPORTED_HEADER_SIZES: [u32; 6] = [12, 40, 52, 56, 108, 124];
+
+// guards the u32 pixel count and keeps allocations sane
+const MAX_PIXELS: i64 = 1 << 28;
+
+
+
+#[derive(Debug, Clone, Copy)]
+struct ChannelMasks {
+    red: u32,
+    green: u32,
+    blue: u32,
+    alpha: u32,
+}
+
+
+
+struct BitmapInfo {
+    width: u32,
+    height: u32,
+    top_down: bool,
+    bits_per_pixel: u16,
+    compression: u32,
+    masks: ChannelMasks,
+    // alpha was not declared by the file, a fully transparent result means "ignore alpha"
+    implicit_alpha: bool,
+    palette: Vec<u32>,
+    pixel_offset: usize,
+}
+
+
+
+pub fn read_bitmap(path: &str) -> Result<Texture, ImageError> {
+    let bytes = std::fs::read(path)?;
+    return decode_bitmap(&bytes);
+}
+
+
+
+pub fn decode_bitmap(bytes: &[u8]) -> Result<Texture, ImageError> {
+    let reader = ByteReader::new(bytes);
+    let info = parse_headers(&reader)?;
+
+    let mut texture_data = match info.compression {
+        BI_RLE8 | BI_RLE4 => decode_rle(&reader, &info)?,
+        _ => decode_uncompressed(&reader, &info)?,
+    };
+
+    if info.implicit_alpha && texture_data.iter().all(|c| c >> 24 == 0) {
+        for c in texture_data.iter_mut() {
+            *c |= 0xFF000000;
+        }
+    }
+
+    return Ok(Texture::new(texture_data, info.width, info.height));
+}
+
+
+
+fn parse_headers(reader: &ByteReader) -> Result<BitmapInfo, ImageError> {
+    if reader.slice(0, 2)? != b"BM" {
+        return Err(ImageError::InvalidSignature);
+    }
+
+    let pixel_offset = reader.u32_le(10)?;
+    let header_size = reader.u32_le(FILE_HEADER_SIZE)?;
+    if !SUPPORTED_HEADER_SIZES.contains(&header_size) {
+        return Err(ImageError::UnsupportedHeaderSize(header_size));
+    }
+
+    let (width, height, planes, bits_per_pixel, compression, colors_used) = if header_size == 12 {
+        (
+            reader.u16_le(18)? as i64,
+            reader.u16_le(20)? as i64,
+            reader.u16_le(22)?,
+            reader.u16_le(24)?,
+            BI_RGB,
+            0,
+        )
+    } else {
+        (
+            reader.i32_le(18)? as i64,
+            reader.i32_le(22)? as i64,
+            reader.u16_le(26)?,
+            reader.u16_le(28)?,
+            reader.u32_le(30)?,
+            reader.u32_le(46)?,
+        )
+    };
+
+    if width <= 0 || height == 0 || width * height.abs() > MAX_PIXELS {
+        return Err(ImageError::InvalidDimensions { width, height });
+    }
+    if planes != 1 {
+        return Err(ImageError::InvalidPlanes(planes));
+    }
+    if ![1, 4, 8, 16, 24, 32].contains(&bits_per_pixel) {
+        return Err(ImageError::UnsupportedBitDepth(bits_per_pixel));
+    }
+
+    let top_down = height < 0;
+    let bitfields = compression == BI_BITFIELDS || compression == BI_ALPHABITFIELDS;
+    match compression {
+        BI_RGB => {}
+        BI_RLE8 if bits_per_pixel != 8 => return Err(ImageError::CompressionBitDepthMismatch { compression, bits_per_pixel }),
+        BI_RLE4 if bits_per_pixel != 4 => return Err(ImageError::CompressionBitDepthMismatch { compression, bits_per_pixel }),
+        BI_RLE8 | BI_RLE4 if top_down => return Err(ImageError::TopDownCompressed),
+        BI_RLE8 | BI_RLE4 => {}
+        BI_BITFIELDS | BI_ALPHABITFIELDS if bits_per_pixel != 16 && bits_per_pixel != 32 => {
+            return Err(ImageError::CompressionBitDepthMismatch { compression, bits_per_pixel })
+        }
+        BI_BITFIELDS | BI_ALPHABITFIELDS => {}
+        _ => return Err(ImageError::UnsupportedCompression(compression)),
+    }
+
+    // masks live at the same offset whether inside a V2+ header or trailing a 40 byte one
+    let masks_offset = FILE_HEADER_SIZE + 40;
+    let has_alpha_mask = header_size >= 56 || compression == BI_ALPHABITFIELDS;
+    let mut implicit_alpha = false;
+
+    let masks = if bitfields {
+        let masks = ChannelMasks {
+            red: reader.u32_le(masks_offset)?,
+            green: reader.u32_le(masks_offset + 4)?,
+            blue: reader.u32_le(masks_offset + 8)?,
+            alpha: if has_alpha_mask { reader.u32_le(masks_offset + 12)? } else { 0 },
+        };
+        validate_masks(masks, bits_per_pixel)?;
+        masks
+    } else if bits_per_pixel == 16 {
+        // X1R5G5B5
+        ChannelMasks { red: 0x7C00, green: 0x03E0, blue: 0x001F, alpha: 0 }
+    } else {
+        let declared_alpha = if header_size >= 56 { reader.u32_le(masks_offset + 12)? } else { 0 };
+        let alpha = if declared_alpha != 0 { declared_alpha } else { 0xFF000000 };
+        implicit_alpha = declared_alpha == 0;
+        let masks = ChannelMasks { red: 0x00FF0000, green: 0x0000FF00, blue: 0x000000FF, alpha };
+        if bits_per_pixel == 32 {
+            validate_masks(masks, bits_per_pixel)?;
+        }
+        masks
+    };
+
+    // palette follows the header, and the masks when they trail a 40 byte header
+    let mut palette_offset = FILE_HEADER_SIZE + header_size as usize;
+    if header_size == 40 && bitfields {
+        palette_offset += if compression == BI_ALPHABITFIELDS { 16 } else { 12 };
+    }
+
+    let mut palette = Vec::new();
+    if bits_per_pixel <= 8 {
+        let max_entries = 1u32 << bits_per_pixel;
+        let entries = if colors_used == 0 { max_entries } else { colors_used };
+        if entries > max_entries {
+            return Err(ImageError::PaletteTooLarge { entries, max: max_entries });
+        }
+
+        let entry_size = if header_size == 12 { 3 } else { 4 };
+        let palette_bytes = reader.slice(palette_offset, entries as usize * entry_size)?;
+        for entry in palette_bytes.chunks_exact(entry_size) {
+            let (b, g, r) = (entry[0] as u32, entry[1] as u32, entry[2] as u32);
+            palette.push(0xFF000000 | (r << 16) | (g << 8) | b);
+        }
+    }
+
+    if pixel_offset as usize >= reader.bytes.len() {
+        return Err(ImageError::InvalidPixelOffset(pixel_offset));
+    }
+
+    return Ok(BitmapInfo {
+        width: width as u32,
+        height: height.unsigned_abs() as u32,
+        top_down,
+        bits_per_pixel,
+        compression,
+        masks,
+        implicit_alpha,
+        palette,
+        pixel_offset: pixel_offset as usize,
+    });
+}
+
+
+
+fn validate_masks(masks: ChannelMasks, bits_per_pixel: u16) -> Result<(), ImageError> {
+    let invalid = ImageError::InvalidBitfields { red: masks.red, green: masks.green, blue: masks.blue, alpha: masks.alpha };
+    let pixel_bits = if bits_per_pixel == 32 { u32::MAX } else { (1u32 << bits_per_pixel) - 1 };
+
+    let channels = [masks.red, masks.green, masks.blue, masks.alpha];
+    if masks.red == 0 || masks.green == 0 || masks.blue == 0 {
+        return Err(invalid);
+    }
+
+    let mut seen = 0u32;
+    for mask in channels {
+        if mask == 0 {
+            continue;
+        }
+        let contiguous = (mask >> mask.trailing_zeros()).trailing_ones() == mask.count_ones();
+        if !contiguous || mask & !pixel_bits != 0 || mask & seen != 0 {
+            return Err(invalid);
+        }
+        seen |= mask;
+    }
+
+    return Ok(());
+}
+
+
+
+// extracts a masked channel and rescales it to 8 bits
+fn extract_channel(pixel: u32, mask: u32) -> u32 {
+    if mask == 0 {
+        return 0;
+    }
+    let value = (pixel & mask) >> mask.trailing_zeros();
+    let max = ((1u64 << mask.count_ones()) - 1) as u32;
+    return ((value as u64 * 255 + max as u64 / 2) / max as u64) as u32;
+}
+
+
+
+fn apply_masks(pixel: u32, masks: ChannelMasks) -> u32 {
+    let r = extract_channel(pixel, masks.red);
+    let g = extract_channel(pixel, masks.green);
+    let b = extract_channel(pixel, masks.blue);
+    let a = if masks.alpha == 0 { 0xFF } else { extract_channel(pixel, masks.alpha) };
+    return (a << 24) | (r << 16) | (g << 8) | b;
+}
+
+
+
+fn palette_lookup(palette: &[u32], index: u32) -> Result<u32, ImageError> {
+    return palette
+        .get(index as usize)
+        .copied()
+        .ok_or(ImageError::PaletteIndexOutOfRange { index, palette_len: palette.len() });
+}
+
+
+
+fn decode_uncompressed(reader: &ByteReader, info: &BitmapInfo) -> Result<Vec<u32>, ImageError> {
+    let width = info.width as usize;
+    let height = info.height as usize;
+    let bpp = info.bits_per_pixel as usize;
+
+    // rows are padded to 4 bytes
+    let stride = (width * bpp).div_ceil(32) * 4;
+    let pixels = reader.slice(info.pixel_offset, stride * height)?;
+
+    let mut texture_data = vec![0u32; width * height];
+
+    for row in 0..height {
+        let row_bytes = &pixels[row * stride..(row + 1) * stride];
+        let y_tex = if info.top_down { row } else { height - 1 - row };
+        let tex_row = &mut texture_data[y_tex * width..(y_tex + 1) * width];
+
+        for (x, texel) in tex_row.iter_mut().enumerate() {
+            *texel = match bpp {
+                1 | 4 | 8 => {
+                    let bit = x * bpp;
+                    let shift = 8 - bpp - bit % 8;
+                    let index = (row_bytes[bit / 8] >> shift) & ((1u16 << bpp) - 1) as u8;
+                    palette_lookup(&info.palette, index as u32)?
+                }
+                16 => {
+                    let pixel = u16::from_le_bytes([row_bytes[x * 2], row_bytes[x * 2 + 1]]) as u32;
+                    apply_masks(pixel, info.masks)
+                }
+                24 => {
+                    let (b, g, r) = (row_bytes[x * 3] as u32, row_bytes[x * 3 + 1] as u32, row_bytes[x * 3 + 2] as u32);
+                    0xFF000000 | (r << 16) | (g << 8) | b
+                }
+                _ => {
+                    let pixel = u32::from_le_bytes(row_bytes[x * 4..x * 4 + 4].try_into().unwrap());
+                    apply_masks(pixel, info.masks)
+                }
+            };
+        }
+    }
+
+    return Ok(texture_data);
+}
+
+
+
+// RLE bitmaps are always bottom-up, pixels skipped by deltas stay transparent
+fn decode_rle(reader: &ByteReader, info: &BitmapInfo) -> Result<Vec<u32>, ImageError> {
+    let width = info.width as usize;
+    let height = info.height as usize;
+    let rle4 = info.compression == BI_RLE4;
+
+    let mut texture_data = vec![0u32; width * height];
+    let mut pos = info.pixel_offset;
+    let mut x = 0usize;
+    let mut y = 0usize;
+
+    let mut put = |x: usize, y: usize, index: u8| -> Result<(), ImageError> {
+        if x < width && y < height {
+            texture_data[(height - 1 - y) * width + x] = palette_lookup(&info.palette, index as u32)?;
+        }
+        return Ok(());
+    };
+
+    // a missing end-of-bitmap marker is common, running out of data ends the image
+    while pos + 1 < reader.bytes.len() && y < height {
+        let count = reader.u8(pos)? as usize;
+        let code = reader.u8(pos + 1)?;
+        pos += 2;
+
+        if count > 0 {
+            for i in 0..count {
+                let index = if !rle4 { code } else if i % 2 == 0 { code >> 4 } else { code & 0x0F };
+                put(x, y, index)?;
+                x += 1;
+            }
+            continue;
+        }
+
+        match code {
+            0 => {
+                x = 0;
+                y += 1;
+            }
+            1 => break,
+            2 => {
+                let delta = reader.slice(pos, 2).map_err(|_| ImageError::CorruptRle { offset: pos, reason: "delta escape cut short" })?;
+                x += delta[0] as usize;
+                y += delta[1] as usize;
+                pos += 2;
+            }
+            literal_count => {
+                let literal_count = literal_count as usize;
+                let byte_count = if rle4 { literal_count.div_ceil(2) } else { literal_count };
+                let literal = reader
+                    .slice(pos, byte_count)
+                    .map_err(|_| ImageError::CorruptRle { offset: pos, reason: "absolute run extends past end of data" })?;
+
+                for i in 0..literal_count {
+                    let index = if !rle4 {
+                        literal[i]
+                    } else if i % 2 == 0 {
+                        literal[i / 2] >> 4
+                    } else {
+                        literal[i / 2] & 0x0F
+                    };
+                    put(x, y, index)?;
+                    x += 1;
+                }
+
+                // absolute runs are padded to a word boundary
+                pos += byte_count + byte_count % 2;
+            }
+        }
+    }
+
+    return Ok(texture_data);
+}
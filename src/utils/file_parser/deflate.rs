@@ -0,0 +1,226 @@
+use super::inflate::{adler32, DIST_BASE, DIST_EXTRA, LENGTH_BASE, LENGTH_EXTRA};
+
+
+
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+
+const HASH_BITS: u32 = 15;
+// candidates looked at per position, more finds longer matches but runs slower
+const MAX_CHAIN: usize = 64;
+// a match at least this long is taken without looking further
+const GOOD_MATCH: usize = 32;
+
+// keeps a block's symbol buffer bounded, each block restarts the fixed codes
+const BLOCK_SYMBOLS: usize = 1 << 16;
+
+const STORED_BLOCK_SIZE: usize = 65535;
+
+
+
+/// zlib stream (RFC 1950) around deflate (RFC 1951) using the fixed huffman codes and hash-chain matching.
+/// not as small as zlib's output, but images with flat areas shrink a lot
+pub(crate) fn zlib_compress(data: &[u8]) -> Vec<u8> {
+    let mut bits = BitWriter::new();
+    // 32k window, default compression, header check bits so that 0x78DA is divisible by 31
+    bits.out.extend_from_slice(&[0x78, 0xDA]);
+
+    let symbols = lz77(data);
+    if symbols.is_empty() {
+        write_block(&mut bits, &[], true);
+    }
+    let block_count = symbols.len().div_ceil(BLOCK_SYMBOLS);
+    for (i, block) in symbols.chunks(BLOCK_SYMBOLS).enumerate() {
+        write_block(&mut bits, block, i + 1 == block_count);
+    }
+
+    let mut out = bits.finish();
+    // noise doesn't compress, storing it as is avoids the fixed codes making it bigger
+    let stored_size = 2 + data.len() + data.len().div_ceil(STORED_BLOCK_SIZE).max(1) * 5;
+    if out.len() > stored_size {
+        out.truncate(2);
+        write_stored(&mut out, data);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    return out;
+}
+
+
+
+fn write_stored(out: &mut Vec<u8>, data: &[u8]) {
+    if data.is_empty() {
+        out.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+        return;
+    }
+    let block_count = data.len().div_ceil(STORED_BLOCK_SIZE);
+    for (i, block) in data.chunks(STORED_BLOCK_SIZE).enumerate() {
+        // final flag, block type 0, then padding to the byte boundary
+        out.push((i + 1 == block_count) as u8);
+        out.extend_from_slice(&(block.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+}
+
+
+
+#[derive(Debug, Clone, Copy)]
+enum Symbol {
+    Literal(u8),
+    Match { length: u16, distance: u16 },
+}
+
+
+
+struct BitWriter {
+    out: Vec<u8>,
+    bit_buf: u64,
+    bit_count: u32,
+}
+
+
+
+impl BitWriter {
+    fn new() -> Self {
+        return BitWriter { out: Vec::new(), bit_buf: 0, bit_count: 0 };
+    }
+
+
+    // least significant bit first, how deflate packs everything but huffman codes
+    fn bits(&mut self, value: u32, count: u32) {
+        self.bit_buf |= (value as u64) << self.bit_count;
+        self.bit_count += count;
+        while self.bit_count >= 8 {
+            self.out.push(self.bit_buf as u8);
+            self.bit_buf >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+
+
+    // huffman codes go most significant bit first
+    fn code(&mut self, code: u32, length: u32) {
+        self.bits(code.reverse_bits() >> (32 - length), length);
+    }
+
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bit_count > 0 {
+            self.out.push(self.bit_buf as u8);
+        }
+        return self.out;
+    }
+}
+
+
+
+fn lz77(data: &[u8]) -> Vec<Symbol> {
+    let mut symbols: Vec<Symbol> = Vec::with_capacity(data.len() / 2);
+    // most recent position for each hash, and the previous position with the same hash for each window slot
+    let mut head: Vec<i32> = vec![-1; 1 << HASH_BITS];
+    let mut prev: Vec<i32> = vec![-1; WINDOW_SIZE];
+
+    let hash = |pos: usize| -> usize {
+        let key = (data[pos] as u32) << 16 | (data[pos + 1] as u32) << 8 | data[pos + 2] as u32;
+        return (key.wrapping_mul(0x9E3779B1) >> (32 - HASH_BITS)) as usize;
+    };
+    let insert = |pos: usize, head: &mut [i32], prev: &mut [i32]| {
+        if pos + MIN_MATCH <= data.len() {
+            let h = hash(pos);
+            prev[pos % WINDOW_SIZE] = head[h];
+            head[h] = pos as i32;
+        }
+    };
+
+    let mut pos = 0;
+    while pos < data.len() {
+        let (length, distance) = longest_match(data, pos, &head, &prev, pos + MIN_MATCH <= data.len(), hash);
+        if length >= MIN_MATCH {
+            symbols.push(Symbol::Match { length: length as u16, distance: distance as u16 });
+            for p in pos..pos + length {
+                insert(p, &mut head, &mut prev);
+            }
+            pos += length;
+        } else {
+            symbols.push(Symbol::Literal(data[pos]));
+            insert(pos, &mut head, &mut prev);
+            pos += 1;
+        }
+    }
+    return symbols;
+}
+
+
+
+fn longest_match(data: &[u8], pos: usize, head: &[i32], prev: &[i32], hashable: bool, hash: impl Fn(usize) -> usize) -> (usize, usize) {
+    if !hashable {
+        return (0, 0);
+    }
+    let max_length = MAX_MATCH.min(data.len() - pos);
+    let mut best = (0, 0);
+    let mut candidate = head[hash(pos)];
+
+    for _ in 0..MAX_CHAIN {
+        if candidate < 0 {
+            break;
+        }
+        let start = candidate as usize;
+        let distance = pos - start;
+        if distance > WINDOW_SIZE - 1 || distance == 0 {
+            break;
+        }
+
+        let length = data[start..start + max_length].iter().zip(&data[pos..pos + max_length]).take_while(|(a, b)| a == b).count();
+        if length > best.0 {
+            best = (length, distance);
+            if length >= GOOD_MATCH.min(max_length) {
+                break;
+            }
+        }
+
+        let next = prev[start % WINDOW_SIZE];
+        // a slot overwritten by a newer position breaks the chain
+        if next >= candidate {
+            break;
+        }
+        candidate = next;
+    }
+    return best;
+}
+
+
+
+fn write_block(bits: &mut BitWriter, symbols: &[Symbol], last: bool) {
+    bits.bits(last as u32, 1);
+    bits.bits(1, 2); // fixed huffman codes
+
+    for &symbol in symbols {
+        match symbol {
+            Symbol::Literal(byte) => write_literal(bits, byte as u16),
+            Symbol::Match { length, distance } => {
+                let code = LENGTH_BASE.partition_point(|&base| base <= length) - 1;
+                write_literal(bits, 257 + code as u16);
+                bits.bits((length - LENGTH_BASE[code]) as u32, LENGTH_EXTRA[code] as u32);
+
+                let code = DIST_BASE.partition_point(|&base| base <= distance) - 1;
+                bits.code(code as u32, 5);
+                bits.bits((distance - DIST_BASE[code]) as u32, DIST_EXTRA[code] as u32);
+            }
+        }
+    }
+    write_literal(bits, 256);
+}
+
+
+
+// the fixed literal/length code from RFC 1951 section 3.2.6
+fn write_literal(bits: &mut BitWriter, value: u16) {
+    let value = value as u32;
+    match value {
+        0..=143 => bits.code(0x30 + value, 8),
+        144..=255 => bits.code(0x190 + value - 144, 9),
+        256..=279 => bits.code(value - 256, 7),
+        _ => bits.code(0xC0 + value - 280, 8),
+    }
+}
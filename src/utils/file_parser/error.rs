@@ -0,0 +1,504 @@
+use std::fmt;
+use std::io::{Error, ErrorKind};
+
+use crate::utils::json::JsonError;
+
+
+
+#[derive(Debug)]
+pub enum ImageError {
+    Io(Error),
+    InvalidSignature,
+    Truncated { needed: usize, available: usize },
+    UnsupportedHeaderSize(u32),
+    InvalidDimensions { width: i64, height: i64 },
+    InvalidPlanes(u16),
+    UnsupportedBitDepth(u16),
+    UnsupportedCompression(u32),
+    CompressionBitDepthMismatch { compression: u32, bits_per_pixel: u16 },
+    TopDownCompressed,
+    InvalidBitfields { red: u32, green: u32, blue: u32, alpha: u32 },
+    PaletteTooLarge { entries: u32, max: u32 },
+    PaletteIndexOutOfRange { index: u32, palette_len: usize },
+    InvalidPixelOffset(u32),
+    CorruptRle { offset: usize, reason: &'static str },
+    UnsupportedImageType(u8),
+    InvalidColorMapType(u8),
+    MissingColorMap,
+    UnsupportedColorMapDepth(u8),
+    CrcMismatch { chunk: [u8; 4], stored: u32, computed: u32 },
+    AdlerMismatch { stored: u32, computed: u32 },
+    ChunkOrder(&'static str),
+    MissingChunk(&'static str),
+    InvalidChunkLength { chunk: [u8; 4], length: usize },
+    UnsupportedCriticalChunk([u8; 4]),
+    InvalidColorType(u8),
+    ColorTypeBitDepthMismatch { color_type: u8, bit_depth: u8 },
+    UnsupportedFilterMethod(u8),
+    UnsupportedInterlace(u8),
+    InvalidFilterType(u8),
+    ImageDataSizeMismatch { expected: usize, actual: usize },
+    Inflate(&'static str),
+    UnknownFormat(String),
+}
+
+
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageError::Io(err) => write!(f, "I/O error: {}", err),
+            ImageError::InvalidSignature => write!(f, "missing or invalid file signature"),
+            ImageError::Truncated { needed, available } => {
+                write!(f, "file truncated: needed {} bytes, only {} available", needed, available)
+            }
+            ImageError::UnsupportedHeaderSize(size) => write!(f, "unsupported header size: {} bytes", size),
+            ImageError::InvalidDimensions { width, height } => write!(f, "invalid image dimensions: {}x{}", width, height),
+            ImageError::InvalidPlanes(planes) => write!(f, "invalid color plane count: {} (must be 1)", planes),
+            ImageError::UnsupportedBitDepth(bpp) => write!(f, "unsupported bits per pixel: {}", bpp),
+            ImageError::UnsupportedCompression(method) => write!(f, "unsupported compression method: {}", method),
+            ImageError::CompressionBitDepthMismatch { compression, bits_per_pixel } => {
+                write!(f, "compression method {} cannot be used with {} bits per pixel", compression, bits_per_pixel)
+            }
+            ImageError::TopDownCompressed => write!(f, "top-down images cannot be compressed"),
+            ImageError::InvalidBitfields { red, green, blue, alpha } => write!(
+                f,
+                "invalid channel bitfields: red {:#010x}, green {:#010x}, blue {:#010x}, alpha {:#010x}",
+                red, green, blue, alpha
+            ),
+            ImageError::PaletteTooLarge { entries, max } => write!(f, "palette has {} entries, at most {} allowed", entries, max),
+            ImageError::PaletteIndexOutOfRange { index, palette_len } => {
+                write!(f, "palette index {} out of range for palette of {} entries", index, palette_len)
+            }
+            ImageError::InvalidPixelOffset(offset) => write!(f, "pixel data offset {} lies outside the file", offset),
+            ImageError::CorruptRle { offset, reason } => write!(f, "corrupt RLE data at byte {}: {}", offset, reason),
+            ImageError::UnsupportedImageType(image_type) => write!(f, "unsupported image type: {}", image_type),
+            ImageError::InvalidColorMapType(map_type) => write!(f, "invalid color map type: {}", map_type),
+            ImageError::MissingColorMap => write!(f, "color-mapped image has no color map"),
+            ImageError::UnsupportedColorMapDepth(depth) => write!(f, "unsupported color map entry size: {} bits", depth),
+            ImageError::CrcMismatch { chunk, stored, computed } => write!(
+                f,
+                "CRC mismatch in {} chunk: stored {:#010x}, computed {:#010x}",
+                String::from_utf8_lossy(chunk), stored, computed
+            ),
+            ImageError::AdlerMismatch { stored, computed } => {
+                write!(f, "adler32 mismatch: stored {:#010x}, computed {:#010x}", stored, computed)
+            }
+            ImageError::ChunkOrder(reason) => write!(f, "invalid chunk layout: {}", reason),
+            ImageError::MissingChunk(chunk) => write!(f, "missing required {} chunk", chunk),
+            ImageError::InvalidChunkLength { chunk, length } => {
+                write!(f, "invalid length {} for {} chunk", length, String::from_utf8_lossy(chunk))
+            }
+            ImageError::UnsupportedCriticalChunk(chunk) => write!(f, "unsupported critical chunk: {}", String::from_utf8_lossy(chunk)),
+            ImageError::InvalidColorType(color_type) => write!(f, "invalid color type: {}", color_type),
+            ImageError::ColorTypeBitDepthMismatch { color_type, bit_depth } => {
+                write!(f, "bit depth {} is not allowed for color type {}", bit_depth, color_type)
+            }
+            ImageError::UnsupportedFilterMethod(method) => write!(f, "unsupported filter method: {}", method),
+            ImageError::UnsupportedInterlace(method) => write!(f, "unsupported interlace method: {}", method),
+            ImageError::InvalidFilterType(filter) => write!(f, "invalid scanline filter type: {}", filter),
+            ImageError::ImageDataSizeMismatch { expected, actual } => {
+                write!(f, "decompressed image data is {} bytes, expected at least {}", actual, expected)
+            }
+            ImageError::Inflate(reason) => write!(f, "decompression failed: {}", reason),
+            ImageError::UnknownFormat(path) => write!(f, "unrecognised image format: {}", path),
+        }
+    }
+}
+
+
+
+impl std::error::Error for ImageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImageError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+
+
+impl From<Error> for ImageError {
+    fn from(err: Error) -> Self {
+        return ImageError::Io(err);
+    }
+}
+
+
+
+// lets callers returning io::Result keep using `?`
+impl From<ImageError> for Error {
+    fn from(err: ImageError) -> Self {
+        match err {
+            ImageError::Io(err) => err,
+            other => Error::new(ErrorKind::InvalidData, other),
+        }
+    }
+}
+
+
+
+/// parse failure with the 1-based line and column it was found at, 0 when unknown
+#[derive(Debug)]
+pub struct ObjError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ObjErrorKind,
+}
+
+
+
+#[derive(Debug)]
+pub enum ObjErrorKind {
+    Io(Error),
+    MissingArgument { statement: String, position: usize },
+    InvalidNumber(String),
+    InvalidIndex(String),
+    IndexOutOfRange { element: &'static str, index: i64, count: usize },
+}
+
+
+
+impl ObjError {
+    pub fn new(line: usize, column: usize, kind: ObjErrorKind) -> Self {
+        return Self { line, column, kind };
+    }
+}
+
+
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line > 0 {
+            write!(f, "line {}, column {}: ", self.line, self.column)?;
+        }
+        match &self.kind {
+            ObjErrorKind::Io(err) => write!(f, "I/O error: {}", err),
+            ObjErrorKind::MissingArgument { statement, position } => {
+                write!(f, "'{}' statement is missing argument {}", statement, position)
+            }
+            ObjErrorKind::InvalidNumber(token) => write!(f, "invalid number '{}'", token),
+            ObjErrorKind::InvalidIndex(token) => write!(f, "invalid index '{}'", token),
+            ObjErrorKind::IndexOutOfRange { element, index, count } => {
+                write!(f, "{} index {} out of range, {} defined so far", element, index, count)
+            }
+        }
+    }
+}
+
+
+
+impl std::error::Error for ObjError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ObjErrorKind::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+
+
+impl From<ObjError> for Error {
+    fn from(err: ObjError) -> Self {
+        match err.kind {
+            ObjErrorKind::Io(io_err) if err.line == 0 => io_err,
+            _ => Error::new(ErrorKind::InvalidData, err),
+        }
+    }
+}
+
+
+
+#[derive(Debug)]
+pub enum GltfError {
+    Io(Error),
+    Json(JsonError),
+    InvalidGlb(&'static str),
+    UnsupportedVersion(String),
+    UnsupportedExtension(String),
+    // `object` names the offending element, e.g. "accessors[3]"
+    MissingField { object: String, field: &'static str },
+    InvalidReference { object: String, field: &'static str, index: usize },
+    InvalidAccessor { index: usize, reason: String },
+    InvalidBuffer { index: usize, reason: String },
+}
+
+
+
+impl fmt::Display for GltfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GltfError::Io(err) => write!(f, "I/O error: {}", err),
+            GltfError::Json(err) => write!(f, "invalid JSON: {}", err),
+            GltfError::InvalidGlb(reason) => write!(f, "invalid GLB container: {}", reason),
+            GltfError::UnsupportedVersion(version) => write!(f, "unsupported glTF version {}", version),
+            GltfError::UnsupportedExtension(name) => write!(f, "required extension {} is not supported", name),
+            GltfError::MissingField { object, field } => write!(f, "{} is missing '{}'", object, field),
+            GltfError::InvalidReference { object, field, index } => {
+                write!(f, "{}.{} refers to missing index {}", object, field, index)
+            }
+            GltfError::InvalidAccessor { index, reason } => write!(f, "accessors[{}]: {}", index, reason),
+            GltfError::InvalidBuffer { index, reason } => write!(f, "buffers[{}]: {}", index, reason),
+        }
+    }
+}
+
+
+
+impl std::error::Error for GltfError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GltfError::Io(err) => Some(err),
+            GltfError::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+
+
+impl From<Error> for GltfError {
+    fn from(err: Error) -> Self {
+        return GltfError::Io(err);
+    }
+}
+
+
+
+impl From<JsonError> for GltfError {
+    fn from(err: JsonError) -> Self {
+        return GltfError::Json(err);
+    }
+}
+
+
+
+impl From<GltfError> for Error {
+    fn from(err: GltfError) -> Self {
+        match err {
+            GltfError::Io(io_err) => io_err,
+            err => Error::new(ErrorKind::InvalidData, err),
+        }
+    }
+}
+
+
+
+#[derive(Debug)]
+pub enum StlError {
+    Io(Error),
+    // binary file shorter than its triangle count says
+    Truncated { triangles: u32, expected: usize, actual: usize },
+    Syntax { line: usize, message: String },
+}
+
+
+
+impl fmt::Display for StlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StlError::Io(err) => write!(f, "I/O error: {}", err),
+            StlError::Truncated { triangles, expected, actual } => {
+                write!(f, "binary STL with {} triangles needs {} bytes, file has {}", triangles, expected, actual)
+            }
+            StlError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+
+
+impl std::error::Error for StlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StlError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+
+
+impl From<Error> for StlError {
+    fn from(err: Error) -> Self {
+        return StlError::Io(err);
+    }
+}
+
+
+
+impl From<StlError> for Error {
+    fn from(err: StlError) -> Self {
+        match err {
+            StlError::Io(io_err) => io_err,
+            err => Error::new(ErrorKind::InvalidData, err),
+        }
+    }
+}
+
+
+
+#[derive(Debug)]
+pub enum PlyError {
+    Io(Error),
+    InvalidHeader { line: usize, message: String },
+    // `index` is the element's position within its element block
+    InvalidData { element: String, index: usize, message: String },
+}
+
+
+
+impl fmt::Display for PlyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlyError::Io(err) => write!(f, "I/O error: {}", err),
+            PlyError::InvalidHeader { line, message } => write!(f, "header line {}: {}", line, message),
+            PlyError::InvalidData { element, index, message } => write!(f, "{} {}: {}", element, index, message),
+        }
+    }
+}
+
+
+
+impl std::error::Error for PlyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PlyError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+
+
+impl From<Error> for PlyError {
+    fn from(err: Error) -> Self {
+        return PlyError::Io(err);
+    }
+}
+
+
+
+impl From<PlyError> for Error {
+    fn from(err: PlyError) -> Self {
+        match err {
+            PlyError::Io(io_err) => io_err,
+            err => Error::new(ErrorKind::InvalidData, err),
+        }
+    }
+}
+
+
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(Error),
+    Json(JsonError),
+    // `field` is a path into the document, e.g. "models[2].shader"
+    Invalid { field: String, message: String },
+    // a model or texture the scene refers to failed to load
+    Asset { path: String, message: String },
+}
+
+
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(err) => write!(f, "I/O error: {}", err),
+            SceneError::Json(err) => write!(f, "JSON error: {}", err),
+            SceneError::Invalid { field, message } => write!(f, "{}: {}", field, message),
+            SceneError::Asset { path, message } => write!(f, "failed to load '{}': {}", path, message),
+        }
+    }
+}
+
+
+
+impl std::error::Error for SceneError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SceneError::Io(err) => Some(err),
+            SceneError::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+
+
+impl From<Error> for SceneError {
+    fn from(err: Error) -> Self {
+        return SceneError::Io(err);
+    }
+}
+
+
+
+impl From<JsonError> for SceneError {
+    fn from(err: JsonError) -> Self {
+        return SceneError::Json(err);
+    }
+}
+
+
+
+impl From<SceneError> for Error {
+    fn from(err: SceneError) -> Self {
+        match err {
+            SceneError::Io(io_err) => io_err,
+            err => Error::new(ErrorKind::InvalidData, err),
+        }
+    }
+}
+
+
+
+/// bounds checked little endian reads over an in-memory file
+pub(crate) struct ByteReader<'a> {
+    pub bytes: &'a [u8],
+}
+
+
+
+impl<'a> ByteReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        return Self { bytes };
+    }
+
+
+    pub fn slice(&self, offset: usize, len: usize) -> Result<&'a [u8], ImageError> {
+        let end = offset.saturating_add(len);
+        if end > self.bytes.len() {
+            return Err(ImageError::Truncated { needed: end, available: self.bytes.len() });
+        }
+        return Ok(&self.bytes[offset..end]);
+    }
+
+
+    pub fn u8(&self, offset: usize) -> Result<u8, ImageError> {
+        return Ok(self.slice(offset, 1)?[0]);
+    }
+
+
+    pub fn u16_le(&self, offset: usize) -> Result<u16, ImageError> {
+        return Ok(u16::from_le_bytes(self.slice(offset, 2)?.try_into().unwrap()));
+    }
+
+
+    pub fn u32_le(&self, offset: usize) -> Result<u32, ImageError> {
+        return Ok(u32::from_le_bytes(self.slice(offset, 4)?.try_into().unwrap()));
+    }
+
+
+    pub fn u32_be(&self, offset: usize) -> Result<u32, ImageError> {
+        return Ok(u32::from_be_bytes(self.slice(offset, 4)?.try_into().unwrap()));
+    }
+
+
+    pub fn i32_le(&self, offset: usize) -> Result<i32, ImageError> {
+        return Ok(i32::from_le_bytes(self.slice(offset, 4)?.try_into().unwrap()));
+    }
+}
@@ -0,0 +1,1141 @@
+use std::path::Path;
+
+use crate::animation::{AnimatedValue, AnimationClip, Interpolation, Joint, Property, Skeleton, Track};
+use crate::types::matrix::{cofactor_transform, decompose, determinant3, mat_mul, transform_normal, transform_point, transform_vector, trs_matrix, Mat4, Quaternion, IDENTITY};
+use crate::types::model::SubMesh;
+use crate::types::morph::MorphTarget;
+use crate::types::normals::{generate_normals, NormalMode};
+use crate::types::shader::{MaterialShader, Shader, SolidShader};
+use crate::types::tangents::generate_tangents;
+use crate::utils::json::Json;
+use crate::{vec2, vec3, Camera, Material, Model, Scene, Texture, Transform, Vec2, Vec3, Vertex};
+use super::error::{ByteReader, GltfError};
+use super::texture_file::decode_texture;
+
+
+
+/// meshes are baked into world space, so every model has an identity transform, except animated root nodes
+/// which keep their node transform for the animations to drive. skinned meshes stay in bind pose and carry
+/// their skin as a `Skeleton`
+pub struct GltfFile {
+    pub models: Vec<Model>,
+    pub cameras: Vec<Camera>,
+    // model indices refer to `models`
+    pub animations: Vec<AnimationClip>,
+    pub warnings: Vec<String>,
+}
+
+
+
+impl GltfFile {
+    /// scene viewed through the first camera in the file, if there is one
+    pub fn into_scene(self) -> Scene {
+        let mut scene = Scene::new();
+        let mut cameras = self.cameras.into_iter();
+        if let Some(camera) = cameras.next() {
+            scene.camera = camera;
+        }
+        scene.cameras.extend(cameras);
+        for model in self.models {
+            scene.load_model(model);
+        }
+        for clip in self.animations {
+            scene.animation.add_clip(clip);
+        }
+        if !scene.animation.clips.is_empty() {
+            scene.animation.play(0);
+        }
+        return scene;
+    }
+}
+
+
+
+const GLB_MAGIC: u32 = 0x46546C67; // "glTF"
+const GLB_JSON_CHUNK: u32 = 0x4E4F534A;
+const GLB_BIN_CHUNK: u32 = 0x004E4942;
+
+// extensions that only add data we can ignore are fine, anything else in extensionsRequired is not
+const SUPPORTED_EXTENSIONS: [&str; 0] = [];
+
+
+
+struct Document<'a> {
+    json: &'a Json,
+    buffers: Vec<Vec<u8>>,
+    base_dir: &'a Path,
+}
+
+
+
+/// loads a `.gltf` (external or base64 buffers) or `.glb` file
+pub fn parse_gltf(path: &str) -> Result<GltfFile, GltfError> {
+    let bytes = std::fs::read(path)?;
+    let base_dir = Path::new(path).parent().unwrap_or(Path::new(""));
+
+    let (json_text, glb_bin) = if bytes.len() >= 4 && ByteReader::new(&bytes).u32_le(0).unwrap() == GLB_MAGIC {
+        split_glb(&bytes)?
+    } else {
+        (std::str::from_utf8(&bytes).map_err(|_| GltfError::InvalidGlb("file is neither GLB nor UTF-8 JSON"))?, None)
+    };
+    // a byte order mark is allowed by some exporters even though the spec forbids it
+    let json = Json::parse(json_text.trim_start_matches('\u{feff}'))?;
+
+    let version = json.get("asset").and_then(|asset| asset.get("version")).and_then(Json::as_str).unwrap_or("");
+    if !version.starts_with("2.") {
+        return Err(GltfError::UnsupportedVersion(version.to_string()));
+    }
+    for extension in array(&json, "extensionsRequired") {
+        let name = extension.as_str().unwrap_or("");
+        if !SUPPORTED_EXTENSIONS.contains(&name) {
+            return Err(GltfError::UnsupportedExtension(name.to_string()));
+        }
+    }
+
+    let mut warnings: Vec<String> = Vec::new();
+    let buffers = load_buffers(&json, base_dir, glb_bin)?;
+    let document = Document { json: &json, buffers, base_dir };
+
+    let images = load_images(&document, &mut warnings)?;
+    let mut models: Vec<Model> = Vec::new();
+    let mut cameras: Vec<Camera> = Vec::new();
+    let animated = animated_nodes(&json);
+    // (node, model) for the animated nodes loaded with their own transform
+    let mut node_models: Vec<(usize, usize)> = Vec::new();
+    // (model, skin) for skinned meshes, their skeletons need every node's world matrix
+    let mut skinned_models: Vec<(usize, usize)> = Vec::new();
+    // (node, model, morph target count) for meshes with morph targets, their weights can be animated
+    let mut node_morphs: Vec<(usize, usize, usize)> = Vec::new();
+    let mut node_worlds: Vec<Option<Mat4>> = vec![None; array(&json, "nodes").len()];
+
+    // the default scene, or every root node when the file doesn't name one
+    let roots: Vec<usize> = match json.get("scene").and_then(Json::as_usize).or(if array(&json, "scenes").is_empty() { None } else { Some(0) }) {
+        Some(scene_index) => {
+            let scene = element(&json, "scenes", scene_index, "scene")?;
+            array(scene, "nodes").iter().filter_map(Json::as_usize).collect()
+        }
+        None => {
+            let mut is_child = vec![false; array(&json, "nodes").len()];
+            for node in array(&json, "nodes") {
+                for child in array(node, "children").iter().filter_map(Json::as_usize) {
+                    if child < is_child.len() {
+                        is_child[child] = true;
+                    }
+                }
+            }
+            (0..is_child.len()).filter(|&i| !is_child[i]).collect()
+        }
+    };
+
+    // explicit stack, with the path so far to reject cycles
+    let mut stack: Vec<(usize, Mat4, Vec<usize>)> = roots.into_iter().rev().map(|root| (root, IDENTITY, Vec::new())).collect();
+    while let Some((node_index, parent_matrix, mut ancestors)) = stack.pop() {
+        if ancestors.contains(&node_index) {
+            warnings.push(format!("nodes[{}] is its own ancestor, skipping cycle", node_index));
+            continue;
+        }
+        let node = element(&json, "nodes", node_index, "node")?;
+        let world = mat_mul(&parent_matrix, &node_matrix(node));
+        node_worlds[node_index] = Some(world);
+
+        if let Some(mesh_index) = node.get("mesh").and_then(Json::as_usize) {
+            // skinned meshes are placed by their joints alone, the node's own transform doesn't apply
+            let skin = node.get("skin").and_then(Json::as_usize);
+
+            // the model's transform only has yaw and pitch, so only nodes without animated or transformed parents
+            // can move as glTF says
+            let is_animated = animated.contains(&node_index) && skin.is_none();
+            let own_transform = is_animated && parent_matrix == IDENTITY && node.get("matrix").is_none();
+            if is_animated && !own_transform {
+                warnings.push(format!("nodes[{}] is animated under a transformed parent, drawn in its rest pose", node_index));
+            }
+            if own_transform && !array(node, "children").is_empty() {
+                warnings.push(format!("children of nodes[{}] don't follow its animation", node_index));
+            }
+
+            let matrix = if own_transform || skin.is_some() { &IDENTITY } else { &world };
+            if let Some(mut model) = load_mesh(&document, mesh_index, matrix, &images, &mut warnings)? {
+                if own_transform {
+                    model.transform = node_transform(node, node_index, &mut warnings);
+                    node_models.push((node_index, models.len()));
+                }
+                if let Some(skin) = skin {
+                    skinned_models.push((models.len(), skin));
+                }
+                if !model.morph_targets.is_empty() {
+                    if let Some(weights) = node.get("weights").and_then(Json::as_f32_array) {
+                        if weights.len() == model.morph_targets.len() {
+                            model.morph_weights = weights;
+                        } else {
+                            warnings.push(format!("nodes[{}]: {} morph weights for {} targets, ignored", node_index, weights.len(), model.morph_targets.len()));
+                        }
+                    }
+                    node_morphs.push((node_index, models.len(), model.morph_targets.len()));
+                }
+                models.push(model);
+            }
+        }
+        if let Some(camera_index) = node.get("camera").and_then(Json::as_usize)
+            && let Some(camera) = load_camera(&json, camera_index, &world, &mut warnings)?
+        {
+            cameras.push(camera);
+        }
+        ancestors.push(node_index);
+        for child in array(node, "children").iter().rev() {
+            let Some(child) = child.as_usize() else { continue };
+            stack.push((child, world, ancestors.clone()));
+        }
+    }
+
+    // (node, model, joint) for the nodes that pose a skeleton
+    let mut node_joints: Vec<(usize, usize, usize)> = Vec::new();
+    let parents = node_parents(&json);
+    for (model, skin) in skinned_models {
+        let Some(skeleton) = load_skeleton(&document, skin, &parents, &node_worlds, &mut warnings)? else { continue };
+        for (joint, node) in array(element(&json, "skins", skin, "node")?, "joints").iter().filter_map(Json::as_usize).enumerate() {
+            node_joints.push((node, model, joint));
+        }
+        models[model].skeleton = Some(skeleton);
+    }
+
+    let animations = load_animations(&document, &node_models, &node_joints, &node_morphs, &mut warnings)?;
+
+    return Ok(GltfFile { models, cameras, animations, warnings });
+}
+
+
+
+// (json text, BIN chunk)
+fn split_glb(bytes: &[u8]) -> Result<(&str, Option<Vec<u8>>), GltfError> {
+    let reader = ByteReader::new(bytes);
+    let header = |offset| reader.u32_le(offset).map_err(|_| GltfError::InvalidGlb("truncated header"));
+
+    let version = header(4)?;
+    if version != 2 {
+        return Err(GltfError::UnsupportedVersion(format!("GLB container {}", version)));
+    }
+    let length = (header(8)? as usize).min(bytes.len());
+
+    let mut json: Option<&str> = None;
+    let mut bin: Option<Vec<u8>> = None;
+    let mut offset = 12;
+    while offset + 8 <= length {
+        let chunk_length = header(offset)? as usize;
+        let chunk_type = header(offset + 4)?;
+        let data = reader
+            .slice(offset + 8, chunk_length)
+            .map_err(|_| GltfError::InvalidGlb("chunk extends past end of file"))?;
+
+        match chunk_type {
+            GLB_JSON_CHUNK if json.is_none() => {
+                json = Some(std::str::from_utf8(data).map_err(|_| GltfError::InvalidGlb("JSON chunk is not UTF-8"))?);
+            }
+            GLB_BIN_CHUNK if bin.is_none() => bin = Some(data.to_vec()),
+            // unknown chunks must be ignored
+            _ => {}
+        }
+        // chunks are padded to 4 bytes
+        offset += 8 + chunk_length.next_multiple_of(4);
+    }
+
+    match json {
+        Some(json) => return Ok((json, bin)),
+        None => return Err(GltfError::InvalidGlb("missing JSON chunk")),
+    }
+}
+
+
+
+fn array<'a>(json: &'a Json, key: &str) -> &'a [Json] {
+    return json.get(key).and_then(Json::as_array).unwrap_or(&[]);
+}
+
+
+
+// json[key][index], named for error messages
+fn element<'a>(json: &'a Json, key: &'static str, index: usize, referrer: &str) -> Result<&'a Json, GltfError> {
+    match array(json, key).get(index) {
+        Some(item) => return Ok(item),
+        None => return Err(GltfError::InvalidReference { object: referrer.to_string(), field: key, index }),
+    }
+}
+
+
+
+fn required_usize(json: &Json, field: &'static str, object: &str) -> Result<usize, GltfError> {
+    match json.get(field).and_then(Json::as_usize) {
+        Some(value) => return Ok(value),
+        None => return Err(GltfError::MissingField { object: object.to_string(), field }),
+    }
+}
+
+
+
+fn load_buffers(json: &Json, base_dir: &Path, mut glb_bin: Option<Vec<u8>>) -> Result<Vec<Vec<u8>>, GltfError> {
+    let mut buffers: Vec<Vec<u8>> = Vec::new();
+
+    for (index, buffer) in array(json, "buffers").iter().enumerate() {
+        let byte_length = required_usize(buffer, "byteLength", &format!("buffers[{}]", index))?;
+        let invalid = |reason: String| GltfError::InvalidBuffer { index, reason };
+
+        let data = match buffer.get("uri").and_then(Json::as_str) {
+            Some(uri) => read_uri(uri, base_dir).map_err(invalid)?,
+            // only the first buffer may point at the GLB BIN chunk
+            None if index == 0 => glb_bin.take().ok_or_else(|| invalid("no uri and no GLB BIN chunk".to_string()))?,
+            None => return Err(invalid("no uri".to_string())),
+        };
+
+        if data.len() < byte_length {
+            return Err(invalid(format!("holds {} bytes, byteLength is {}", data.len(), byte_length)));
+        }
+        buffers.push(data);
+    }
+
+    return Ok(buffers);
+}
+
+
+
+// data: uris are decoded in place, anything else is a path relative to the file
+fn read_uri(uri: &str, base_dir: &Path) -> Result<Vec<u8>, String> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        let Some((header, payload)) = data.split_once(',') else {
+            return Err("malformed data uri".to_string());
+        };
+        if !header.ends_with(";base64") {
+            return Err("only base64 data uris are supported".to_string());
+        }
+        return decode_base64(payload).ok_or_else(|| "invalid base64 in data uri".to_string());
+    }
+
+    let path = base_dir.join(percent_decode(uri));
+    return std::fs::read(&path).map_err(|err| format!("failed to read '{}': {}", path.display(), err));
+}
+
+
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(text.len() / 4 * 3);
+    let mut bits: u32 = 0;
+    let mut bit_count = 0;
+
+    for c in text.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            b' ' | b'\n' | b'\r' | b'\t' => continue,
+            _ => return None,
+        };
+        bits = (bits << 6) | value as u32;
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            bytes.push((bits >> bit_count) as u8);
+        }
+    }
+
+    return Some(bytes);
+}
+
+
+
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok()).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    return String::from_utf8_lossy(&decoded).into_owned();
+}
+
+
+
+fn buffer_view_bytes<'a>(document: &'a Document, view_index: usize, referrer: &str) -> Result<(&'a [u8], Option<usize>), GltfError> {
+    let view = element(document.json, "bufferViews", view_index, referrer)?;
+    let name = format!("bufferViews[{}]", view_index);
+
+    let buffer_index = required_usize(view, "buffer", &name)?;
+    let buffer = document
+        .buffers
+        .get(buffer_index)
+        .ok_or(GltfError::InvalidReference { object: name.clone(), field: "buffer", index: buffer_index })?;
+
+    let offset = view.get("byteOffset").and_then(Json::as_usize).unwrap_or(0);
+    let length = required_usize(view, "byteLength", &name)?;
+    let stride = view.get("byteStride").and_then(Json::as_usize);
+
+    match offset.checked_add(length).and_then(|end| buffer.get(offset..end)) {
+        Some(bytes) => return Ok((bytes, stride)),
+        None => {
+            return Err(GltfError::InvalidBuffer {
+                index: buffer_index,
+                reason: format!("{} spans {}..{} past the end of the buffer", name, offset, offset.saturating_add(length)),
+            })
+        }
+    }
+}
+
+
+
+// component size in bytes for an accessor componentType
+fn component_size(component_type: usize) -> Option<usize> {
+    match component_type {
+        5120 | 5121 => return Some(1),
+        5122 | 5123 => return Some(2),
+        5125 | 5126 => return Some(4),
+        _ => return None,
+    }
+}
+
+
+
+fn read_component(bytes: &[u8], component_type: usize, normalized: bool) -> f64 {
+    let raw = match component_type {
+        5120 => bytes[0] as i8 as f64,
+        5121 => bytes[0] as f64,
+        5122 => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+        5123 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+        5125 => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+        _ => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+    };
+    if !normalized {
+        return raw;
+    }
+    match component_type {
+        5120 => return (raw / 127.0).max(-1.0),
+        5121 => return raw / 255.0,
+        5122 => return (raw / 32767.0).max(-1.0),
+        5123 => return raw / 65535.0,
+        5125 => return raw / u32::MAX as f64,
+        _ => return raw,
+    }
+}
+
+
+
+// `count` elements of `components` values starting at `offset` in a buffer view
+fn read_elements(
+    bytes: &[u8],
+    stride: Option<usize>,
+    offset: usize,
+    count: usize,
+    components: usize,
+    component_type: usize,
+    normalized: bool,
+) -> Option<Vec<f64>> {
+    let size = component_size(component_type)?;
+    let element_size = size * components;
+    let stride = stride.unwrap_or(element_size);
+
+    if count > 0 {
+        let end = offset.checked_add(stride.checked_mul(count - 1)?)?.checked_add(element_size)?;
+        if end > bytes.len() {
+            return None;
+        }
+    }
+
+    let mut values: Vec<f64> = Vec::with_capacity(count * components);
+    for i in 0..count {
+        let start = offset + i * stride;
+        for c in 0..components {
+            values.push(read_component(&bytes[start + c * size..], component_type, normalized));
+        }
+    }
+    return Some(values);
+}
+
+
+
+/// flattened accessor values and the number of components per element, sparse substitution applied
+fn read_accessor(document: &Document, index: usize) -> Result<(Vec<f64>, usize), GltfError> {
+    let accessor = element(document.json, "accessors", index, "accessor")?;
+    let name = format!("accessors[{}]", index);
+    let invalid = |reason: &str| GltfError::InvalidAccessor { index, reason: reason.to_string() };
+
+    let count = required_usize(accessor, "count", &name)?;
+    let component_type = required_usize(accessor, "componentType", &name)?;
+    if component_size(component_type).is_none() {
+        return Err(invalid(&format!("unknown componentType {}", component_type)));
+    }
+    let normalized = accessor.get("normalized").and_then(Json::as_bool).unwrap_or(false);
+    let components = match accessor.get("type").and_then(Json::as_str) {
+        Some("SCALAR") => 1,
+        Some("VEC2") => 2,
+        Some("VEC3") => 3,
+        Some("VEC4") | Some("MAT2") => 4,
+        Some("MAT3") => 9,
+        Some("MAT4") => 16,
+        _ => return Err(invalid("missing or unknown type")),
+    };
+
+    // without a buffer view the data starts out as zeros
+    let mut values = match accessor.get("bufferView").and_then(Json::as_usize) {
+        Some(view_index) => {
+            let (bytes, stride) = buffer_view_bytes(document, view_index, &name)?;
+            let offset = accessor.get("byteOffset").and_then(Json::as_usize).unwrap_or(0);
+            read_elements(bytes, stride, offset, count, components, component_type, normalized)
+                .ok_or_else(|| invalid("data extends past the end of its buffer view"))?
+        }
+        None => vec![0.0; count * components],
+    };
+
+    if let Some(sparse) = accessor.get("sparse") {
+        let sparse_count = required_usize(sparse, "count", &name)?;
+        let indices = sparse.get("indices").ok_or(GltfError::MissingField { object: name.clone(), field: "sparse.indices" })?;
+        let sparse_values = sparse.get("values").ok_or(GltfError::MissingField { object: name.clone(), field: "sparse.values" })?;
+
+        let (index_bytes, _) = buffer_view_bytes(document, required_usize(indices, "bufferView", &name)?, &name)?;
+        let index_offset = indices.get("byteOffset").and_then(Json::as_usize).unwrap_or(0);
+        let index_type = required_usize(indices, "componentType", &name)?;
+        let targets = read_elements(index_bytes, None, index_offset, sparse_count, 1, index_type, false)
+            .ok_or_else(|| invalid("sparse indices extend past their buffer view"))?;
+
+        let (value_bytes, _) = buffer_view_bytes(document, required_usize(sparse_values, "bufferView", &name)?, &name)?;
+        let value_offset = sparse_values.get("byteOffset").and_then(Json::as_usize).unwrap_or(0);
+        let replacements = read_elements(value_bytes, None, value_offset, sparse_count, components, component_type, normalized)
+            .ok_or_else(|| invalid("sparse values extend past their buffer view"))?;
+
+        for (i, &target) in targets.iter().enumerate() {
+            let target = target as usize;
+            if target >= count {
+                return Err(invalid("sparse index out of range"));
+            }
+            values[target * components..(target + 1) * components].copy_from_slice(&replacements[i * components..(i + 1) * components]);
+        }
+    }
+
+    return Ok((values, components));
+}
+
+
+
+fn load_images(document: &Document, warnings: &mut Vec<String>) -> Result<Vec<Option<Texture>>, GltfError> {
+    let mut images: Vec<Option<Texture>> = Vec::new();
+
+    for (index, image) in array(document.json, "images").iter().enumerate() {
+        let name = format!("images[{}]", index);
+
+        let (bytes, extension) = if let Some(uri) = image.get("uri").and_then(Json::as_str) {
+            let extension = Path::new(uri).extension().and_then(|e| e.to_str()).unwrap_or("").to_string();
+            match read_uri(uri, document.base_dir) {
+                Ok(bytes) => (bytes, extension),
+                Err(reason) => {
+                    warnings.push(format!("{}: {}", name, reason));
+                    images.push(None);
+                    continue;
+                }
+            }
+        } else if let Some(view_index) = image.get("bufferView").and_then(Json::as_usize) {
+            let (bytes, _) = buffer_view_bytes(document, view_index, &name)?;
+            let mime_type = image.get("mimeType").and_then(Json::as_str).unwrap_or("");
+            (bytes.to_vec(), mime_type.trim_start_matches("image/").to_string())
+        } else {
+            return Err(GltfError::MissingField { object: name, field: "uri" });
+        };
+
+        match decode_texture(&bytes, &extension) {
+            Ok(texture) => images.push(Some(texture)),
+            Err(err) => {
+                warnings.push(format!("{}: {}", name, err));
+                images.push(None);
+            }
+        }
+    }
+
+    return Ok(images);
+}
+
+
+
+// decoded image behind a textureInfo object such as baseColorTexture
+fn texture_for(document: &Document, texture_info: Option<&Json>, images: &[Option<Texture>], referrer: &str, warnings: &mut Vec<String>) -> Result<Option<Texture>, GltfError> {
+    let Some(info) = texture_info else {
+        return Ok(None);
+    };
+    let texture_index = required_usize(info, "index", referrer)?;
+    let texture = element(document.json, "textures", texture_index, referrer)?;
+
+    if info.get("texCoord").and_then(Json::as_usize).unwrap_or(0) != 0 {
+        warnings.push(format!("{}: only TEXCOORD_0 is supported, using it instead", referrer));
+    }
+
+    let Some(source) = texture.get("source").and_then(Json::as_usize) else {
+        warnings.push(format!("textures[{}] has no source image", texture_index));
+        return Ok(None);
+    };
+    match images.get(source) {
+        Some(image) => return Ok(image.clone()),
+        None => return Err(GltfError::InvalidReference { object: format!("textures[{}]", texture_index), field: "source", index: source }),
+    }
+}
+
+
+
+fn load_material(document: &Document, material_index: usize, images: &[Option<Texture>], warnings: &mut Vec<String>) -> Result<MaterialShader, GltfError> {
+    let json = element(document.json, "materials", material_index, "primitive")?;
+    let name = format!("materials[{}]", material_index);
+
+    let mut material = Material::new(json.get("name").and_then(Json::as_str).unwrap_or(&name));
+    let pbr = json.get("pbrMetallicRoughness");
+    let factor = |key: &str| pbr.and_then(|pbr| pbr.get(key));
+
+    let base_color = factor("baseColorFactor").and_then(Json::as_f32_array).filter(|c| c.len() == 4).unwrap_or(vec![1.0; 4]);
+    material.diffuse = vec3!(base_color[0], base_color[1], base_color[2]);
+    material.metallic = factor("metallicFactor").and_then(Json::as_f32).unwrap_or(1.0);
+    material.roughness = factor("roughnessFactor").and_then(Json::as_f32).unwrap_or(1.0);
+    if let Some(emissive) = json.get("emissiveFactor").and_then(Json::as_f32_array).filter(|c| c.len() == 3) {
+        material.emissive = vec3!(emissive[0], emissive[1], emissive[2]);
+    }
+
+    let mut alpha_cutoff = None;
+    match json.get("alphaMode").and_then(Json::as_str).unwrap_or("OPAQUE") {
+        "OPAQUE" => material.opacity = 1.0,
+        "MASK" => {
+            material.opacity = base_color[3];
+            alpha_cutoff = Some(json.get("alphaCutoff").and_then(Json::as_f32).unwrap_or(0.5));
+        }
+        mode => {
+            warnings.push(format!("{}: alphaMode {} isn't supported, transparency is ignored and it draws opaque", name, mode));
+            material.opacity = 1.0;
+        }
+    }
+
+    let mut shader = MaterialShader::new(material);
+    shader.alpha_cutoff = alpha_cutoff;
+    shader.diffuse_texture = texture_for(document, factor("baseColorTexture"), images, &name, warnings)?;
+    shader.normal_texture = texture_for(document, json.get("normalTexture"), images, &name, warnings)?;
+    return Ok(shader);
+}
+
+
+
+fn load_mesh(document: &Document, mesh_index: usize, world: &Mat4, images: &[Option<Texture>], warnings: &mut Vec<String>) -> Result<Option<Model>, GltfError> {
+    let mesh = element(document.json, "meshes", mesh_index, "node")?;
+    let mirrored = determinant3(world) < 0.0;
+
+    let mut vertices: Vec<Vertex> = Vec::new();
+    let mut submeshes: Vec<SubMesh> = Vec::new();
+
+    // every primitive has the same targets, exporters put their names in the mesh's extras
+    let target_count = array(mesh, "primitives").first().map_or(0, |primitive| array(primitive, "targets").len());
+    let target_names = mesh.get("extras").map_or(&[][..], |extras| array(extras, "targetNames"));
+    let mut morph_targets: Vec<MorphTarget> = (0..target_count).map(|i| MorphTarget {
+        name: target_names.get(i).and_then(Json::as_str).map(str::to_string).unwrap_or_else(|| format!("target {}", i)),
+        positions: Vec::new(),
+        normals: Vec::new(),
+    }).collect();
+    let mut morphs_valid = true;
+
+    for (primitive_index, primitive) in array(mesh, "primitives").iter().enumerate() {
+        let name = format!("meshes[{}].primitives[{}]", mesh_index, primitive_index);
+        let attributes = primitive.get("attributes").ok_or(GltfError::MissingField { object: name.clone(), field: "attributes" })?;
+
+        let mode = primitive.get("mode").and_then(Json::as_usize).unwrap_or(4);
+        if !(4..=6).contains(&mode) {
+            warnings.push(format!("{}: points and lines are not drawn", name));
+            continue;
+        }
+
+        let attribute = |key: &str| -> Result<Option<(Vec<f64>, usize)>, GltfError> {
+            match attributes.get(key).and_then(Json::as_usize) {
+                Some(index) => return Ok(Some(read_accessor(document, index)?)),
+                None => return Ok(None),
+            }
+        };
+        let Some((positions, _)) = attribute("POSITION")? else {
+            return Err(GltfError::MissingField { object: format!("{}.attributes", name), field: "POSITION" });
+        };
+        let normals = attribute("NORMAL")?;
+        let texcoords = attribute("TEXCOORD_0")?;
+        let tangents = attribute("TANGENT")?;
+        let colors = attribute("COLOR_0")?;
+        let joints = attribute("JOINTS_0")?;
+        let weights = attribute("WEIGHTS_0")?;
+        if attributes.get("JOINTS_1").is_some() {
+            warnings.push(format!("{}: only the first four joint influences are used", name));
+        }
+
+        let vertex_count = positions.len() / 3;
+        let indices: Vec<usize> = match primitive.get("indices").and_then(Json::as_usize) {
+            Some(accessor) => read_accessor(document, accessor)?.0.into_iter().map(|i| i as usize).collect(),
+            None => (0..vertex_count).collect(),
+        };
+        if let Some(&bad) = indices.iter().find(|&&i| i >= vertex_count) {
+            return Err(GltfError::InvalidAccessor {
+                index: primitive.get("indices").and_then(Json::as_usize).unwrap_or(0),
+                reason: format!("index {} out of range for {} vertices", bad, vertex_count),
+            });
+        }
+
+        let corner = |i: usize| -> Vertex {
+            let position = vec3!(positions[i * 3], positions[i * 3 + 1], positions[i * 3 + 2]);
+            let normal = match &normals {
+                Some((n, 3)) if n.len() > i * 3 + 2 => vec3!(n[i * 3], n[i * 3 + 1], n[i * 3 + 2]),
+                _ => vec3!(0, 0, 0),
+            };
+            // glTF already puts the texture origin at the top left
+            let texcoord = match &texcoords {
+                Some((t, 2)) if t.len() > i * 2 + 1 => vec2!(t[i * 2] as f32, t[i * 2 + 1] as f32),
+                _ => vec2!(0, 0),
+            };
+            let (tangent, bitangent_sign) = match &tangents {
+                Some((t, 4)) if t.len() > i * 4 + 3 => (vec3!(t[i * 4], t[i * 4 + 1], t[i * 4 + 2]), if t[i * 4 + 3] < 0.0 { -1.0 } else { 1.0 }),
+                _ => (vec3!(0, 0, 0), 1.0),
+            };
+            // COLOR_0 may be rgb or rgba, alpha is dropped
+            let color = match &colors {
+                Some((c, n @ 3..=4)) if c.len() >= (i + 1) * n => vec3!(c[i * n], c[i * n + 1], c[i * n + 2]),
+                _ => vec3!(1, 1, 1),
+            };
+            let (joints, weights) = match (&joints, &weights) {
+                (Some((j, 4)), Some((w, 4))) if j.len() > i * 4 + 3 && w.len() > i * 4 + 3 => {
+                    (std::array::from_fn(|k| j[i * 4 + k] as u16), std::array::from_fn(|k| w[i * 4 + k] as f32))
+                }
+                _ => ([0; 4], [0.0; 4]),
+            };
+            return Vertex { position, texcoord, normal, tangent, bitangent_sign, color, joints, weights };
+        };
+
+        // source vertex of every triangle corner
+        let mut corners: Vec<usize> = Vec::new();
+        let mut push_triangle = |a: usize, b: usize, c: usize| {
+            // a mirroring transform flips the winding, swap to keep faces front facing
+            let (b, c) = if mirrored { (c, b) } else { (b, c) };
+            corners.extend([indices[a], indices[b], indices[c]]);
+        };
+        match mode {
+            4 => (0..indices.len() / 3).for_each(|t| push_triangle(t * 3, t * 3 + 1, t * 3 + 2)),
+            5 => (0..indices.len().saturating_sub(2)).for_each(|i| if i.is_multiple_of(2) { push_triangle(i, i + 1, i + 2) } else { push_triangle(i + 1, i, i + 2) }),
+            _ => (1..indices.len().saturating_sub(1)).for_each(|i| push_triangle(0, i, i + 1)),
+        }
+        let mut triangles: Vec<Vertex> = corners.iter().map(|&i| corner(i)).collect();
+
+        let targets = array(primitive, "targets");
+        if targets.len() != target_count {
+            warnings.push(format!("{}: has {} morph targets where the mesh has {}, morph targets ignored", name, targets.len(), target_count));
+            morphs_valid = false;
+        }
+        for (target_index, (target, morph_target)) in targets.iter().zip(&mut morph_targets).enumerate() {
+            let delta = |key: &str| -> Result<Option<Vec<f64>>, GltfError> {
+                match target.get(key).and_then(Json::as_usize) {
+                    Some(index) => match read_accessor(document, index)? {
+                        (values, 3) if values.len() >= vertex_count * 3 => return Ok(Some(values)),
+                        _ => return Err(GltfError::InvalidAccessor { index, reason: format!("expected a VEC3 per vertex for {}.targets[{}].{}", name, target_index, key) }),
+                    },
+                    None => return Ok(None),
+                }
+            };
+            let vector = |values: &[f64], i: usize| vec3!(values[i * 3], values[i * 3 + 1], values[i * 3 + 2]);
+
+            // deltas go through the same transform as the vertices, missing ones are zero
+            let position_deltas = delta("POSITION")?;
+            morph_target.positions.extend(corners.iter().map(|&i| match &position_deltas {
+                Some(deltas) => transform_vector(world, vector(deltas, i)),
+                None => vec3!(0, 0, 0),
+            }));
+            // without normals in the file they're flat and generated, there's nothing for deltas to add to
+            let normal_deltas = delta("NORMAL")?;
+            morph_target.normals.extend(corners.iter().map(|&i| match (&normal_deltas, &normals) {
+                // scaled like the normal it's added to, which was normalized after transforming
+                (Some(deltas), Some((n, 3))) if n.len() > i * 3 + 2 => {
+                    let length = cofactor_transform(world, vector(n, i)).length();
+                    if length > 0.0 { cofactor_transform(world, vector(deltas, i)) * (1.0 / length) } else { vec3!(0, 0, 0) }
+                }
+                _ => vec3!(0, 0, 0),
+            }));
+        }
+
+        for vertex in &mut triangles {
+            vertex.position = transform_point(world, vertex.position);
+            vertex.normal = transform_normal(world, vertex.normal);
+            vertex.tangent = transform_vector(world, vertex.tangent).normalize();
+        }
+        // the spec asks for flat normals when they're missing
+        if normals.is_none() {
+            generate_normals(&mut triangles, None, NormalMode::Flat);
+        }
+        if tangents.is_none() || normals.is_none() || mirrored {
+            generate_tangents(&mut triangles);
+        }
+
+        let shader: Box<dyn Shader> = match primitive.get("material").and_then(Json::as_usize) {
+            Some(material_index) => Box::new(load_material(document, material_index, images, warnings)?),
+            None => Box::new(SolidShader::new()),
+        };
+
+        let start = vertices.len();
+        vertices.extend(triangles);
+        submeshes.push(SubMesh {
+            material: primitive.get("material").and_then(Json::as_usize).map(|m| format!("materials[{}]", m)).unwrap_or_default(),
+            vertices: start..vertices.len(),
+            shader,
+        });
+    }
+
+    if vertices.is_empty() {
+        return Ok(None);
+    }
+
+    if !morphs_valid {
+        morph_targets.clear();
+    }
+    for target in &mut morph_targets {
+        if target.normals.iter().all(|&delta| delta == vec3!(0, 0, 0)) {
+            target.normals.clear();
+        }
+    }
+    // the node can override these
+    let mut morph_weights = mesh.get("weights").and_then(Json::as_f32_array).unwrap_or_default();
+    morph_weights.resize(morph_targets.len(), 0.0);
+
+    let mut model = Model::new(vertices, Box::new(SolidShader::new()));
+    model.submeshes = submeshes;
+    model.morph_targets = morph_targets;
+    model.morph_weights = morph_weights;
+    return Ok(Some(model));
+}
+
+
+
+fn load_camera(json: &Json, camera_index: usize, world: &Mat4, warnings: &mut Vec<String>) -> Result<Option<Camera>, GltfError> {
+    let camera = element(json, "cameras", camera_index, "node")?;
+
+    let Some(perspective) = camera.get("perspective") else {
+        warnings.push(format!("cameras[{}]: only perspective cameras are supported", camera_index));
+        return Ok(None);
+    };
+    let yfov = perspective.get("yfov").and_then(Json::as_f32).ok_or(GltfError::MissingField {
+        object: format!("cameras[{}].perspective", camera_index),
+        field: "yfov",
+    })?;
+
+    let mut result = Camera::new(yfov.to_degrees());
+    result.transform.position = transform_point(world, vec3!(0, 0, 0));
+
+    // both conventions look down -z, so the camera's +z axis gives yaw and pitch; roll is lost
+    let back = transform_vector(world, vec3!(0, 0, 1)).normalize();
+    let pitch = back.y.clamp(-1.0, 1.0).asin().to_degrees();
+    let yaw = back.x.atan2(back.z).to_degrees();
+    result.transform.set_rotation(pitch, yaw);
+
+    let up = transform_vector(world, vec3!(0, 1, 0)).normalize();
+    if up.dot(result.transform.basis_vectors.1) < 0.999 {
+        warnings.push(format!("cameras[{}]: camera roll is not supported and was dropped", camera_index));
+    }
+
+    return Ok(Some(result));
+}
+
+
+
+// parent of every node, None for roots
+fn node_parents(json: &Json) -> Vec<Option<usize>> {
+    let mut parents = vec![None; array(json, "nodes").len()];
+    for (index, node) in array(json, "nodes").iter().enumerate() {
+        for child in array(node, "children").iter().filter_map(Json::as_usize) {
+            if child < parents.len() {
+                parents[child] = Some(index);
+            }
+        }
+    }
+    return parents;
+}
+
+
+
+// the skin's joints in their rest pose, None with a warning when it can't be used
+fn load_skeleton(document: &Document, skin_index: usize, parents: &[Option<usize>], node_worlds: &[Option<Mat4>], warnings: &mut Vec<String>) -> Result<Option<Skeleton>, GltfError> {
+    let skin = element(document.json, "skins", skin_index, "node")?;
+    let name = format!("skins[{}]", skin_index);
+    let joint_nodes: Vec<usize> = array(skin, "joints").iter().filter_map(Json::as_usize).collect();
+    if joint_nodes.is_empty() || joint_nodes.len() > u16::MAX as usize {
+        warnings.push(format!("{}: {} joints, drawn in bind pose", name, joint_nodes.len()));
+        return Ok(None);
+    }
+
+    let inverse_binds: Vec<Mat4> = match skin.get("inverseBindMatrices").and_then(Json::as_usize) {
+        Some(accessor) => {
+            let (values, components) = read_accessor(document, accessor)?;
+            if components != 16 || values.len() / 16 < joint_nodes.len() {
+                return Err(GltfError::InvalidAccessor { index: accessor, reason: format!("expected a MAT4 per joint of {}", name) });
+            }
+            values.chunks_exact(16).map(|m| std::array::from_fn(|i| m[i] as f32)).collect()
+        }
+        None => vec![IDENTITY; joint_nodes.len()],
+    };
+
+    let mut joints: Vec<Joint> = Vec::with_capacity(joint_nodes.len());
+    for (i, &node_index) in joint_nodes.iter().enumerate() {
+        let node = element(document.json, "nodes", node_index, &name)?;
+        let parent_node = parents.get(node_index).copied().flatten();
+        let parent = parent_node.and_then(|parent| joint_nodes.iter().position(|&joint| joint == parent));
+        // a root joint hangs from whatever is above it in the file
+        let base = match (parent, parent_node) {
+            (None, Some(parent_node)) => node_worlds.get(parent_node).copied().flatten().unwrap_or(IDENTITY),
+            _ => IDENTITY,
+        };
+        let (translation, rotation, scale) = decompose(&node_matrix(node));
+        joints.push(Joint {
+            name: node.get("name").and_then(Json::as_str).map(str::to_string).unwrap_or_else(|| format!("nodes[{}]", node_index)),
+            parent,
+            base,
+            inverse_bind: inverse_binds[i],
+            translation,
+            rotation,
+            scale,
+        });
+    }
+
+    match Skeleton::new(joints) {
+        Ok(skeleton) => return Ok(Some(skeleton)),
+        Err(reason) => {
+            warnings.push(format!("{}: {}, drawn in bind pose", name, reason));
+            return Ok(None);
+        }
+    }
+}
+
+
+
+// nodes whose translation, rotation or scale some animation drives
+fn animated_nodes(json: &Json) -> Vec<usize> {
+    let mut nodes: Vec<usize> = Vec::new();
+    for animation in array(json, "animations") {
+        for channel in array(animation, "channels") {
+            let Some(target) = channel.get("target") else { continue };
+            let path = target.get("path").and_then(Json::as_str);
+            if let Some(node) = target.get("node").and_then(Json::as_usize)
+                && matches!(path, Some("translation" | "rotation" | "scale"))
+                && !nodes.contains(&node)
+            {
+                nodes.push(node);
+            }
+        }
+    }
+    return nodes;
+}
+
+
+
+// the node's TRS as a model transform
+fn node_transform(node: &Json, node_index: usize, warnings: &mut Vec<String>) -> Transform {
+    let mut transform = Transform::new();
+    if let Some(&[x, y, z]) = node.get("translation").and_then(Json::as_f32_array).as_deref() {
+        transform.position = vec3!(x, y, z);
+    }
+    if let Some(&[x, y, z]) = node.get("scale").and_then(Json::as_f32_array).as_deref() {
+        transform.scale = vec3!(x, y, z);
+    }
+    if let Some(&[x, y, z, w]) = node.get("rotation").and_then(Json::as_f32_array).as_deref() {
+        let (pitch, yaw, rolled) = quaternion_pitch_yaw([x, y, z, w]);
+        transform.set_rotation(pitch, yaw);
+        if rolled {
+            warnings.push(format!("nodes[{}]: roll is not supported and was dropped", node_index));
+        }
+    }
+    return transform;
+}
+
+
+
+// (pitch, yaw) in degrees the way `Transform` turns, and whether the rotation also rolled, which is lost
+fn quaternion_pitch_yaw(rotation: [f32; 4]) -> (f32, f32, bool) {
+    let (axis, w) = (vec3!(rotation[0], rotation[1], rotation[2]), rotation[3]);
+    let rotate = |v: Vec3| v + axis.cross(v) * (2.0 * w) + axis.cross(axis.cross(v)) * 2.0;
+
+    // same as cameras: the rotated +z axis gives yaw and pitch
+    let back = rotate(vec3!(0, 0, 1)).normalize();
+    let pitch = back.y.clamp(-1.0, 1.0).asin().to_degrees();
+    let yaw = back.x.atan2(back.z).to_degrees();
+
+    let mut transform = Transform::new();
+    transform.set_rotation(pitch, yaw);
+    let up = rotate(vec3!(0, 1, 0)).normalize();
+    return (pitch, yaw, up.dot(transform.basis_vectors.1) < 0.999);
+}
+
+
+
+// clips driving the translation, rotation and scale of the nodes in `node_models` and `node_joints`, and the
+// morph weights of those in `node_morphs`
+fn load_animations(
+    document: &Document,
+    node_models: &[(usize, usize)],
+    node_joints: &[(usize, usize, usize)],
+    node_morphs: &[(usize, usize, usize)],
+    warnings: &mut Vec<String>,
+) -> Result<Vec<AnimationClip>, GltfError> {
+    let mut clips: Vec<AnimationClip> = Vec::new();
+
+    for (animation_index, animation) in array(document.json, "animations").iter().enumerate() {
+        let default_name = format!("animation {}", animation_index);
+        let mut clip = AnimationClip::new(animation.get("name").and_then(Json::as_str).unwrap_or(&default_name));
+
+        for (channel_index, channel) in array(animation, "channels").iter().enumerate() {
+            let name = format!("animations[{}].channels[{}]", animation_index, channel_index);
+            let target = channel.get("target").ok_or(GltfError::MissingField { object: name.clone(), field: "target" })?;
+            let path = target.get("path").and_then(Json::as_str).unwrap_or("");
+            if !matches!(path, "translation" | "rotation" | "scale" | "weights") {
+                warnings.push(format!("{}: unknown target path '{}' ignored", name, path));
+                continue;
+            }
+            // nodes without a mesh or joint of their own, or already warned about
+            let Some(node) = target.get("node").and_then(Json::as_usize) else { continue };
+            let model = node_models.iter().find(|(animated, _)| *animated == node).map(|&(_, model)| model);
+            let joints: Vec<(usize, usize)> = node_joints.iter().filter(|(joint_node, _, _)| *joint_node == node).map(|&(_, model, joint)| (model, joint)).collect();
+            let morph = node_morphs.iter().find(|(morphed, _, _)| *morphed == node).map(|&(_, model, targets)| (model, targets));
+            let (model, joints, morph) = if path == "weights" { (None, Vec::new(), morph) } else { (model, joints, None) };
+            if model.is_none() && joints.is_empty() && morph.is_none() {
+                continue;
+            }
+
+            let sampler_index = required_usize(channel, "sampler", &name)?;
+            let sampler = element(animation, "samplers", sampler_index, &name)?;
+            let sampler_name = format!("animations[{}].samplers[{}]", animation_index, sampler_index);
+            let (times, _) = read_accessor(document, required_usize(sampler, "input", &sampler_name)?)?;
+            let (output, components) = read_accessor(document, required_usize(sampler, "output", &sampler_name)?)?;
+            let times: Vec<f32> = times.iter().map(|&time| time as f32).collect();
+
+            let interpolation = match sampler.get("interpolation").and_then(Json::as_str).unwrap_or("LINEAR") {
+                "STEP" => Interpolation::Step,
+                "CUBICSPLINE" => Interpolation::Cubic,
+                "LINEAR" => Interpolation::Linear,
+                other => {
+                    warnings.push(format!("{}: unknown interpolation '{}', using LINEAR", sampler_name, other));
+                    Interpolation::Linear
+                }
+            };
+            let expected = match path {
+                "rotation" => 4,
+                "weights" => 1,
+                _ => 3,
+            };
+            if components != expected {
+                warnings.push(format!("{}: expected {} components per keyframe, got {}, ignored", name, expected, components));
+                continue;
+            }
+
+            // weights keyframes hold one value per morph target
+            let stride = morph.map_or(1, |(_, targets)| targets);
+            // cubic splines store the in tangents, the values and the out tangents per keyframe
+            let parts = if interpolation == Interpolation::Cubic { 3 } else { 1 };
+            let elements: Vec<AnimatedValue> = output.chunks_exact(components).map(|element| {
+                let element: Vec<f32> = element.iter().map(|&c| c as f32).collect();
+                return AnimatedValue::from_slice(&element).unwrap();
+            }).collect();
+            if morph.is_some() && elements.len() != times.len() * stride * parts {
+                warnings.push(format!("{}: expected {} weights per keyframe, ignored", name, stride * parts));
+                continue;
+            }
+            // one part of every keyframe for one morph target, or the only value there is
+            let column = |part: usize, target: usize| -> Vec<AnimatedValue> {
+                return elements.iter().skip(part * stride + target).step_by(stride * parts).copied().collect();
+            };
+            let track = |target: usize| match interpolation {
+                Interpolation::Cubic => Track::with_tangents(times.clone(), column(1, target), column(0, target), column(2, target)),
+                _ => Track::new(interpolation, times.clone(), column(0, target)),
+            };
+            let values = column(if parts == 3 { 1 } else { 0 }, 0);
+
+            let mut tracks: Vec<(Property, Result<Track, String>)> = Vec::new();
+            if let Some((model, targets)) = morph {
+                for target in 0..targets {
+                    tracks.push((Property::MorphWeight(model, target), track(target)));
+                }
+            }
+            for &(model, joint) in &joints {
+                let property = match path {
+                    "translation" => Property::JointTranslation(model, joint),
+                    "rotation" => Property::JointRotation(model, joint),
+                    _ => Property::JointScale(model, joint),
+                };
+                tracks.push((property, track(0)));
+            }
+            if let Some(model) = model {
+                match path {
+                    "translation" => tracks.push((Property::ModelPosition(model), track(0))),
+                    "scale" => tracks.push((Property::ModelScale(model), track(0))),
+                    _ => {
+                        // pitch and yaw tangents don't follow from quaternion ones, cubic rotations get smooth ones instead
+                        let (rotations, rolled) = unwrapped_rotations(&values);
+                        if rolled {
+                            warnings.push(format!("{}: roll is not supported and was dropped", name));
+                        }
+                        tracks.push((Property::ModelRotation(model), Track::new(interpolation, times.clone(), rotations)));
+                    }
+                }
+            }
+
+            for (property, track) in tracks {
+                match track {
+                    Ok(track) => clip.add_channel(property, track).expect("keyframe widths match the property"),
+                    Err(reason) => {
+                        warnings.push(format!("{}: {}, ignored", name, reason));
+                        break;
+                    }
+                }
+            }
+        }
+
+        if !clip.channels.is_empty() {
+            clips.push(clip);
+        }
+    }
+
+    return Ok(clips);
+}
+
+
+
+// quaternions to (pitch, yaw), with yaw kept within half a turn of the previous key so interpolation takes
+// the short way around
+fn unwrapped_rotations(quaternions: &[AnimatedValue]) -> (Vec<AnimatedValue>, bool) {
+    let mut rotations: Vec<AnimatedValue> = Vec::with_capacity(quaternions.len());
+    let mut rolled = false;
+    let mut previous_yaw: Option<f32> = None;
+    for quaternion in quaternions {
+        let AnimatedValue::Quaternion(q) = *quaternion else { continue };
+        let (pitch, mut yaw, roll) = quaternion_pitch_yaw(q);
+        if let Some(previous) = previous_yaw {
+            yaw = previous + (yaw - previous + 180.0).rem_euclid(360.0) - 180.0;
+        }
+        previous_yaw = Some(yaw);
+        rolled |= roll;
+        rotations.push(AnimatedValue::Vector2(vec2!(pitch, yaw)));
+    }
+    return (rotations, rolled);
+}
+
+
+
+fn node_matrix(node: &Json) -> Mat4 {
+    if let Some(matrix) = node.get("matrix").and_then(Json::as_f32_array).filter(|m| m.len() == 16) {
+        return matrix.try_into().unwrap();
+    }
+
+    let translation = node.get("translation").and_then(Json::as_f32_array).filter(|t| t.len() == 3).unwrap_or(vec![0.0; 3]);
+    let rotation = node.get("rotation").and_then(Json::as_f32_array).filter(|r| r.len() == 4).unwrap_or(vec![0.0, 0.0, 0.0, 1.0]);
+    let scale = node.get("scale").and_then(Json::as_f32_array).filter(|s| s.len() == 3).unwrap_or(vec![1.0; 3]);
+
+    return trs_matrix(
+        vec3!(translation[0], translation[1], translation[2]),
+        [rotation[0], rotation[1], rotation[2], rotation[3]],
+        vec3!(scale[0], scale[1], scale[2]),
+    );
+}
+
@@ -0,0 +1,321 @@
+use super::error::ImageError;
+
+
+
+const MAX_BITS: usize = 15;
+
+pub(super) const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+pub(super) const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+pub(super) const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+    8193, 12289, 16385, 24577,
+];
+pub(super) const DIST_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+// order code length code lengths are stored in
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+
+
+/// zlib stream (RFC 1950) wrapped around deflate (RFC 1951)
+pub(crate) fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>, ImageError> {
+    if data.len() < 6 {
+        return Err(ImageError::Inflate("zlib stream too short"));
+    }
+
+    let cmf = data[0];
+    let flg = data[1];
+    if cmf & 0x0F != 8 || cmf >> 4 > 7 {
+        return Err(ImageError::Inflate("zlib compression method is not deflate"));
+    }
+    if !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) {
+        return Err(ImageError::Inflate("zlib header check bits are wrong"));
+    }
+    if flg & 0x20 != 0 {
+        return Err(ImageError::Inflate("zlib preset dictionaries are not supported"));
+    }
+
+    let mut bits = BitReader::new(&data[2..]);
+    let out = inflate(&mut bits)?;
+
+    let trailer_start = 2 + bits.byte_position();
+    let trailer = data.get(trailer_start..trailer_start + 4).ok_or(ImageError::Inflate("missing adler32 checksum"))?;
+    let stored = u32::from_be_bytes(trailer.try_into().unwrap());
+    let computed = adler32(&out);
+    if stored != computed {
+        return Err(ImageError::AdlerMismatch { stored, computed });
+    }
+
+    return Ok(out);
+}
+
+
+
+pub(super) fn adler32(data: &[u8]) -> u32 {
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+    // 5552 is the largest block that can't overflow before the modulo
+    for block in data.chunks(5552) {
+        for &byte in block {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    return (b << 16) | a;
+}
+
+
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit_buf: u32,
+    bit_count: u32,
+}
+
+
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        return Self { data, pos: 0, bit_buf: 0, bit_count: 0 };
+    }
+
+
+    fn bits(&mut self, count: u32) -> Result<u32, ImageError> {
+        while self.bit_count < count {
+            let byte = *self.data.get(self.pos).ok_or(ImageError::Inflate("compressed data ends unexpectedly"))?;
+            self.pos += 1;
+            self.bit_buf |= (byte as u32) << self.bit_count;
+            self.bit_count += 8;
+        }
+
+        let value = if count == 0 { 0 } else { self.bit_buf & ((1u32 << count) - 1) };
+        self.bit_buf = if count >= 32 { 0 } else { self.bit_buf >> count };
+        self.bit_count -= count;
+        return Ok(value);
+    }
+
+
+    // drops the remaining bits of the current byte
+    fn align_to_byte(&mut self) {
+        self.bit_buf = 0;
+        self.bit_count = 0;
+    }
+
+
+    // whole bytes consumed, unused buffered bytes are handed back
+    fn byte_position(&self) -> usize {
+        return self.pos - (self.bit_count / 8) as usize;
+    }
+}
+
+
+
+// canonical huffman code stored as symbol counts per length plus symbols sorted by code
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self, ImageError> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+
+        // reject over-subscribed codes, incomplete ones are legal (e.g. a single distance code)
+        let mut left: i32 = 1;
+        for count in counts.iter().skip(1) {
+            left = (left << 1) - *count as i32;
+            if left < 0 {
+                return Err(ImageError::Inflate("over-subscribed huffman code"));
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for len in 1..=MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+
+        return Ok(Self { counts, symbols });
+    }
+
+
+    fn decode(&self, bits: &mut BitReader) -> Result<u16, ImageError> {
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+
+        for len in 1..=MAX_BITS {
+            code |= bits.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        return Err(ImageError::Inflate("invalid huffman code"));
+    }
+}
+
+
+
+fn inflate(bits: &mut BitReader) -> Result<Vec<u8>, ImageError> {
+    let mut out = Vec::new();
+
+    loop {
+        let is_final = bits.bits(1)? == 1;
+        match bits.bits(2)? {
+            0 => inflate_stored(bits, &mut out)?,
+            1 => {
+                let (literals, distances) = fixed_codes()?;
+                inflate_block(bits, &mut out, &literals, &distances)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_codes(bits)?;
+                inflate_block(bits, &mut out, &literals, &distances)?;
+            }
+            _ => return Err(ImageError::Inflate("invalid deflate block type")),
+        }
+
+        if is_final {
+            return Ok(out);
+        }
+    }
+}
+
+
+
+fn inflate_stored(bits: &mut BitReader, out: &mut Vec<u8>) -> Result<(), ImageError> {
+    bits.align_to_byte();
+    let pos = bits.pos;
+
+    let header = bits.data.get(pos..pos + 4).ok_or(ImageError::Inflate("stored block header cut short"))?;
+    let len = u16::from_le_bytes([header[0], header[1]]);
+    let nlen = u16::from_le_bytes([header[2], header[3]]);
+    if len != !nlen {
+        return Err(ImageError::Inflate("stored block length does not match its complement"));
+    }
+
+    let start = pos + 4;
+    let block = bits.data.get(start..start + len as usize).ok_or(ImageError::Inflate("stored block extends past end of data"))?;
+    out.extend_from_slice(block);
+    bits.pos = start + len as usize;
+    return Ok(());
+}
+
+
+
+fn fixed_codes() -> Result<(Huffman, Huffman), ImageError> {
+    let mut lengths = [0u8; 288];
+    lengths[0..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..288].fill(8);
+
+    return Ok((Huffman::new(&lengths)?, Huffman::new(&[5u8; 30])?));
+}
+
+
+
+fn dynamic_codes(bits: &mut BitReader) -> Result<(Huffman, Huffman), ImageError> {
+    let literal_count = bits.bits(5)? as usize + 257;
+    let distance_count = bits.bits(5)? as usize + 1;
+    let code_length_count = bits.bits(4)? as usize + 4;
+    if literal_count > 286 || distance_count > 30 {
+        return Err(ImageError::Inflate("too many literal or distance codes"));
+    }
+
+    let mut code_length_lengths = [0u8; 19];
+    for &index in CODE_LENGTH_ORDER.iter().take(code_length_count) {
+        code_length_lengths[index] = bits.bits(3)? as u8;
+    }
+    let code_length_code = Huffman::new(&code_length_lengths)?;
+
+    let mut lengths = vec![0u8; literal_count + distance_count];
+    let mut i = 0;
+    while i < lengths.len() {
+        let symbol = code_length_code.decode(bits)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                if i == 0 {
+                    return Err(ImageError::Inflate("length repeat with no previous length"));
+                }
+                (lengths[i - 1], 3 + bits.bits(2)? as usize)
+            }
+            17 => (0, 3 + bits.bits(3)? as usize),
+            _ => (0, 11 + bits.bits(7)? as usize),
+        };
+
+        if i + repeat > lengths.len() {
+            return Err(ImageError::Inflate("code lengths overflow the declared count"));
+        }
+        lengths[i..i + repeat].fill(value);
+        i += repeat;
+    }
+
+    if lengths[256] == 0 {
+        return Err(ImageError::Inflate("missing end-of-block code"));
+    }
+
+    let literals = Huffman::new(&lengths[..literal_count])?;
+    let distances = Huffman::new(&lengths[literal_count..])?;
+    return Ok((literals, distances));
+}
+
+
+
+fn inflate_block(bits: &mut BitReader, out: &mut Vec<u8>, literals: &Huffman, distances: &Huffman) -> Result<(), ImageError> {
+    loop {
+        let symbol = literals.decode(bits)? as usize;
+
+        if symbol < 256 {
+            out.push(symbol as u8);
+            continue;
+        }
+        if symbol == 256 {
+            return Ok(());
+        }
+
+        let length_index = symbol - 257;
+        if length_index >= LENGTH_BASE.len() {
+            return Err(ImageError::Inflate("invalid length symbol"));
+        }
+        let length = LENGTH_BASE[length_index] as usize + bits.bits(LENGTH_EXTRA[length_index] as u32)? as usize;
+
+        let dist_index = distances.decode(bits)? as usize;
+        if dist_index >= DIST_BASE.len() {
+            return Err(ImageError::Inflate("invalid distance symbol"));
+        }
+        let distance = DIST_BASE[dist_index] as usize + bits.bits(DIST_EXTRA[dist_index] as u32)? as usize;
+        if distance > out.len() {
+            return Err(ImageError::Inflate("distance reaches before start of output"));
+        }
+
+        // byte by byte, the copy may overlap its own output
+        let start = out.len() - distance;
+        for i in 0..length {
+            out.push(out[start + i]);
+        }
+    }
+}
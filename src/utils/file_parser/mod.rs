@@ -0,0 +1,25 @@
+pub mod bitmap;
+pub mod error;
+pub mod gltf;
+mod deflate;
+mod inflate;
+pub mod mtl;
+pub mod obj;
+pub mod ply;
+pub mod png;
+pub mod scene_file;
+pub mod stl;
+pub mod texture_file;
+pub mod tga;
+
+pub use bitmap::*;
+pub use error::*;
+pub use gltf::*;
+pub use mtl::*;
+pub use obj::*;
+pub use ply::*;
+pub use png::*;
+pub use scene_file::*;
+pub use stl::*;
+pub use texture_file::*;
+pub use tga::*;
@@ -0,0 +1,171 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Result};
+use std::path::Path;
+
+use crate::types::shader::MaterialShader;
+use crate::{Material, Texture, Vec3};
+use super::texture_file::read_texture;
+
+
+
+/// statements that can't be used are skipped with a warning naming the file and line
+pub fn parse_mtl(mtl_file_path: &str, warnings: &mut Vec<String>) -> Result<Vec<Material>> {
+    let file = File::open(mtl_file_path)?;
+    let reader = BufReader::new(file);
+    let base_dir = Path::new(mtl_file_path).parent().unwrap_or(Path::new(""));
+
+    let mut materials: Vec<Material> = Vec::new();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let mut warn = |message: String| warnings.push(format!("{}:{}: {}", mtl_file_path, index + 1, message));
+        let line = line.split('#').next().unwrap_or("");
+        let parts: Vec<&str> = line.split_whitespace().collect();
+
+        if parts.is_empty() {
+            continue;
+        }
+
+        if parts[0] == "newmtl" {
+            materials.push(Material::new(&parts[1..].join(" ")));
+            continue;
+        }
+
+        let Some(material) = materials.last_mut() else {
+            warn(format!("'{}' before any newmtl ignored", parts[0]));
+            continue;
+        };
+
+        match parts[0] {
+            "Ka" => material.ambient = parse_color(&parts, material.ambient, &mut warn),
+            "Kd" => material.diffuse = parse_color(&parts, material.diffuse, &mut warn),
+            "Ks" => material.specular = parse_color(&parts, material.specular, &mut warn),
+            "Ns" => material.shininess = parse_scalar(&parts, material.shininess, &mut warn),
+            "d" => material.opacity = parse_scalar(&parts, material.opacity, &mut warn),
+            "Tr" => material.opacity = 1.0 - parse_scalar(&parts, 1.0 - material.opacity, &mut warn),
+            "Ke" => material.emissive = parse_color(&parts, material.emissive, &mut warn),
+            "Pm" => material.metallic = parse_scalar(&parts, material.metallic, &mut warn),
+            "Pr" => material.roughness = parse_scalar(&parts, material.roughness, &mut warn),
+            "illum" => material.illum = parse_scalar(&parts, material.illum as f32, &mut warn) as u32,
+            "map_Kd" => material.diffuse_map = parse_map_path(&parts, base_dir, &mut warn),
+            "map_Bump" | "map_bump" | "bump" => {
+                material.bump_map = parse_map_path(&parts, base_dir, &mut warn);
+                material.bump_multiplier = map_option(&parts, "-bm").unwrap_or(1.0);
+            }
+            "norm" => material.normal_map = parse_map_path(&parts, base_dir, &mut warn),
+            "map_d" => material.alpha_map = parse_map_path(&parts, base_dir, &mut warn),
+            _ => {}
+        }
+    }
+
+    return Ok(materials);
+}
+
+
+
+/// builds a shader for the material, textures that fail to load are skipped with a warning.
+/// nothing is blended, an alpha map cuts the surface out at half opacity and plain d/Tr is ignored
+pub fn load_material_shader(material: &Material, warnings: &mut Vec<String>) -> MaterialShader {
+    let mut shader = MaterialShader::new(material.clone());
+    shader.diffuse_texture = load_map(&material.diffuse_map, warnings);
+    // height maps only carry slopes, they light like the normal map they turn into
+    shader.normal_texture = match &material.normal_map {
+        Some(_) => load_map(&material.normal_map, warnings),
+        None => load_map(&material.bump_map, warnings).map(|height| height.height_to_normal_map(material.bump_multiplier)),
+    };
+    shader.alpha_texture = load_map(&material.alpha_map, warnings);
+    if shader.alpha_texture.is_some() {
+        shader.alpha_cutoff = Some(0.5);
+    } else if material.opacity < 1.0 {
+        warnings.push(format!("material '{}': transparency is ignored, drawn opaque", material.name));
+    }
+    return shader;
+}
+
+
+
+fn load_map(path: &Option<String>, warnings: &mut Vec<String>) -> Option<Texture> {
+    let path = path.as_ref()?;
+    match read_texture(path) {
+        Ok(texture) => return Some(texture),
+        Err(err) => {
+            warnings.push(format!("failed to load texture '{}': {}", path, err));
+            return None;
+        }
+    }
+}
+
+
+
+fn parse_color(parts: &[&str], fallback: Vec3, warn: &mut impl FnMut(String)) -> Vec3 {
+    // "Kd r" is shorthand for a gray, spectral and xyz forms aren't supported
+    let values: Vec<f32> = parts[1..].iter().filter_map(|p| p.parse().ok()).collect();
+    match values.len() {
+        1 => return Vec3 { x: values[0], y: values[0], z: values[0] },
+        n if n >= 3 => return Vec3 { x: values[0], y: values[1], z: values[2] },
+        _ => {
+            warn(format!("unsupported color statement '{}', keeping previous value", parts.join(" ")));
+            return fallback;
+        }
+    }
+}
+
+
+
+fn parse_scalar(parts: &[&str], fallback: f32, warn: &mut impl FnMut(String)) -> f32 {
+    // "d -halo 0.5" puts the value last
+    match parts.last().and_then(|p| p.parse().ok()) {
+        Some(value) => return value,
+        None => {
+            warn(format!("failed to parse '{}', keeping previous value", parts.join(" ")));
+            return fallback;
+        }
+    }
+}
+
+
+
+// number of arguments taken by each texture map option, -o/-s/-t take up to three
+fn map_option_arity(option: &str) -> usize {
+    match option {
+        "-o" | "-s" | "-t" => return 3,
+        "-mm" => return 2,
+        _ => return 1,
+    }
+}
+
+
+
+// first argument of a texture map option, "-bm 0.5"
+fn map_option(parts: &[&str], option: &str) -> Option<f32> {
+    let index = parts.iter().position(|part| *part == option)?;
+    return parts.get(index + 1)?.parse().ok();
+}
+
+
+
+fn parse_map_path(parts: &[&str], base_dir: &Path, warn: &mut impl FnMut(String)) -> Option<String> {
+    let mut i = 1;
+    while i < parts.len() && parts[i].starts_with('-') {
+        let arity = map_option_arity(parts[i]);
+        i += 1;
+        let mut taken = 0;
+        while taken < arity && i < parts.len() - 1 {
+            // optional trailing numeric arguments stop at the first non-number
+            if taken > 0 && parts[i].parse::<f32>().is_err() {
+                break;
+            }
+            i += 1;
+            taken += 1;
+        }
+    }
+
+    if i >= parts.len() {
+        warn(format!("texture map statement '{}' has no file name", parts.join(" ")));
+        return None;
+    }
+
+    // file names may contain spaces, exporters on windows write backslashes
+    let file_name = parts[i..].join(" ").replace('\\', "/");
+    return Some(base_dir.join(file_name).to_string_lossy().into_owned());
+}
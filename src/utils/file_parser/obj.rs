@@ -0,0 +1,528 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use crate::types::model::SubMesh;
+use crate::types::normals::{generate_normals, NormalMode, NormalWeighting, DEFAULT_SMOOTHING_ANGLE};
+use crate::types::tangents::generate_tangents;
+use crate::types::shader::{Shader, SolidShader};
+use crate::types::vertex::Vertex;
+use crate::{Material, Model};
+use crate::{Vec3, vec3, Vec2, vec2};
+use super::error::{ObjError, ObjErrorKind};
+use super::mtl::{load_material_shader, parse_mtl};
+
+
+
+pub struct ObjFile {
+    pub meshes: Vec<ObjMesh>,
+    pub warnings: Vec<ObjWarning>,
+}
+
+
+
+/// faces sharing one `o` object and `g` group, split by material into submeshes
+pub struct ObjMesh {
+    pub object: String,
+    pub group: String,
+    pub model: Model,
+    // smoothing group per triangle of `model.vertices`, 0 is "off"
+    pub smoothing_groups: Vec<u32>,
+}
+
+
+
+#[derive(Debug, Clone)]
+pub struct ObjWarning {
+    pub line: usize,
+    pub message: String,
+}
+
+
+
+impl fmt::Display for ObjWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+
+
+// faces collected for one (object, group) pair, bucketed by material in order of first use
+struct MeshBuilder {
+    object: String,
+    group: String,
+    material_groups: Vec<MaterialGroup>,
+    current_material: usize,
+}
+
+
+
+struct MaterialGroup {
+    material: Option<String>,
+    usemtl_line: usize,
+    vertices: Vec<Vertex>,
+    // smoothing group per triangle
+    smoothing_groups: Vec<u32>,
+}
+
+
+
+impl MaterialGroup {
+    fn new(material: Option<String>, usemtl_line: usize) -> Self {
+        return Self { material, usemtl_line, vertices: Vec::new(), smoothing_groups: Vec::new() };
+    }
+}
+
+
+
+impl MeshBuilder {
+    fn new(object: &str, group: &str) -> Self {
+        return Self {
+            object: object.to_string(),
+            group: group.to_string(),
+            material_groups: vec![MaterialGroup::new(None, 0)],
+            current_material: 0,
+        };
+    }
+
+
+    fn use_material(&mut self, name: Option<String>, line: usize) {
+        self.current_material = match self.material_groups.iter().position(|group| group.material == name) {
+            Some(index) => index,
+            None => {
+                self.material_groups.push(MaterialGroup::new(name, line));
+                self.material_groups.len() - 1
+            }
+        };
+    }
+
+
+    fn is_empty(&self) -> bool {
+        return self.material_groups.iter().all(|group| group.vertices.is_empty());
+    }
+}
+
+
+
+pub fn parse_obj(obj_file_path: &str) -> Result<ObjFile, ObjError> {
+    let file = File::open(obj_file_path).map_err(|err| ObjError::new(0, 0, ObjErrorKind::Io(err)))?;
+    let reader = BufReader::new(file);
+    let base_dir = Path::new(obj_file_path).parent().unwrap_or(Path::new(""));
+
+    let mut vertices: Vec<Vec3> = Vec::new();
+    let mut texcoords: Vec<Vec2> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+
+    let mut materials: HashMap<String, Material> = HashMap::new();
+    let mut warnings: Vec<ObjWarning> = Vec::new();
+
+    let mut builders: Vec<MeshBuilder> = vec![MeshBuilder::new("", "")];
+    let mut current_builder = 0;
+    let mut smoothing_group = 0;
+    let mut has_smoothing_groups = false;
+
+    for (line_number, line) in logical_lines(reader)? {
+        let parts = tokenize(&line);
+
+        if parts.is_empty() {
+            continue;
+        }
+
+        let statement = parts[0].1;
+        match statement {
+            "v" => {
+                // extra values (w, or vertex colors) are ignored
+                let x = parse_float(&parts, 1, line_number, &line)?;
+                let y = parse_float(&parts, 2, line_number, &line)?;
+                let z = parse_float(&parts, 3, line_number, &line)?;
+                vertices.push(Vec3 { x, y, z });
+            }
+            "vt" => {
+                let u = parse_float(&parts, 1, line_number, &line)?;
+                let v = if parts.len() > 2 { parse_float(&parts, 2, line_number, &line)? } else { 0.0 };
+                // OBJ puts v = 0 at the bottom, textures are stored top row first
+                texcoords.push(Vec2 { x: u, y: 1.0 - v });
+            }
+            "vn" => {
+                let x = parse_float(&parts, 1, line_number, &line)?;
+                let y = parse_float(&parts, 2, line_number, &line)?;
+                let z = parse_float(&parts, 3, line_number, &line)?;
+                normals.push(Vec3 { x, y, z });
+            }
+            "f" => {
+                let face_vertices = parse_face(&parts, line_number, &vertices, &texcoords, &normals)?;
+                if face_vertices.len() < 3 {
+                    warnings.push(ObjWarning { line: line_number, message: format!("face with {} vertices skipped", face_vertices.len()) });
+                    continue;
+                }
+
+                let builder = &mut builders[current_builder];
+                let group = &mut builder.material_groups[builder.current_material];
+                let triangles = triangulate_face(&face_vertices);
+                group.smoothing_groups.extend(std::iter::repeat_n(smoothing_group, triangles.len() / 3));
+                group.vertices.extend(triangles);
+            }
+            "o" | "g" => {
+                let name = join_arguments(&parts);
+                let (object, group) = if statement == "o" {
+                    (name, String::new())
+                } else {
+                    (builders[current_builder].object.clone(), name)
+                };
+
+                // the active material carries across object and group boundaries
+                let previous = &builders[current_builder];
+                let material = previous.material_groups[previous.current_material].material.clone();
+
+                current_builder = match builders.iter().position(|b| b.object == object && b.group == group) {
+                    Some(index) => index,
+                    None => {
+                        builders.push(MeshBuilder::new(&object, &group));
+                        builders.len() - 1
+                    }
+                };
+                if material.is_some() {
+                    builders[current_builder].use_material(material, line_number);
+                }
+            }
+            "s" => {
+                let (column, value) = required(&parts, 1, line_number, &line)?;
+                has_smoothing_groups = true;
+                smoothing_group = match value {
+                    "off" => 0,
+                    group => group
+                        .parse()
+                        .map_err(|_| ObjError::new(line_number, column, ObjErrorKind::InvalidNumber(group.to_string())))?,
+                };
+            }
+            "mtllib" => {
+                // several libraries may be listed on one line
+                for (_, library) in &parts[1..] {
+                    let mtl_path = base_dir.join(library).to_string_lossy().into_owned();
+                    let mut library_warnings = Vec::new();
+                    let library = parse_mtl(&mtl_path, &mut library_warnings);
+                    warnings.extend(library_warnings.into_iter().map(|message| ObjWarning { line: line_number, message }));
+                    match library {
+                        Ok(library_materials) => {
+                            for material in library_materials {
+                                materials.insert(material.name.clone(), material);
+                            }
+                        }
+                        Err(err) => warnings.push(ObjWarning {
+                            line: line_number,
+                            message: format!("failed to read material library '{}': {}", mtl_path, err),
+                        }),
+                    }
+                }
+            }
+            "usemtl" => {
+                required(&parts, 1, line_number, &line)?;
+                builders[current_builder].use_material(Some(join_arguments(&parts)), line_number);
+            }
+            _ => {
+                warnings.push(ObjWarning { line: line_number, message: format!("unsupported statement '{}' skipped", statement) });
+            }
+        }
+    }
+
+    let mut meshes: Vec<ObjMesh> = Vec::new();
+
+    for builder in builders {
+        if builder.is_empty() {
+            continue;
+        }
+
+        let uses_materials = builder.material_groups.len() > 1;
+        let mut triangulated_vertices: Vec<Vertex> = Vec::new();
+        let mut smoothing_groups: Vec<u32> = Vec::new();
+        let mut submeshes: Vec<SubMesh> = Vec::new();
+
+        for group in builder.material_groups {
+            if group.vertices.is_empty() {
+                continue;
+            }
+
+            let name = group.material;
+            let usemtl_line = group.usemtl_line;
+            let start = triangulated_vertices.len();
+            triangulated_vertices.extend(group.vertices);
+            smoothing_groups.extend(group.smoothing_groups);
+
+            if uses_materials {
+                let shader: Box<dyn Shader> = match name.as_ref().map(|name| materials.get(name)) {
+                    Some(Some(material)) => {
+                        let mut texture_warnings = Vec::new();
+                        let shader = load_material_shader(material, &mut texture_warnings);
+                        warnings.extend(texture_warnings.into_iter().map(|message| ObjWarning { line: usemtl_line, message }));
+                        Box::new(shader)
+                    }
+                    Some(None) => {
+                        warnings.push(ObjWarning {
+                            line: usemtl_line,
+                            message: format!("material '{}' not found in any material library, using solid shader", name.as_deref().unwrap()),
+                        });
+                        Box::new(SolidShader::new())
+                    }
+                    None => Box::new(SolidShader::new()),
+                };
+
+                submeshes.push(SubMesh {
+                    material: name.unwrap_or_default(),
+                    vertices: start..triangulated_vertices.len(),
+                    shader,
+                });
+            }
+        }
+
+        // faces without vn indices have zero normals. theirs are generated from the whole mesh, smoothing groups
+        // decide what's smooth, or a crease angle if there are none
+        let missing_normals: Vec<usize> = triangulated_vertices
+            .chunks_exact(3)
+            .enumerate()
+            .filter(|(_, triangle)| triangle.iter().any(|vertex| vertex.normal == vec3!(0, 0, 0)))
+            .map(|(face, _)| face)
+            .collect();
+        if !missing_normals.is_empty() {
+            let mut generated = triangulated_vertices.clone();
+            if has_smoothing_groups {
+                let mode = NormalMode::Smooth { angle_threshold: 180.0, weighting: NormalWeighting::Angle };
+                generate_normals(&mut generated, Some(&smoothing_groups), mode);
+            } else {
+                let mode = NormalMode::Smooth { angle_threshold: DEFAULT_SMOOTHING_ANGLE, weighting: NormalWeighting::Angle };
+                generate_normals(&mut generated, None, mode);
+            }
+            for face in missing_normals {
+                for corner in face * 3..face * 3 + 3 {
+                    triangulated_vertices[corner].normal = generated[corner].normal;
+                }
+            }
+        }
+        generate_tangents(&mut triangulated_vertices);
+
+        let mut model = Model::new(triangulated_vertices, Box::new(SolidShader::new()));
+        model.submeshes = submeshes;
+        meshes.push(ObjMesh { object: builder.object, group: builder.group, model, smoothing_groups });
+    }
+
+    return Ok(ObjFile { meshes, warnings });
+}
+
+
+
+impl ObjMesh {
+    /// regenerates normals, blending only within the mesh's smoothing groups
+    pub fn generate_normals(&mut self, mode: NormalMode) {
+        generate_normals(&mut self.model.vertices, Some(&self.smoothing_groups), mode);
+    }
+}
+
+
+
+impl ObjFile {
+    /// merges every object and group into one model, keeping material submeshes
+    pub fn into_model(self) -> Model {
+        let any_submeshes = self.meshes.iter().any(|mesh| !mesh.model.submeshes.is_empty());
+
+        let mut vertices: Vec<Vertex> = Vec::new();
+        let mut submeshes: Vec<SubMesh> = Vec::new();
+
+        for mesh in self.meshes {
+            let offset = vertices.len();
+            let model = mesh.model;
+
+            if any_submeshes && model.submeshes.is_empty() {
+                submeshes.push(SubMesh {
+                    material: String::new(),
+                    vertices: offset..offset + model.vertices.len(),
+                    shader: model.shader,
+                });
+            } else {
+                for submesh in model.submeshes {
+                    submeshes.push(SubMesh {
+                        material: submesh.material,
+                        vertices: submesh.vertices.start + offset..submesh.vertices.end + offset,
+                        shader: submesh.shader,
+                    });
+                }
+            }
+
+            vertices.extend(model.vertices);
+        }
+
+        let mut model = Model::new(vertices, Box::new(SolidShader::new()));
+        model.submeshes = submeshes;
+        return model;
+    }
+}
+
+
+
+// joins `\` continued lines and strips comments, keeping the first physical line number
+fn logical_lines(reader: impl BufRead) -> Result<Vec<(usize, String)>, ObjError> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+
+    for (index, line) in reader.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.map_err(|err| ObjError::new(line_number, 0, ObjErrorKind::Io(err)))?;
+        let line = line.split('#').next().unwrap_or("");
+
+        let (start, mut text) = pending.take().unwrap_or((line_number, String::new()));
+        if let Some(continued) = line.trim_end().strip_suffix('\\') {
+            text.push_str(continued);
+            text.push(' ');
+            pending = Some((start, text));
+            continue;
+        }
+
+        text.push_str(line);
+        lines.push((start, text));
+    }
+
+    if let Some(last) = pending {
+        lines.push(last);
+    }
+
+    return Ok(lines);
+}
+
+
+
+// whitespace separated tokens with their 1-based column
+fn tokenize(line: &str) -> Vec<(usize, &str)> {
+    let mut tokens = Vec::new();
+    let mut start: Option<usize> = None;
+
+    for (i, c) in line.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(s)) => {
+                tokens.push((s, &line[s..i]));
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        tokens.push((s, &line[s..]));
+    }
+
+    return tokens.into_iter().map(|(s, token)| (line[..s].chars().count() + 1, token)).collect();
+}
+
+
+
+// names may contain spaces
+fn join_arguments(parts: &[(usize, &str)]) -> String {
+    return parts[1..].iter().map(|(_, part)| *part).collect::<Vec<&str>>().join(" ");
+}
+
+
+
+fn required<'a>(parts: &[(usize, &'a str)], index: usize, line_number: usize, line: &str) -> Result<(usize, &'a str), ObjError> {
+    match parts.get(index) {
+        Some(part) => return Ok(*part),
+        None => {
+            let column = line.trim_end().chars().count() + 1;
+            return Err(ObjError::new(
+                line_number,
+                column,
+                ObjErrorKind::MissingArgument { statement: parts[0].1.to_string(), position: index },
+            ));
+        }
+    }
+}
+
+
+
+fn parse_float(parts: &[(usize, &str)], index: usize, line_number: usize, line: &str) -> Result<f32, ObjError> {
+    let (column, token) = required(parts, index, line_number, line)?;
+    return token
+        .parse()
+        .map_err(|_| ObjError::new(line_number, column, ObjErrorKind::InvalidNumber(token.to_string())));
+}
+
+
+
+// 1-based, negative indices count back from the most recent element
+fn resolve_index(token: &str, column: usize, line_number: usize, count: usize, element: &'static str) -> Result<usize, ObjError> {
+    let index: i64 = token
+        .parse()
+        .map_err(|_| ObjError::new(line_number, column, ObjErrorKind::InvalidIndex(token.to_string())))?;
+
+    let resolved = if index > 0 { index - 1 } else { count as i64 + index };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(ObjError::new(line_number, column, ObjErrorKind::IndexOutOfRange { element, index, count }));
+    }
+
+    return Ok(resolved as usize);
+}
+
+
+
+fn parse_face(
+    parts: &[(usize, &str)],
+    line_number: usize,
+    vertices: &[Vec3],
+    texture_coords: &[Vec2],
+    normals: &[Vec3],
+) -> Result<Vec<Vertex>, ObjError> {
+    let mut face_vertices: Vec<Vertex> = Vec::new();
+
+    for &(column, part) in &parts[1..] {
+        // column of each slash separated index within the token
+        let mut indices: Vec<(usize, &str)> = Vec::new();
+        let mut offset = 0;
+        for index in part.split('/') {
+            indices.push((column + offset, index));
+            offset += index.chars().count() + 1;
+        }
+
+        let (vertex_column, vertex_token) = indices[0];
+        let position = vertices[resolve_index(vertex_token, vertex_column, line_number, vertices.len(), "vertex")?];
+
+        let mut texcoord = vec2!(0.0, 0.0 );
+        if let Some(&(texture_column, texture_token)) = indices.get(1).filter(|(_, token)| !token.is_empty()) {
+            texcoord = texture_coords[resolve_index(texture_token, texture_column, line_number, texture_coords.len(), "texture coordinate")?];
+        }
+
+        let mut normal = vec3!(0.0, 0.0, 0.0);
+        if let Some(&(normal_column, normal_token)) = indices.get(2).filter(|(_, token)| !token.is_empty()) {
+            normal = normals[resolve_index(normal_token, normal_column, line_number, normals.len(), "normal")?];
+        }
+
+        face_vertices.push(Vertex {
+            position,
+            texcoord,
+            normal,
+            tangent: vec3!(0.0, 0.0, 0.0),
+            bitangent_sign: 1.0,
+            color: vec3!(1.0, 1.0, 1.0),
+            joints: [0; 4],
+            weights: [0.0; 4],
+        });
+    }
+    return Ok(face_vertices);
+}
+
+
+
+// fan triangulation, faces are assumed convex
+pub(crate) fn triangulate_face(polygon: &[Vertex]) -> Vec<Vertex> {
+    if polygon.len() < 3 {
+        return vec![];
+    }
+
+    let mut triangles = Vec::with_capacity((polygon.len() - 2) * 3);
+    let anchor = polygon[0];
+
+    for i in 1..(polygon.len() - 1) {
+        triangles.push(anchor);
+        triangles.push(polygon[i]);
+        triangles.push(polygon[i + 1]);
+    }
+
+    return triangles;
+}
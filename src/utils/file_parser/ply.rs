@@ -0,0 +1,502 @@
+use std::path::Path;
+use std::str::SplitAsciiWhitespace;
+
+use crate::types::normals::{generate_normals, NormalMode, NormalWeighting, DEFAULT_SMOOTHING_ANGLE};
+use crate::types::shader::{MaterialShader, Shader, SolidShader, VertexColorShader};
+use crate::types::tangents::generate_tangents;
+use crate::{vec2, vec3, Material, Model, PointCloud, Vec2, Vec3, Vertex};
+use super::error::PlyError;
+use super::obj::triangulate_face;
+use super::texture_file::read_texture;
+
+
+
+/// files with faces load as a mesh, vertex-only files as a point cloud
+pub enum PlyGeometry {
+    Mesh(Model),
+    Points(PointCloud),
+}
+
+
+
+pub struct PlyFile {
+    pub geometry: PlyGeometry,
+    pub comments: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ScalarType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+
+
+#[derive(Debug, Clone, Copy)]
+enum PropertyType {
+    Scalar(ScalarType),
+    List { count: ScalarType, item: ScalarType },
+}
+
+
+
+struct Property {
+    name: String,
+    kind: PropertyType,
+}
+
+
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+
+
+impl ScalarType {
+    fn parse(name: &str) -> Option<ScalarType> {
+        match name {
+            "char" | "int8" => return Some(ScalarType::Int8),
+            "uchar" | "uint8" => return Some(ScalarType::UInt8),
+            "short" | "int16" => return Some(ScalarType::Int16),
+            "ushort" | "uint16" => return Some(ScalarType::UInt16),
+            "int" | "int32" => return Some(ScalarType::Int32),
+            "uint" | "uint32" => return Some(ScalarType::UInt32),
+            "float" | "float32" => return Some(ScalarType::Float32),
+            "double" | "float64" => return Some(ScalarType::Float64),
+            _ => return None,
+        }
+    }
+
+
+    fn size(self) -> usize {
+        match self {
+            ScalarType::Int8 | ScalarType::UInt8 => return 1,
+            ScalarType::Int16 | ScalarType::UInt16 => return 2,
+            ScalarType::Int32 | ScalarType::UInt32 | ScalarType::Float32 => return 4,
+            ScalarType::Float64 => return 8,
+        }
+    }
+
+
+    // colors stored as integers span the type's full range
+    fn color_scale(self) -> f64 {
+        match self {
+            ScalarType::UInt8 | ScalarType::Int8 => return 255.0,
+            ScalarType::UInt16 | ScalarType::Int16 => return 65535.0,
+            ScalarType::UInt32 | ScalarType::Int32 => return u32::MAX as f64,
+            ScalarType::Float32 | ScalarType::Float64 => return 1.0,
+        }
+    }
+}
+
+
+
+enum BodyReader<'a> {
+    // every token takes a character and a separator, so there are at most half as many as the body has bytes
+    Ascii { tokens: SplitAsciiWhitespace<'a>, max_tokens: usize },
+    Binary { bytes: &'a [u8], pos: usize, big_endian: bool },
+}
+
+
+
+impl BodyReader<'_> {
+    // None at end of file or on a malformed ascii number
+    fn scalar(&mut self, kind: ScalarType) -> Option<f64> {
+        match self {
+            BodyReader::Ascii { tokens, .. } => return tokens.next()?.parse().ok(),
+            BodyReader::Binary { bytes, pos, big_endian } => {
+                let size = kind.size();
+                let raw = bytes.get(*pos..*pos + size)?;
+                *pos += size;
+
+                let mut buffer = [0u8; 8];
+                buffer[..size].copy_from_slice(raw);
+                if *big_endian {
+                    buffer[..size].reverse();
+                }
+                let value = match kind {
+                    ScalarType::Int8 => buffer[0] as i8 as f64,
+                    ScalarType::UInt8 => buffer[0] as f64,
+                    ScalarType::Int16 => i16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+                    ScalarType::UInt16 => u16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+                    ScalarType::Int32 => i32::from_le_bytes(buffer[..4].try_into().unwrap()) as f64,
+                    ScalarType::UInt32 => u32::from_le_bytes(buffer[..4].try_into().unwrap()) as f64,
+                    ScalarType::Float32 => f32::from_le_bytes(buffer[..4].try_into().unwrap()) as f64,
+                    ScalarType::Float64 => f64::from_le_bytes(buffer),
+                };
+                return Some(value);
+            }
+        }
+    }
+
+
+    // upper bound on the values of `kind` left, list counts above it can't be right
+    fn max_items(&self, kind: ScalarType) -> usize {
+        match self {
+            BodyReader::Ascii { max_tokens, .. } => return *max_tokens,
+            BodyReader::Binary { bytes, pos, .. } => return bytes.len().saturating_sub(*pos) / kind.size(),
+        }
+    }
+
+
+    // upper bound on the records of `element` left, every property takes at least a token or its scalar,
+    // lists at least their count
+    fn max_records(&self, element: &Element) -> usize {
+        match self {
+            BodyReader::Ascii { max_tokens, .. } => return *max_tokens / element.properties.len().max(1),
+            BodyReader::Binary { bytes, pos, .. } => {
+                let record_size: usize = element.properties.iter().map(|property| match property.kind {
+                    PropertyType::Scalar(kind) => kind.size(),
+                    PropertyType::List { count, .. } => count.size(),
+                }).sum();
+                return bytes.len().saturating_sub(*pos) / record_size.max(1);
+            }
+        }
+    }
+}
+
+
+
+// where each vertex attribute lives among the vertex element's properties
+#[derive(Default)]
+struct VertexLayout {
+    position: [Option<usize>; 3],
+    normal: [Option<usize>; 3],
+    color: [Option<usize>; 3],
+    texcoord: [Option<usize>; 2],
+}
+
+
+
+/// reads ASCII and binary (either endianness) PLY files with optional normals, colors and texture coordinates
+pub fn parse_ply(path: &str) -> Result<PlyFile, PlyError> {
+    let bytes = std::fs::read(path)?;
+    let base_dir = Path::new(path).parent().unwrap_or(Path::new(""));
+
+    let (format, elements, comments, body_start) = parse_header(&bytes)?;
+    let mut warnings: Vec<String> = Vec::new();
+
+    let body = &bytes[body_start..];
+    let mut reader = match format {
+        Format::Ascii => BodyReader::Ascii {
+            tokens: std::str::from_utf8(body).map_err(|_| PlyError::InvalidData {
+                element: elements.first().map(|e| e.name.clone()).unwrap_or_default(),
+                index: 0,
+                message: "ascii body is not valid text".to_string(),
+            })?.split_ascii_whitespace(),
+            max_tokens: body.len() / 2 + 1,
+        },
+        _ => BodyReader::Binary { bytes: body, pos: 0, big_endian: format == Format::BinaryBigEndian },
+    };
+
+    let mut vertices: Vec<Vertex> = Vec::new();
+    let mut faces: Vec<(Vec<usize>, Option<Vec<Vec2>>)> = Vec::new();
+    let mut has_normals = false;
+    let mut has_colors = false;
+    let mut has_texcoords = false;
+
+    for element in &elements {
+        let layout = vertex_layout(element);
+        let is_vertex = element.name == "vertex";
+        let is_face = element.name == "face";
+        if is_vertex {
+            has_normals = layout.normal.iter().all(Option::is_some);
+            has_colors = layout.color.iter().all(Option::is_some);
+            has_texcoords = layout.texcoord.iter().all(Option::is_some);
+            if layout.position.iter().any(Option::is_none) {
+                return Err(PlyError::InvalidData { element: element.name.clone(), index: 0, message: "vertex element needs x, y and z".to_string() });
+            }
+        }
+
+        // a corrupt count would otherwise keep the loop below going long after the data ran out
+        let element_error = |message: String| PlyError::InvalidData { element: element.name.clone(), index: 0, message };
+        if element.count > 0 && element.properties.is_empty() {
+            return Err(element_error(format!("{} records without any properties", element.count)));
+        }
+        if element.count > reader.max_records(element) {
+            return Err(element_error(format!("count {} is more than the rest of the file holds", element.count)));
+        }
+
+        for index in 0..element.count {
+            let invalid = |message: &str| PlyError::InvalidData { element: element.name.clone(), index, message: message.to_string() };
+
+            let mut scalars: Vec<f64> = Vec::with_capacity(element.properties.len());
+            let mut lists: Vec<Vec<f64>> = Vec::new();
+            for property in &element.properties {
+                match property.kind {
+                    PropertyType::Scalar(kind) => scalars.push(reader.scalar(kind).ok_or_else(|| invalid(&format!("missing or invalid '{}'", property.name)))?),
+                    PropertyType::List { count, item } => {
+                        let length = reader.scalar(count).ok_or_else(|| invalid(&format!("missing or invalid '{}' count", property.name)))?;
+                        if length < 0.0 || length.fract() != 0.0 {
+                            return Err(invalid(&format!("invalid '{}' count {}", property.name, length)));
+                        }
+                        // checked before allocating, a corrupt count could ask for gigabytes
+                        if length > reader.max_items(item) as f64 {
+                            return Err(invalid(&format!("'{}' count {} is more than the rest of the file holds", property.name, length)));
+                        }
+                        let mut items: Vec<f64> = Vec::with_capacity(length as usize);
+                        for _ in 0..length as usize {
+                            items.push(reader.scalar(item).ok_or_else(|| invalid(&format!("missing or invalid '{}' item", property.name)))?);
+                        }
+                        // lists keep a placeholder among the scalars so indices stay aligned
+                        scalars.push(lists.len() as f64);
+                        lists.push(items);
+                    }
+                }
+            }
+
+            if is_vertex {
+                vertices.push(build_vertex(element, &layout, &scalars));
+            } else if is_face {
+                faces.push(face_lists(element, &scalars, &lists, index)?);
+            }
+        }
+    }
+
+    let texture_file = comments.iter().find_map(|comment| comment.strip_prefix("TextureFile ")).map(str::trim);
+
+    if faces.is_empty() {
+        let mut point_cloud = PointCloud::new(vertices);
+        if !has_colors {
+            // plain white points are hard to tell apart, shade them by normal if there is one
+            if has_normals {
+                for point in &mut point_cloud.points {
+                    point.color = point.normal * 0.5 + vec3!(0.5, 0.5, 0.5);
+                }
+            }
+        }
+        return Ok(PlyFile { geometry: PlyGeometry::Points(point_cloud), comments, warnings });
+    }
+
+    let mut triangulated: Vec<Vertex> = Vec::new();
+    for (face_index, (indices, face_texcoords)) in faces.iter().enumerate() {
+        let mut polygon: Vec<Vertex> = Vec::with_capacity(indices.len());
+        for (corner, &vertex_index) in indices.iter().enumerate() {
+            let Some(&vertex) = vertices.get(vertex_index) else {
+                return Err(PlyError::InvalidData {
+                    element: "face".to_string(),
+                    index: face_index,
+                    message: format!("vertex index {} out of range, {} vertices", vertex_index, vertices.len()),
+                });
+            };
+            let mut vertex = vertex;
+            if let Some(texcoords) = face_texcoords {
+                vertex.texcoord = texcoords[corner];
+            }
+            polygon.push(vertex);
+        }
+
+        if polygon.len() < 3 {
+            warnings.push(format!("face {} with {} vertices skipped", face_index, polygon.len()));
+            continue;
+        }
+        triangulated.extend(triangulate_face(&polygon));
+    }
+
+    if !has_normals {
+        let mode = NormalMode::Smooth { angle_threshold: DEFAULT_SMOOTHING_ANGLE, weighting: NormalWeighting::Angle };
+        generate_normals(&mut triangulated, None, mode);
+    }
+    generate_tangents(&mut triangulated);
+
+    // MeshLab names the texture in a comment
+    let shader: Box<dyn Shader> = match texture_file {
+        Some(file) => {
+            let texture_path = base_dir.join(file).to_string_lossy().into_owned();
+            let mut shader = MaterialShader::new(Material::new(file));
+            match read_texture(&texture_path) {
+                Ok(texture) => shader.diffuse_texture = Some(texture),
+                Err(err) => warnings.push(format!("failed to load texture '{}': {}", texture_path, err)),
+            }
+            shader.material.diffuse_map = Some(texture_path);
+            Box::new(shader)
+        }
+        None if has_colors => Box::new(VertexColorShader::new()),
+        None => Box::new(SolidShader::new()),
+    };
+    if has_texcoords && texture_file.is_none() {
+        warnings.push("texture coordinates present but no TextureFile comment".to_string());
+    }
+
+    let model = Model::new(triangulated, shader);
+    return Ok(PlyFile { geometry: PlyGeometry::Mesh(model), comments, warnings });
+}
+
+
+
+// (format, elements, comments, offset of the body)
+fn parse_header(bytes: &[u8]) -> Result<(Format, Vec<Element>, Vec<String>, usize), PlyError> {
+    let mut format: Option<Format> = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut comments: Vec<String> = Vec::new();
+
+    let mut offset = 0;
+    let mut line_number = 0;
+    loop {
+        line_number += 1;
+        let header_error = |message: &str| PlyError::InvalidHeader { line: line_number, message: message.to_string() };
+
+        let Some(length) = bytes[offset..].iter().position(|&b| b == b'\n') else {
+            return Err(header_error("header has no end_header line"));
+        };
+        let line = String::from_utf8_lossy(&bytes[offset..offset + length]).trim_end_matches('\r').to_string();
+        offset += length + 1;
+        let parts: Vec<&str> = line.split_whitespace().collect();
+
+        if line_number == 1 {
+            if line != "ply" {
+                return Err(header_error("missing 'ply' magic"));
+            }
+            continue;
+        }
+
+        match parts.first().copied() {
+            None => {}
+            Some("format") => {
+                format = match parts.get(1).copied() {
+                    Some("ascii") => Some(Format::Ascii),
+                    Some("binary_little_endian") => Some(Format::BinaryLittleEndian),
+                    Some("binary_big_endian") => Some(Format::BinaryBigEndian),
+                    _ => return Err(header_error(&format!("unknown format '{}'", parts[1..].join(" ")))),
+                };
+                if parts.get(2).is_some_and(|version| !version.starts_with('1')) {
+                    return Err(header_error(&format!("unsupported version {}", parts[2])));
+                }
+            }
+            Some("comment") | Some("obj_info") => comments.push(line.split_once(char::is_whitespace).map_or("", |(_, rest)| rest).trim().to_string()),
+            Some("element") => {
+                let (Some(name), Some(count)) = (parts.get(1), parts.get(2).and_then(|c| c.parse().ok())) else {
+                    return Err(header_error("expected 'element <name> <count>'"));
+                };
+                elements.push(Element { name: name.to_string(), count, properties: Vec::new() });
+            }
+            Some("property") => {
+                let Some(element) = elements.last_mut() else {
+                    return Err(header_error("property before any element"));
+                };
+                let scalar = |name: Option<&&str>| name.and_then(|name| ScalarType::parse(name));
+                let property = match parts.get(1).copied() {
+                    Some("list") => match (scalar(parts.get(2)), scalar(parts.get(3)), parts.get(4)) {
+                        (Some(count), Some(item), Some(name)) => Property { name: name.to_string(), kind: PropertyType::List { count, item } },
+                        _ => return Err(header_error("expected 'property list <count type> <item type> <name>'")),
+                    },
+                    _ => match (scalar(parts.get(1)), parts.get(2)) {
+                        (Some(kind), Some(name)) => Property { name: name.to_string(), kind: PropertyType::Scalar(kind) },
+                        _ => return Err(header_error("expected 'property <type> <name>'")),
+                    },
+                };
+                element.properties.push(property);
+            }
+            Some("end_header") => break,
+            Some(other) => return Err(header_error(&format!("unknown header keyword '{}'", other))),
+        }
+    }
+
+    match format {
+        Some(format) => return Ok((format, elements, comments, offset)),
+        None => return Err(PlyError::InvalidHeader { line: line_number, message: "missing format line".to_string() }),
+    }
+}
+
+
+
+fn vertex_layout(element: &Element) -> VertexLayout {
+    let find = |names: &[&str]| element.properties.iter().position(|p| names.contains(&p.name.as_str()));
+    return VertexLayout {
+        position: [find(&["x"]), find(&["y"]), find(&["z"])],
+        normal: [find(&["nx"]), find(&["ny"]), find(&["nz"])],
+        color: [find(&["red", "r", "diffuse_red"]), find(&["green", "g", "diffuse_green"]), find(&["blue", "b", "diffuse_blue"])],
+        texcoord: [find(&["u", "s", "texture_u", "texture_s"]), find(&["v", "t", "texture_v", "texture_t"])],
+    };
+}
+
+
+
+fn build_vertex(element: &Element, layout: &VertexLayout, scalars: &[f64]) -> Vertex {
+    let value = |index: Option<usize>| index.map_or(0.0, |i| scalars[i]);
+
+    let normal = if layout.normal.iter().all(Option::is_some) {
+        vec3!(value(layout.normal[0]), value(layout.normal[1]), value(layout.normal[2]))
+    } else {
+        vec3!(0, 0, 0)
+    };
+
+    let color = if layout.color.iter().all(Option::is_some) {
+        let channel = |index: Option<usize>| {
+            let index = index.unwrap();
+            let scale = match element.properties[index].kind {
+                PropertyType::Scalar(kind) => kind.color_scale(),
+                PropertyType::List { .. } => 1.0,
+            };
+            scalars[index] / scale
+        };
+        vec3!(channel(layout.color[0]), channel(layout.color[1]), channel(layout.color[2]))
+    } else {
+        vec3!(1, 1, 1)
+    };
+
+    // PLY puts v = 0 at the bottom like OBJ, textures are stored top row first
+    let texcoord = vec2!(value(layout.texcoord[0]), 1.0 - value(layout.texcoord[1]));
+
+    return Vertex {
+        position: vec3!(value(layout.position[0]), value(layout.position[1]), value(layout.position[2])),
+        texcoord,
+        normal,
+        tangent: vec3!(0, 0, 0),
+        bitangent_sign: 1.0,
+        color,
+        joints: [0; 4],
+        weights: [0.0; 4],
+    };
+}
+
+
+
+// vertex indices of a face and, when present, its per-corner texture coordinates
+fn face_lists(element: &Element, scalars: &[f64], lists: &[Vec<f64>], index: usize) -> Result<(Vec<usize>, Option<Vec<Vec2>>), PlyError> {
+    let list = |names: &[&str]| -> Option<&Vec<f64>> {
+        let position = element.properties.iter().position(|p| names.contains(&p.name.as_str()) && matches!(p.kind, PropertyType::List { .. }))?;
+        return Some(&lists[scalars[position] as usize]);
+    };
+    let invalid = |message: String| PlyError::InvalidData { element: element.name.clone(), index, message };
+
+    let Some(raw_indices) = list(&["vertex_indices", "vertex_index"]) else {
+        return Err(invalid("face element has no vertex_indices list".to_string()));
+    };
+    let mut indices: Vec<usize> = Vec::with_capacity(raw_indices.len());
+    for &raw in raw_indices {
+        if raw < 0.0 || raw.fract() != 0.0 {
+            return Err(invalid(format!("invalid vertex index {}", raw)));
+        }
+        indices.push(raw as usize);
+    }
+
+    let texcoords = match list(&["texcoord"]) {
+        Some(values) if values.len() == indices.len() * 2 => Some(values.chunks_exact(2).map(|uv| vec2!(uv[0], 1.0 - uv[1])).collect()),
+        Some(values) => return Err(invalid(format!("texcoord list has {} values for {} corners", values.len(), indices.len()))),
+        None => None,
+    };
+
+    return Ok((indices, texcoords));
+}
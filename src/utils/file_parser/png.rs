@@ -0,0 +1,496 @@
+use crate::Texture;
+use super::error::{ByteReader, ImageError};
+use super::deflate::zlib_compress;
+use super::inflate::zlib_decompress;
+
+
+
+const SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+const COLOR_GRAYSCALE: u8 = 0;
+const COLOR_RGB: u8 = 2;
+const COLOR_INDEXED: u8 = 3;
+const COLOR_GRAYSCALE_ALPHA: u8 = 4;
+const COLOR_RGBA: u8 = 6;
+
+// (x start, y start, x step, y step) of the seven Adam7 passes
+const ADAM7_PASSES: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+const MAX_PIXELS: u64 = 1 << 28;
+
+
+
+struct PngHeader {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool,
+}
+
+
+
+impl PngHeader {
+    fn channels(&self) -> usize {
+        return match self.color_type {
+            COLOR_RGB => 3,
+            COLOR_GRAYSCALE_ALPHA => 2,
+            COLOR_RGBA => 4,
+            _ => 1,
+        };
+    }
+
+
+    // filters operate on whole pixels, or whole bytes below 8 bits
+    fn filter_stride(&self) -> usize {
+        return (self.channels() * self.bit_depth as usize).div_ceil(8);
+    }
+
+
+    fn row_bytes(&self, width: usize) -> usize {
+        return (width * self.channels() * self.bit_depth as usize).div_ceil(8);
+    }
+}
+
+
+
+#[derive(Default)]
+struct Transparency {
+    // alpha per palette entry, missing entries are opaque
+    palette_alpha: Vec<u8>,
+    // single fully transparent sample value for grayscale and rgb images
+    key: Option<[u16; 3]>,
+}
+
+
+
+pub fn read_png(path: &str) -> Result<Texture, ImageError> {
+    let bytes = std::fs::read(path)?;
+    return decode_png(&bytes);
+}
+
+
+
+pub fn decode_png(bytes: &[u8]) -> Result<Texture, ImageError> {
+    let reader = ByteReader::new(bytes);
+    if reader.slice(0, 8)? != SIGNATURE {
+        return Err(ImageError::InvalidSignature);
+    }
+
+    let mut header: Option<PngHeader> = None;
+    let mut palette: Vec<u32> = Vec::new();
+    let mut transparency = Transparency::default();
+    let mut compressed: Vec<u8> = Vec::new();
+    let mut seen_idat = false;
+    let mut seen_iend = false;
+
+    let mut offset = 8;
+    while !seen_iend {
+        let length = reader.u32_be(offset)? as usize;
+        let chunk_type: [u8; 4] = reader.slice(offset + 4, 4)?.try_into().unwrap();
+        let data = reader.slice(offset + 8, length)?;
+        let stored_crc = reader.u32_be(offset + 8 + length)?;
+
+        let computed_crc = crc32(reader.slice(offset + 4, length + 4)?);
+        if stored_crc != computed_crc {
+            return Err(ImageError::CrcMismatch { chunk: chunk_type, stored: stored_crc, computed: computed_crc });
+        }
+        offset += 12 + length;
+
+        if header.is_none() && &chunk_type != b"IHDR" {
+            return Err(ImageError::ChunkOrder("IHDR must be the first chunk"));
+        }
+
+        match &chunk_type {
+            b"IHDR" => {
+                if header.is_some() {
+                    return Err(ImageError::ChunkOrder("IHDR appears more than once"));
+                }
+                header = Some(parse_header(data)?);
+            }
+            b"PLTE" => {
+                if seen_idat {
+                    return Err(ImageError::ChunkOrder("PLTE must come before IDAT"));
+                }
+                if !length.is_multiple_of(3) || length == 0 || length > 256 * 3 {
+                    return Err(ImageError::InvalidChunkLength { chunk: chunk_type, length });
+                }
+                palette = data
+                    .chunks_exact(3)
+                    .map(|rgb| 0xFF000000 | ((rgb[0] as u32) << 16) | ((rgb[1] as u32) << 8) | rgb[2] as u32)
+                    .collect();
+            }
+            b"tRNS" => {
+                if seen_idat {
+                    return Err(ImageError::ChunkOrder("tRNS must come before IDAT"));
+                }
+                transparency = parse_transparency(header.as_ref().unwrap(), data, palette.len())?;
+            }
+            b"IDAT" => {
+                seen_idat = true;
+                compressed.extend_from_slice(data);
+            }
+            b"IEND" => seen_iend = true,
+            _ => {
+                // ancillary chunks have a lowercase first letter and are safe to skip
+                if chunk_type[0].is_ascii_uppercase() {
+                    return Err(ImageError::UnsupportedCriticalChunk(chunk_type));
+                }
+            }
+        }
+    }
+
+    let header = header.unwrap();
+    if !seen_idat {
+        return Err(ImageError::MissingChunk("IDAT"));
+    }
+    if header.color_type == COLOR_INDEXED && palette.is_empty() {
+        return Err(ImageError::MissingChunk("PLTE"));
+    }
+
+    let raw = zlib_decompress(&compressed)?;
+    let texture_data = decode_image_data(&header, &raw, &palette, &transparency)?;
+    return Ok(Texture::new(texture_data, header.width, header.height));
+}
+
+
+
+fn parse_header(data: &[u8]) -> Result<PngHeader, ImageError> {
+    if data.len() != 13 {
+        return Err(ImageError::InvalidChunkLength { chunk: *b"IHDR", length: data.len() });
+    }
+    let reader = ByteReader::new(data);
+
+    let header = PngHeader {
+        width: reader.u32_be(0)?,
+        height: reader.u32_be(4)?,
+        bit_depth: data[8],
+        color_type: data[9],
+        interlaced: data[12] == 1,
+    };
+
+    if header.width == 0 || header.height == 0 || header.width as u64 * header.height as u64 > MAX_PIXELS {
+        return Err(ImageError::InvalidDimensions { width: header.width as i64, height: header.height as i64 });
+    }
+
+    let allowed_depths: &[u8] = match header.color_type {
+        COLOR_GRAYSCALE => &[1, 2, 4, 8, 16],
+        COLOR_INDEXED => &[1, 2, 4, 8],
+        COLOR_RGB | COLOR_GRAYSCALE_ALPHA | COLOR_RGBA => &[8, 16],
+        other => return Err(ImageError::InvalidColorType(other)),
+    };
+    if !allowed_depths.contains(&header.bit_depth) {
+        return Err(ImageError::ColorTypeBitDepthMismatch { color_type: header.color_type, bit_depth: header.bit_depth });
+    }
+
+    if data[10] != 0 {
+        return Err(ImageError::UnsupportedCompression(data[10] as u32));
+    }
+    if data[11] != 0 {
+        return Err(ImageError::UnsupportedFilterMethod(data[11]));
+    }
+    if data[12] > 1 {
+        return Err(ImageError::UnsupportedInterlace(data[12]));
+    }
+
+    return Ok(header);
+}
+
+
+
+fn parse_transparency(header: &PngHeader, data: &[u8], palette_len: usize) -> Result<Transparency, ImageError> {
+    let invalid = ImageError::InvalidChunkLength { chunk: *b"tRNS", length: data.len() };
+    let sample = |i: usize| u16::from_be_bytes([data[i * 2], data[i * 2 + 1]]);
+
+    match header.color_type {
+        COLOR_INDEXED => {
+            if data.len() > palette_len {
+                return Err(invalid);
+            }
+            return Ok(Transparency { palette_alpha: data.to_vec(), key: None });
+        }
+        COLOR_GRAYSCALE => {
+            if data.len() != 2 {
+                return Err(invalid);
+            }
+            return Ok(Transparency { palette_alpha: Vec::new(), key: Some([sample(0), 0, 0]) });
+        }
+        COLOR_RGB => {
+            if data.len() != 6 {
+                return Err(invalid);
+            }
+            return Ok(Transparency { palette_alpha: Vec::new(), key: Some([sample(0), sample(1), sample(2)]) });
+        }
+        _ => return Err(ImageError::ChunkOrder("tRNS is not allowed for images with an alpha channel")),
+    }
+}
+
+
+
+fn decode_image_data(header: &PngHeader, raw: &[u8], palette: &[u32], transparency: &Transparency) -> Result<Vec<u32>, ImageError> {
+    let width = header.width as usize;
+    let height = header.height as usize;
+    let mut texture_data = vec![0u32; width * height];
+
+    let passes: &[(usize, usize, usize, usize)] = if header.interlaced { &ADAM7_PASSES } else { &[(0, 0, 1, 1)] };
+
+    let mut offset = 0;
+    for &(x_start, y_start, x_step, y_step) in passes {
+        let pass_width = if width > x_start { (width - x_start).div_ceil(x_step) } else { 0 };
+        let pass_height = if height > y_start { (height - y_start).div_ceil(y_step) } else { 0 };
+
+        // empty passes carry no filter bytes at all
+        if pass_width == 0 || pass_height == 0 {
+            continue;
+        }
+
+        let row_bytes = header.row_bytes(pass_width);
+        let pass_size = (row_bytes + 1) * pass_height;
+        let pass_data = raw
+            .get(offset..offset + pass_size)
+            .ok_or(ImageError::ImageDataSizeMismatch { expected: offset + pass_size, actual: raw.len() })?;
+        offset += pass_size;
+
+        let rows = unfilter(pass_data, row_bytes, pass_height, header.filter_stride())?;
+
+        for (pass_y, row) in rows.chunks_exact(row_bytes).enumerate() {
+            let y = y_start + pass_y * y_step;
+            for pass_x in 0..pass_width {
+                let x = x_start + pass_x * x_step;
+                texture_data[y * width + x] = pixel_color(header, row, pass_x, palette, transparency)?;
+            }
+        }
+    }
+
+    return Ok(texture_data);
+}
+
+
+
+// undoes the per-row filters, returns the rows without their filter type bytes
+fn unfilter(data: &[u8], row_bytes: usize, rows: usize, stride: usize) -> Result<Vec<u8>, ImageError> {
+    let mut out = vec![0u8; row_bytes * rows];
+
+    for y in 0..rows {
+        let filter_type = data[y * (row_bytes + 1)];
+        let src = &data[y * (row_bytes + 1) + 1..(y + 1) * (row_bytes + 1)];
+        let (previous, current) = out.split_at_mut(y * row_bytes);
+        let prior = if y == 0 { None } else { Some(&previous[(y - 1) * row_bytes..]) };
+        let current = &mut current[..row_bytes];
+
+        for x in 0..row_bytes {
+            let a = if x >= stride { current[x - stride] } else { 0 };
+            let b = prior.map_or(0, |p| p[x]);
+            let c = if x >= stride { prior.map_or(0, |p| p[x - stride]) } else { 0 };
+
+            let predictor = match filter_type {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                other => return Err(ImageError::InvalidFilterType(other)),
+            };
+            current[x] = src[x].wrapping_add(predictor);
+        }
+    }
+
+    return Ok(out);
+}
+
+
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+
+    if pa <= pb && pa <= pc {
+        return a;
+    }
+    if pb <= pc {
+        return b;
+    }
+    return c;
+}
+
+
+
+// sample `index` of a packed row, sub-byte samples are packed most significant bit first
+fn read_sample(row: &[u8], index: usize, bit_depth: u8) -> u16 {
+    match bit_depth {
+        16 => return u16::from_be_bytes([row[index * 2], row[index * 2 + 1]]),
+        8 => return row[index] as u16,
+        _ => {
+            let bit = index * bit_depth as usize;
+            let shift = 8 - bit_depth as usize - bit % 8;
+            return ((row[bit / 8] >> shift) & ((1u8 << bit_depth) - 1)) as u16;
+        }
+    }
+}
+
+
+
+fn to_8_bits(sample: u16, bit_depth: u8) -> u32 {
+    match bit_depth {
+        16 => return (sample >> 8) as u32,
+        8 => return sample as u32,
+        _ => return sample as u32 * 255 / ((1u32 << bit_depth) - 1),
+    }
+}
+
+
+
+fn pixel_color(header: &PngHeader, row: &[u8], x: usize, palette: &[u32], transparency: &Transparency) -> Result<u32, ImageError> {
+    let depth = header.bit_depth;
+    let channels = header.channels();
+    let sample = |c: usize| read_sample(row, x * channels + c, depth);
+
+    let (a, r, g, b) = match header.color_type {
+        COLOR_INDEXED => {
+            let index = sample(0) as usize;
+            let color = *palette
+                .get(index)
+                .ok_or(ImageError::PaletteIndexOutOfRange { index: index as u32, palette_len: palette.len() })?;
+            let alpha = transparency.palette_alpha.get(index).copied().unwrap_or(0xFF) as u32;
+            return Ok((alpha << 24) | (color & 0x00FFFFFF));
+        }
+        COLOR_GRAYSCALE => {
+            let gray = sample(0);
+            let alpha = if transparency.key.is_some_and(|key| key[0] == gray) { 0 } else { 0xFF };
+            let gray = to_8_bits(gray, depth);
+            (alpha, gray, gray, gray)
+        }
+        COLOR_RGB => {
+            let rgb = [sample(0), sample(1), sample(2)];
+            let alpha = if transparency.key == Some(rgb) { 0 } else { 0xFF };
+            (alpha, to_8_bits(rgb[0], depth), to_8_bits(rgb[1], depth), to_8_bits(rgb[2], depth))
+        }
+        COLOR_GRAYSCALE_ALPHA => {
+            let gray = to_8_bits(sample(0), depth);
+            (to_8_bits(sample(1), depth), gray, gray, gray)
+        }
+        _ => (
+            to_8_bits(sample(3), depth),
+            to_8_bits(sample(0), depth),
+            to_8_bits(sample(1), depth),
+            to_8_bits(sample(2), depth),
+        ),
+    };
+
+    return Ok((a << 24) | (r << 16) | (g << 8) | b);
+}
+
+
+
+fn crc32(data: &[u8]) -> u32 {
+    static TABLE: std::sync::OnceLock<[u32; 256]> = std::sync::OnceLock::new();
+    let table = TABLE.get_or_init(|| {
+        let mut table = [0u32; 256];
+        for (n, entry) in table.iter_mut().enumerate() {
+            let mut c = n as u32;
+            for _ in 0..8 {
+                c = if c & 1 != 0 { 0xEDB88320 ^ (c >> 1) } else { c >> 1 };
+            }
+            *entry = c;
+        }
+        table
+    });
+
+    let mut crc = 0xFFFFFFFFu32;
+    for &byte in data {
+        crc = table[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    return crc ^ 0xFFFFFFFF;
+}
+
+
+
+/// writes packed ARGB pixels as an 8-bit RGB png, alpha is dropped
+pub fn write_png(path: &str, pixels: &[u32], width: u32, height: u32) -> Result<(), ImageError> {
+    std::fs::write(path, encode_png(pixels, width, height))?;
+    return Ok(());
+}
+
+
+
+pub fn encode_png(pixels: &[u32], width: u32, height: u32) -> Vec<u8> {
+    assert_eq!(pixels.len(), (width * height) as usize, "pixel count does not match the dimensions");
+    let row_bytes = width as usize * 3;
+
+    let mut raw: Vec<u8> = Vec::with_capacity((row_bytes + 1) * height as usize);
+    let mut prior = vec![0u8; row_bytes];
+    let mut current = vec![0u8; row_bytes];
+    for row in pixels.chunks_exact(width.max(1) as usize).take(height as usize) {
+        for (x, &pixel) in row.iter().enumerate() {
+            current[x * 3..x * 3 + 3].copy_from_slice(&[(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8]);
+        }
+        filter_row(&current, &prior, 3, &mut raw);
+        std::mem::swap(&mut prior, &mut current);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    header.extend_from_slice(&[8, COLOR_RGB, 0, 0, 0]);
+
+    let mut out = SIGNATURE.to_vec();
+    write_chunk(&mut out, b"IHDR", &header);
+    write_chunk(&mut out, b"IDAT", &zlib_compress(&raw));
+    write_chunk(&mut out, b"IEND", &[]);
+    return out;
+}
+
+
+
+// appends the filter type byte and the filtered row, picking whichever of the five filters
+// gives the smallest sum of absolute values, the usual heuristic for compressing well
+fn filter_row(row: &[u8], prior: &[u8], stride: usize, out: &mut Vec<u8>) {
+    let mut best: Vec<u8> = Vec::new();
+    let mut best_score = u64::MAX;
+    let mut filtered = vec![0u8; row.len()];
+
+    for filter_type in 0..5u8 {
+        for x in 0..row.len() {
+            let a = if x >= stride { row[x - stride] } else { 0 };
+            let b = prior[x];
+            let c = if x >= stride { prior[x - stride] } else { 0 };
+            let predicted = match filter_type {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                _ => paeth(a, b, c),
+            };
+            filtered[x] = row[x].wrapping_sub(predicted);
+        }
+        let score: u64 = filtered.iter().map(|&v| (v as i8).unsigned_abs() as u64).sum();
+        if score < best_score {
+            best_score = score;
+            best.clear();
+            best.push(filter_type);
+            best.extend_from_slice(&filtered);
+        }
+    }
+    out.extend_from_slice(&best);
+}
+
+
+
+fn write_chunk(out: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(chunk_type);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
@@ -0,0 +1,901 @@
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+
+use crate::animation::{AnimatedValue, AnimationClip, Interpolation, Property, Track, WrapMode};
+use crate::config::DEFAULT_FOV;
+use crate::graphics::camera_controller::{CameraPath, CameraWaypoint};
+use crate::types::shader::{NormalMapShader, Shader, SolidShader, TextureShader, VertexColorShader, DEFAULT_AMBIENT, DEFAULT_LIGHT_DIRECTION};
+use crate::utils::json::Json;
+use crate::{vec3, Camera, Light, Material, MipFilter, Model, PointCloud, RenderSettings, Scene, shader_lighting, Texture, Transform, Vec3};
+use super::error::SceneError;
+use super::gltf::parse_gltf;
+use super::mtl::load_material_shader;
+use super::obj::parse_obj;
+use super::ply::{parse_ply, PlyGeometry};
+use super::stl::parse_stl;
+use super::texture_file::read_texture;
+
+
+
+pub struct SceneFile {
+    pub scene: Scene,
+    pub warnings: Vec<String>,
+}
+
+
+
+/// shader requested by a scene file, kept so the scene can be written back out. paths are resolved
+#[derive(Debug, Clone)]
+pub enum ShaderDescription {
+    Solid,
+    VertexColor,
+    Texture { texture: String },
+    NormalMap { normal_map: String, diffuse_texture: Option<String> },
+    Material(Material),
+}
+
+
+
+/// a model file the scene refers to and the models and point clouds it loaded as
+#[derive(Debug, Clone)]
+pub struct SceneSource {
+    pub path: String,
+    // None keeps the materials the file came with
+    pub shader: Option<ShaderDescription>,
+    pub models: Range<usize>,
+    pub point_clouds: Range<usize>,
+}
+
+
+
+const TOP_LEVEL_KEYS: [&str; 6] = ["settings", "cameras", "lights", "models", "animations", "camera_path"];
+
+
+
+/// reads a JSON scene with render settings, cameras, lights, models, animations and a camera path, paths are
+/// relative to the scene file. the first camera becomes `scene.camera`, any others go to `scene.cameras`. the
+/// first animation clip, from the scene or a model file, starts playing
+pub fn parse_scene(path: &str) -> Result<SceneFile, SceneError> {
+    let text = std::fs::read_to_string(path)?;
+    let json = Json::parse(&text)?;
+    let base_dir = absolute_dir(path)?;
+
+    let mut warnings: Vec<String> = Vec::new();
+    let mut scene = Scene::new();
+
+    let Some(members) = json.as_object() else {
+        return Err(invalid("scene", "expected an object"));
+    };
+    for (key, _) in members {
+        if !TOP_LEVEL_KEYS.contains(&key.as_str()) {
+            warnings.push(format!("unknown key '{}' ignored", key));
+        }
+    }
+
+    if let Some(settings) = json.get("settings") {
+        scene.settings = parse_settings(settings)?;
+    }
+
+    for (i, light) in array(&json, "lights", "scene")?.iter().enumerate() {
+        scene.lights.push(parse_light(light, &format!("lights[{}]", i))?);
+    }
+    let directional_lights = scene.lights.iter().filter(|light| matches!(light, Light::Directional { .. })).count();
+    if directional_lights > 1 {
+        warnings.push(format!("{} directional lights, only the first one is used for shading", directional_lights));
+    }
+
+    let mut cameras: Vec<Camera> = Vec::new();
+    for (i, camera) in array(&json, "cameras", "scene")?.iter().enumerate() {
+        cameras.push(parse_camera(camera, &format!("cameras[{}]", i))?);
+    }
+    scene.camera = if cameras.is_empty() { Camera::new(DEFAULT_FOV) } else { cameras.remove(0) };
+    scene.cameras = cameras;
+    if let Some(path) = json.get("camera_path") {
+        scene.camera_path = Some(parse_camera_path(path, "camera_path")?);
+    }
+
+    for (i, entry) in array(&json, "models", "scene")?.iter().enumerate() {
+        load_entry(&mut scene, entry, &format!("models[{}]", i), &base_dir, &mut warnings)?;
+    }
+    // shaders the model files came with haven't seen the lights yet, textures are loaded with box filtered mips
+    scene.apply_lighting();
+    if scene.settings.mip_filter != MipFilter::Box {
+        scene.apply_mip_filter();
+    }
+
+    // after the models, targets refer to their entries
+    for (i, animation) in array(&json, "animations", "scene")?.iter().enumerate() {
+        let clip = parse_animation(animation, &format!("animations[{}]", i), &scene)?;
+        scene.animation.add_clip(clip);
+    }
+    if !scene.animation.clips.is_empty() {
+        scene.animation.play(0);
+    }
+
+    return Ok(SceneFile { scene, warnings });
+}
+
+
+
+impl Scene {
+    /// writes the scene in the format `parse_scene` reads, with paths relative to the new file.
+    /// models and point clouds added in code have no source file and are left out, as are the animations
+    /// that came with a model file or drive them
+    pub fn save(&self, path: &str) -> Result<(), SceneError> {
+        let base_dir = absolute_dir(path)?;
+
+        let settings = object(vec![
+            ("width", Json::Number(self.settings.width as f64)),
+            ("height", Json::Number(self.settings.height as f64)),
+            ("background", vector_json(self.settings.background)),
+            ("near_clip", number_json(self.settings.near_clip)),
+            ("far_clip", number_json(self.settings.far_clip)),
+            ("mip_filter", string_json(self.settings.mip_filter.name())),
+        ]);
+
+        let cameras = std::iter::once(&self.camera).chain(&self.cameras).map(|camera| {
+            return object(vec![("fov", number_json(camera.fov)), ("transform", transform_json(&camera.transform))]);
+        });
+
+        let lights = self.lights.iter().map(|light| match light {
+            Light::Directional { direction } => object(vec![("type", string_json("directional")), ("direction", vector_json(*direction))]),
+            Light::Ambient { intensity } => object(vec![("type", string_json("ambient")), ("intensity", number_json(*intensity))]),
+        });
+
+        let mut models: Vec<Json> = Vec::new();
+        for source in &self.sources {
+            // every model from one file shares the transform it was placed with
+            let first_model = self.models.get(source.models.start).filter(|_| !source.models.is_empty());
+            let first_cloud = self.point_clouds.get(source.point_clouds.start).filter(|_| !source.point_clouds.is_empty());
+            let transform = match (first_model, first_cloud) {
+                (Some(model), _) => &model.transform,
+                (None, Some(cloud)) => &cloud.transform,
+                (None, None) => continue,
+            };
+
+            let mut entry = vec![
+                ("path", string_json(&relative_path(&source.path, &base_dir))),
+                ("transform", transform_json(transform)),
+            ];
+            if let Some(shader) = &source.shader {
+                entry.push(("shader", shader_json(shader, &base_dir)));
+            }
+            if let Some(cloud) = first_cloud {
+                entry.push(("point_size", number_json(cloud.point_size)));
+            }
+            // targets with the same name in several of the entry's models got the same weight
+            let mut morph_weights: Vec<(String, Json)> = Vec::new();
+            for model in &self.models[source.models.clone()] {
+                for (target, weight) in model.morph_targets.iter().zip(&model.morph_weights) {
+                    if !morph_weights.iter().any(|(name, _)| *name == target.name) {
+                        morph_weights.push((target.name.clone(), number_json(*weight)));
+                    }
+                }
+            }
+            if !morph_weights.is_empty() {
+                entry.push(("morph_weights", Json::Object(morph_weights)));
+            }
+            models.push(object(entry));
+        }
+
+        let animations = self.animation.clips.iter()
+            .filter(|clip| clip.asset.is_none())
+            .map(|clip| animation_json(clip, &self.sources, &self.models));
+
+        let mut document = vec![
+            ("settings", settings),
+            ("cameras", Json::Array(cameras.collect())),
+            ("lights", Json::Array(lights.collect())),
+            ("models", Json::Array(models)),
+            ("animations", Json::Array(animations.collect())),
+        ];
+        if let Some(path) = &self.camera_path {
+            document.push(("camera_path", camera_path_json(path)));
+        }
+        let document = object(document);
+        std::fs::write(path, document.to_string_pretty() + "\n")?;
+        return Ok(());
+    }
+}
+
+
+
+fn load_entry(scene: &mut Scene, entry: &Json, field: &str, base_dir: &Path, warnings: &mut Vec<String>) -> Result<(), SceneError> {
+    let Some(relative) = entry.get("path").and_then(Json::as_str) else {
+        return Err(invalid(&format!("{}.path", field), "expected a file path"));
+    };
+    let path = resolve(base_dir, relative);
+    let transform = parse_transform(entry.get("transform"), &format!("{}.transform", field))?;
+    let shader = match entry.get("shader") {
+        Some(shader) => Some(parse_shader(shader, &format!("{}.shader", field), base_dir)?),
+        None => None,
+    };
+    let point_size = number(entry, "point_size", field, 2.0)?;
+    // morph target name to weight, for every model of the entry that has the target
+    let mut morph_weights: Vec<(&str, f32)> = Vec::new();
+    if let Some(weights) = entry.get("morph_weights") {
+        let weights_field = format!("{}.morph_weights", field);
+        let weights = weights.as_object().ok_or_else(|| invalid(&weights_field, "expected an object of morph target names to weights"))?;
+        for (name, weight) in weights {
+            let weight = weight.as_f32().ok_or_else(|| invalid(&format!("{}.{}", weights_field, name), "expected a number"))?;
+            morph_weights.push((name, weight));
+        }
+    }
+
+    let Asset { models, point_clouds, clips } = load_asset(&path, field, warnings)?;
+    let (light_direction, ambient) = shader_lighting(&scene.lights);
+
+    // models the file animates keep the transforms it gave them, the entry can't move them as well
+    let animated = |model: usize| clips.iter().flat_map(|clip| &clip.channels).any(|channel| matches!(
+        channel.property,
+        Property::ModelPosition(m) | Property::ModelRotation(m) | Property::ModelScale(m) if m == model
+    ));
+    let identity = Transform::new();
+    let entry_moves = transform.position != identity.position || transform.yaw != 0.0 || transform.pitch != 0.0 || transform.scale != identity.scale;
+    if entry_moves && (0..models.len()).any(animated) {
+        warnings.push(format!("{}: the transform doesn't apply to models the file animates", field));
+    }
+
+    for &(name, _) in &morph_weights {
+        if !models.iter().any(|model| model.morph_target(name).is_some()) {
+            warnings.push(format!("{}.morph_weights: no model has a morph target named '{}'", field, name));
+        }
+    }
+
+    let model_start = scene.models.len();
+    for (i, mut model) in models.into_iter().enumerate() {
+        for &(name, weight) in &morph_weights {
+            if let Some(target) = model.morph_target(name) {
+                model.morph_weights[target] = weight;
+            }
+        }
+        if !animated(i) {
+            model.transform.position = transform.position;
+            model.transform.set_rotation(transform.pitch, transform.yaw);
+            model.transform.scale = transform.scale;
+        }
+        if let Some(description) = &shader {
+            model.shader = build_shader(description, light_direction, ambient, warnings)?;
+            model.submeshes.clear();
+        }
+        scene.load_model(model);
+    }
+
+    let cloud_start = scene.point_clouds.len();
+    if !point_clouds.is_empty() && shader.is_some() {
+        warnings.push(format!("{}: shaders don't apply to point clouds, ignored", field));
+    }
+    for mut point_cloud in point_clouds {
+        point_cloud.transform.position = transform.position;
+        point_cloud.transform.set_rotation(transform.pitch, transform.yaw);
+        point_cloud.transform.scale = transform.scale;
+        point_cloud.point_size = point_size;
+        scene.load_point_cloud(point_cloud);
+    }
+
+    scene.sources.push(SceneSource {
+        path: path.clone(),
+        shader,
+        models: model_start..scene.models.len(),
+        point_clouds: cloud_start..scene.point_clouds.len(),
+    });
+
+    for mut clip in clips {
+        for channel in &mut clip.channels {
+            channel.property = channel.property.offset_model(model_start);
+        }
+        clip.asset = Some(path.clone());
+        scene.animation.add_clip(clip);
+    }
+    return Ok(());
+}
+
+
+
+// what one model file loaded as, clips refer to its models by their index in `models`
+struct Asset {
+    models: Vec<Model>,
+    point_clouds: Vec<PointCloud>,
+    clips: Vec<AnimationClip>,
+}
+
+
+
+impl Asset {
+    fn models(models: Vec<Model>) -> Asset {
+        return Asset { models, point_clouds: Vec::new(), clips: Vec::new() };
+    }
+}
+
+
+
+// picks the loader from the extension
+fn load_asset(path: &str, field: &str, warnings: &mut Vec<String>) -> Result<Asset, SceneError> {
+    let asset = |message: String| SceneError::Asset { path: path.to_string(), message };
+    let extension = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+
+    match extension.as_str() {
+        "obj" => {
+            let obj = parse_obj(path).map_err(|err| asset(err.to_string()))?;
+            warnings.extend(obj.warnings.iter().map(|warning| format!("{}: {}", path, warning)));
+            return Ok(Asset::models(vec![obj.into_model()]));
+        }
+        "gltf" | "glb" => {
+            let gltf = parse_gltf(path).map_err(|err| asset(err.to_string()))?;
+            warnings.extend(gltf.warnings.iter().map(|warning| format!("{}: {}", path, warning)));
+            if !gltf.cameras.is_empty() {
+                warnings.push(format!("{}: cameras in the file ignored, declare them in the scene", path));
+            }
+            return Ok(Asset { models: gltf.models, point_clouds: Vec::new(), clips: gltf.animations });
+        }
+        "stl" => {
+            let stl = parse_stl(path).map_err(|err| asset(err.to_string()))?;
+            warnings.extend(stl.warnings.iter().map(|warning| format!("{}: {}", path, warning)));
+            return Ok(Asset::models(vec![stl.model]));
+        }
+        "ply" => {
+            let ply = parse_ply(path).map_err(|err| asset(err.to_string()))?;
+            warnings.extend(ply.warnings.iter().map(|warning| format!("{}: {}", path, warning)));
+            match ply.geometry {
+                PlyGeometry::Mesh(model) => return Ok(Asset::models(vec![model])),
+                PlyGeometry::Points(point_cloud) => return Ok(Asset { models: Vec::new(), point_clouds: vec![point_cloud], clips: Vec::new() }),
+            }
+        }
+        _ => return Err(invalid(&format!("{}.path", field), &format!("unsupported model format '{}'", extension))),
+    }
+}
+
+
+
+fn build_shader(description: &ShaderDescription, light_direction: Vec3, ambient: f32, warnings: &mut Vec<String>) -> Result<Box<dyn Shader>, SceneError> {
+    match description {
+        ShaderDescription::Solid => return Ok(Box::new(SolidShader::new())),
+        ShaderDescription::VertexColor => return Ok(Box::new(VertexColorShader::new())),
+        ShaderDescription::Texture { texture } => return Ok(Box::new(TextureShader::new(load_texture(texture)?))),
+        ShaderDescription::NormalMap { normal_map, diffuse_texture } => {
+            let mut shader = NormalMapShader::new(load_texture(normal_map)?);
+            if let Some(path) = diffuse_texture {
+                shader.diffuse_texture = Some(load_texture(path)?);
+            }
+            shader.set_lighting(light_direction, ambient);
+            return Ok(Box::new(shader));
+        }
+        ShaderDescription::Material(material) => {
+            let mut shader = load_material_shader(material, warnings);
+            shader.set_lighting(light_direction, ambient);
+            return Ok(Box::new(shader));
+        }
+    }
+}
+
+
+
+fn load_texture(path: &str) -> Result<Texture, SceneError> {
+    return read_texture(path).map_err(|err| SceneError::Asset { path: path.to_string(), message: err.to_string() });
+}
+
+
+
+fn parse_settings(json: &Json) -> Result<RenderSettings, SceneError> {
+    let defaults = RenderSettings::new();
+    let field = "settings";
+    let settings = RenderSettings {
+        width: integer(json, "width", field, defaults.width)?,
+        height: integer(json, "height", field, defaults.height)?,
+        background: vector(json, "background", field, defaults.background)?,
+        near_clip: number(json, "near_clip", field, defaults.near_clip)?,
+        far_clip: number(json, "far_clip", field, defaults.far_clip)?,
+        mip_filter: match json.get("mip_filter") {
+            None => defaults.mip_filter,
+            Some(value) => match value.as_str().and_then(MipFilter::from_name) {
+                Some(filter) => filter,
+                None => return Err(invalid(&format!("{}.mip_filter", field), "expected \"box\" or \"kaiser\"")),
+            },
+        },
+    };
+
+    if settings.width == 0 || settings.height == 0 {
+        return Err(invalid(field, "width and height must be positive"));
+    }
+    if settings.near_clip <= 0.0 || settings.far_clip <= settings.near_clip {
+        return Err(invalid(field, "expected 0 < near_clip < far_clip"));
+    }
+    return Ok(settings);
+}
+
+
+
+fn parse_light(json: &Json, field: &str) -> Result<Light, SceneError> {
+    match json.get("type").and_then(Json::as_str) {
+        Some("directional") => {
+            let direction = vector(json, "direction", field, DEFAULT_LIGHT_DIRECTION)?;
+            if direction.length() == 0.0 {
+                return Err(invalid(&format!("{}.direction", field), "direction can't be zero"));
+            }
+            return Ok(Light::Directional { direction });
+        }
+        Some("ambient") => return Ok(Light::Ambient { intensity: number(json, "intensity", field, DEFAULT_AMBIENT)? }),
+        Some(other) => return Err(invalid(&format!("{}.type", field), &format!("unknown light type '{}'", other))),
+        None => return Err(invalid(&format!("{}.type", field), "expected \"directional\" or \"ambient\"")),
+    }
+}
+
+
+
+fn parse_camera(json: &Json, field: &str) -> Result<Camera, SceneError> {
+    let mut camera = Camera::new(number(json, "fov", field, DEFAULT_FOV)?);
+    if camera.fov <= 0.0 || camera.fov >= 180.0 {
+        return Err(invalid(&format!("{}.fov", field), "expected degrees between 0 and 180"));
+    }
+    camera.transform = parse_transform(json.get("transform"), &format!("{}.transform", field))?;
+    return Ok(camera);
+}
+
+
+
+// waypoints for the scripted camera controller, looping unless it says otherwise
+fn parse_camera_path(json: &Json, field: &str) -> Result<CameraPath, SceneError> {
+    let looping = match json.get("looping") {
+        None => true,
+        Some(value) => value.as_bool().ok_or_else(|| invalid(&format!("{}.looping", field), "expected true or false"))?,
+    };
+    let mut waypoints: Vec<CameraWaypoint> = Vec::new();
+    for (i, waypoint) in array(json, "waypoints", field)?.iter().enumerate() {
+        let field = format!("{}.waypoints[{}]", field, i);
+        let time = waypoint.get("time").and_then(Json::as_f32).ok_or_else(|| invalid(&format!("{}.time", field), "expected seconds"))?;
+        waypoints.push(CameraWaypoint {
+            time,
+            position: vector(waypoint, "position", &field, vec3!(0, 0, 0))?,
+            pitch: number(waypoint, "pitch", &field, 0.0)?,
+            yaw: number(waypoint, "yaw", &field, 0.0)?,
+        });
+    }
+    if waypoints.is_empty() {
+        return Err(invalid(&format!("{}.waypoints", field), "expected at least one waypoint"));
+    }
+    waypoints.sort_by(|a, b| a.time.total_cmp(&b.time));
+    return Ok(CameraPath { waypoints, looping });
+}
+
+
+
+fn camera_path_json(path: &CameraPath) -> Json {
+    let waypoints = path.waypoints.iter().map(|waypoint| object(vec![
+        ("time", number_json(waypoint.time)),
+        ("position", vector_json(waypoint.position)),
+        ("pitch", number_json(waypoint.pitch)),
+        ("yaw", number_json(waypoint.yaw)),
+    ]));
+    return object(vec![("looping", Json::Bool(path.looping)), ("waypoints", Json::Array(waypoints.collect()))]);
+}
+
+
+
+// a clip: { name, wrap, channels: [{ target, property, interpolation, times, values, in_tangents, out_tangents }] }.
+// targets are "camera", "models[i]" for every model of entry i, or "lights[i]"
+fn parse_animation(json: &Json, field: &str, scene: &Scene) -> Result<AnimationClip, SceneError> {
+    let default_name = format!("animation {}", scene.animation.clips.len());
+    let mut clip = AnimationClip::new(json.get("name").and_then(Json::as_str).unwrap_or(&default_name));
+    if let Some(wrap) = json.get("wrap") {
+        clip.wrap = wrap.as_str().and_then(WrapMode::parse)
+            .ok_or_else(|| invalid(&format!("{}.wrap", field), "expected \"once\", \"loop\" or \"ping_pong\""))?;
+    }
+
+    for (i, channel) in array(json, "channels", field)?.iter().enumerate() {
+        let field = format!("{}.channels[{}]", field, i);
+        let track = parse_track(channel, &field)?;
+        for property in parse_channel_properties(channel, &field, scene)? {
+            clip.add_channel(property, track.clone()).map_err(|message| invalid(&format!("{}.values", field), &message))?;
+        }
+    }
+    if clip.channels.is_empty() {
+        return Err(invalid(&format!("{}.channels", field), "expected at least one channel"));
+    }
+    return Ok(clip);
+}
+
+
+
+// a model entry can load as several models, they all get the track
+fn parse_channel_properties(json: &Json, field: &str, scene: &Scene) -> Result<Vec<Property>, SceneError> {
+    let Some(target) = json.get("target").and_then(Json::as_str) else {
+        return Err(invalid(&format!("{}.target", field), "expected \"camera\", \"models[i]\" or \"lights[i]\""));
+    };
+    let property = json.get("property").and_then(Json::as_str).unwrap_or("");
+    let unknown = |expected: &str| invalid(&format!("{}.property", field), &format!("'{}' can't be animated on {}, expected {}", property, target, expected));
+    let index = |prefix: &str| target.strip_prefix(prefix).and_then(|rest| rest.strip_suffix(']')).and_then(|i| i.parse::<usize>().ok());
+
+    if target == "camera" {
+        match property {
+            "position" => return Ok(vec![Property::CameraPosition]),
+            "rotation" => return Ok(vec![Property::CameraRotation]),
+            "fov" => return Ok(vec![Property::CameraFov]),
+            _ => return Err(unknown("position, rotation or fov")),
+        }
+    }
+    if let Some(entry) = index("models[") {
+        let Some(source) = scene.sources.get(entry).filter(|source| !source.models.is_empty()) else {
+            return Err(invalid(&format!("{}.target", field), &format!("models[{}] isn't an entry with meshes", entry)));
+        };
+        if let Some(name) = property.strip_prefix("morph_weights.") {
+            let properties: Vec<Property> = source.models.clone()
+                .filter_map(|model| scene.models[model].morph_target(name).map(|target| Property::MorphWeight(model, target)))
+                .collect();
+            if properties.is_empty() {
+                return Err(invalid(&format!("{}.property", field), &format!("models[{}] has no morph target named '{}'", entry, name)));
+            }
+            return Ok(properties);
+        }
+        let property: fn(usize) -> Property = match property {
+            "position" => Property::ModelPosition,
+            "rotation" => Property::ModelRotation,
+            "scale" => Property::ModelScale,
+            _ => return Err(unknown("position, rotation, scale or morph_weights.<target name>")),
+        };
+        return Ok(source.models.clone().map(property).collect());
+    }
+    if let Some(light) = index("lights[") {
+        match (scene.lights.get(light), property) {
+            (Some(Light::Directional { .. }), "direction") => return Ok(vec![Property::LightDirection(light)]),
+            (Some(Light::Ambient { .. }), "intensity") => return Ok(vec![Property::LightIntensity(light)]),
+            (Some(Light::Directional { .. }), _) => return Err(unknown("direction")),
+            (Some(Light::Ambient { .. }), _) => return Err(unknown("intensity")),
+            (None, _) => return Err(invalid(&format!("{}.target", field), &format!("there is no lights[{}]", light))),
+        }
+    }
+    return Err(invalid(&format!("{}.target", field), &format!("unknown target '{}'", target)));
+}
+
+
+
+fn parse_track(json: &Json, field: &str) -> Result<Track, SceneError> {
+    let interpolation = match json.get("interpolation") {
+        None => Interpolation::Linear,
+        Some(value) => value.as_str().and_then(Interpolation::parse)
+            .ok_or_else(|| invalid(&format!("{}.interpolation", field), "expected \"step\", \"linear\" or \"cubic\""))?,
+    };
+    let times = json.get("times").and_then(Json::as_f32_array)
+        .ok_or_else(|| invalid(&format!("{}.times", field), "expected an array of seconds"))?;
+    let values = keyframe_values(json, "values", field)?;
+    let in_tangents = keyframe_values(json, "in_tangents", field)?;
+    let out_tangents = keyframe_values(json, "out_tangents", field)?;
+
+    let track = match (interpolation, in_tangents.is_empty() && out_tangents.is_empty()) {
+        (_, true) => Track::new(interpolation, times, values),
+        (Interpolation::Cubic, false) => Track::with_tangents(times, values, in_tangents, out_tangents),
+        (_, false) => return Err(invalid(&format!("{}.in_tangents", field), "tangents only apply to cubic interpolation")),
+    };
+    return track.map_err(|message| invalid(field, &message));
+}
+
+
+
+// numbers for one component properties, arrays of two or three numbers otherwise. missing arrays are empty
+fn keyframe_values(json: &Json, key: &str, field: &str) -> Result<Vec<AnimatedValue>, SceneError> {
+    let mut values: Vec<AnimatedValue> = Vec::new();
+    for (i, value) in array(json, key, field)?.iter().enumerate() {
+        let components = value.as_f32().map(|x| vec![x]).or_else(|| value.as_f32_array());
+        match components.as_deref().and_then(AnimatedValue::from_slice) {
+            Some(value) => values.push(value),
+            None => return Err(invalid(&format!("{}.{}[{}]", field, key, i), "expected a number or an array of two or three numbers")),
+        }
+    }
+    return Ok(values);
+}
+
+
+
+// inverse of `parse_animation`. a channel is written once for the entry its models came from
+fn animation_json(clip: &AnimationClip, sources: &[SceneSource], models: &[Model]) -> Json {
+    let mut channels: Vec<Json> = Vec::new();
+    for channel in &clip.channels {
+        let entry = |model: usize| sources.iter().position(|source| source.models.start == model && !source.models.is_empty());
+        let (target, property) = match channel.property {
+            Property::ModelPosition(model) => (entry(model).map(|i| format!("models[{}]", i)), "position".to_string()),
+            Property::ModelRotation(model) => (entry(model).map(|i| format!("models[{}]", i)), "rotation".to_string()),
+            Property::ModelScale(model) => (entry(model).map(|i| format!("models[{}]", i)), "scale".to_string()),
+            Property::CameraPosition => (Some("camera".to_string()), "position".to_string()),
+            Property::CameraRotation => (Some("camera".to_string()), "rotation".to_string()),
+            Property::CameraFov => (Some("camera".to_string()), "fov".to_string()),
+            Property::LightDirection(light) => (Some(format!("lights[{}]", light)), "direction".to_string()),
+            Property::LightIntensity(light) => (Some(format!("lights[{}]", light)), "intensity".to_string()),
+            // skeletons only come from model files
+            Property::JointTranslation(_, _) | Property::JointRotation(_, _) | Property::JointScale(_, _) => (None, String::new()),
+            // written for the first of the entry's models with a target of that name
+            Property::MorphWeight(model, target) => match models.get(model).and_then(|m| m.morph_targets.get(target)) {
+                Some(morph_target) => {
+                    let first = |source: &SceneSource| source.models.clone().find(|&m| models[m].morph_target(&morph_target.name).is_some());
+                    let entry = sources.iter().position(|source| first(source) == Some(model));
+                    (entry.map(|i| format!("models[{}]", i)), format!("morph_weights.{}", morph_target.name))
+                }
+                None => (None, String::new()),
+            },
+        };
+        // the other models of an entry, joints, or models added in code
+        let Some(target) = target else {
+            continue;
+        };
+
+        let track = &channel.track;
+        let mut members = vec![
+            ("target", string_json(&target)),
+            ("property", string_json(&property)),
+            ("interpolation", string_json(track.interpolation.name())),
+            ("times", Json::Array(track.times.iter().map(|time| number_json(*time)).collect())),
+            ("values", keyframe_values_json(&track.values)),
+        ];
+        if track.interpolation == Interpolation::Cubic {
+            members.push(("in_tangents", keyframe_values_json(&track.in_tangents)));
+            members.push(("out_tangents", keyframe_values_json(&track.out_tangents)));
+        }
+        channels.push(object(members));
+    }
+
+    return object(vec![
+        ("name", string_json(&clip.name)),
+        ("wrap", string_json(clip.wrap.name())),
+        ("channels", Json::Array(channels)),
+    ]);
+}
+
+
+
+fn keyframe_values_json(values: &[AnimatedValue]) -> Json {
+    return Json::Array(values.iter().map(|value| match value {
+        AnimatedValue::Scalar(x) => number_json(*x),
+        other => Json::Array(other.components().into_iter().map(number_json).collect()),
+    }).collect());
+}
+
+
+
+// missing transforms and fields are the identity
+fn parse_transform(json: Option<&Json>, field: &str) -> Result<Transform, SceneError> {
+    let mut transform = Transform::new();
+    let Some(json) = json else {
+        return Ok(transform);
+    };
+    if json.as_object().is_none() {
+        return Err(invalid(field, "expected an object"));
+    }
+    transform.position = vector(json, "position", field, transform.position)?;
+    transform.set_rotation(number(json, "pitch", field, 0.0)?, number(json, "yaw", field, 0.0)?);
+    transform.scale = vector(json, "scale", field, transform.scale)?;
+    return Ok(transform);
+}
+
+
+
+fn parse_shader(json: &Json, field: &str, base_dir: &Path) -> Result<ShaderDescription, SceneError> {
+    let path = |key: &str| -> Result<Option<String>, SceneError> {
+        match json.get(key) {
+            None => return Ok(None),
+            Some(value) => match value.as_str() {
+                Some(relative) => return Ok(Some(resolve(base_dir, relative))),
+                None => return Err(invalid(&format!("{}.{}", field, key), "expected a file path")),
+            },
+        }
+    };
+    let required = |key: &str| -> Result<String, SceneError> {
+        return path(key)?.ok_or_else(|| invalid(&format!("{}.{}", field, key), "missing"));
+    };
+
+    match json.get("type").and_then(Json::as_str) {
+        Some("solid") => return Ok(ShaderDescription::Solid),
+        Some("vertex_color") => return Ok(ShaderDescription::VertexColor),
+        Some("texture") => return Ok(ShaderDescription::Texture { texture: required("texture")? }),
+        Some("normal_map") => {
+            return Ok(ShaderDescription::NormalMap { normal_map: required("normal_map")?, diffuse_texture: path("diffuse_texture")? });
+        }
+        Some("material") => {
+            let defaults = Material::new("");
+            let name = json.get("name").and_then(Json::as_str).unwrap_or("scene material");
+            let mut material = Material::new(name);
+            material.ambient = vector(json, "ambient", field, defaults.ambient)?;
+            material.diffuse = vector(json, "diffuse", field, defaults.diffuse)?;
+            material.specular = vector(json, "specular", field, defaults.specular)?;
+            material.emissive = vector(json, "emissive", field, defaults.emissive)?;
+            material.shininess = number(json, "shininess", field, defaults.shininess)?;
+            material.opacity = number(json, "opacity", field, defaults.opacity)?;
+            material.metallic = number(json, "metallic", field, defaults.metallic)?;
+            material.roughness = number(json, "roughness", field, defaults.roughness)?;
+            material.diffuse_map = path("diffuse_map")?;
+            material.bump_map = path("bump_map")?;
+            material.bump_multiplier = number(json, "bump_multiplier", field, defaults.bump_multiplier)?;
+            material.normal_map = path("normal_map")?;
+            material.alpha_map = path("alpha_map")?;
+            return Ok(ShaderDescription::Material(material));
+        }
+        Some(other) => return Err(invalid(&format!("{}.type", field), &format!("unknown shader type '{}'", other))),
+        None => return Err(invalid(&format!("{}.type", field), "missing")),
+    }
+}
+
+
+
+fn shader_json(shader: &ShaderDescription, base_dir: &Path) -> Json {
+    let path = |path: &str| string_json(&relative_path(path, base_dir));
+
+    match shader {
+        ShaderDescription::Solid => return object(vec![("type", string_json("solid"))]),
+        ShaderDescription::VertexColor => return object(vec![("type", string_json("vertex_color"))]),
+        ShaderDescription::Texture { texture } => return object(vec![("type", string_json("texture")), ("texture", path(texture))]),
+        ShaderDescription::NormalMap { normal_map, diffuse_texture } => {
+            let mut members = vec![("type", string_json("normal_map")), ("normal_map", path(normal_map))];
+            if let Some(diffuse) = diffuse_texture {
+                members.push(("diffuse_texture", path(diffuse)));
+            }
+            return object(members);
+        }
+        ShaderDescription::Material(material) => {
+            let mut members = vec![
+                ("type", string_json("material")),
+                ("name", string_json(&material.name)),
+                ("ambient", vector_json(material.ambient)),
+                ("diffuse", vector_json(material.diffuse)),
+                ("specular", vector_json(material.specular)),
+                ("emissive", vector_json(material.emissive)),
+                ("shininess", number_json(material.shininess)),
+                ("opacity", number_json(material.opacity)),
+                ("metallic", number_json(material.metallic)),
+                ("roughness", number_json(material.roughness)),
+            ];
+            let maps = [
+                ("diffuse_map", &material.diffuse_map),
+                ("bump_map", &material.bump_map),
+                ("normal_map", &material.normal_map),
+                ("alpha_map", &material.alpha_map),
+            ];
+            for (key, map) in maps {
+                if let Some(map) = map {
+                    members.push((key, path(map)));
+                }
+            }
+            if material.bump_multiplier != 1.0 {
+                members.push(("bump_multiplier", number_json(material.bump_multiplier)));
+            }
+            return object(members);
+        }
+    }
+}
+
+
+
+fn transform_json(transform: &Transform) -> Json {
+    let mut members = vec![
+        ("position", vector_json(transform.position)),
+        ("yaw", number_json(transform.yaw)),
+        ("pitch", number_json(transform.pitch)),
+    ];
+    if transform.scale != Transform::new().scale {
+        members.push(("scale", vector_json(transform.scale)));
+    }
+    return object(members);
+}
+
+
+
+fn object(members: Vec<(&str, Json)>) -> Json {
+    return Json::Object(members.into_iter().map(|(key, value)| (key.to_string(), value)).collect());
+}
+
+
+
+fn string_json(s: &str) -> Json {
+    return Json::String(s.to_string());
+}
+
+
+
+// via the shortest f32 representation so 0.1 is written as 0.1, not 0.10000000149011612
+fn number_json(n: f32) -> Json {
+    return Json::Number(n.to_string().parse().unwrap_or(0.0));
+}
+
+
+
+fn vector_json(v: Vec3) -> Json {
+    return Json::Array(vec![number_json(v.x), number_json(v.y), number_json(v.z)]);
+}
+
+
+
+fn invalid(field: &str, message: &str) -> SceneError {
+    return SceneError::Invalid { field: field.to_string(), message: message.to_string() };
+}
+
+
+
+// missing arrays are empty
+fn array<'a>(json: &'a Json, key: &str, field: &str) -> Result<&'a [Json], SceneError> {
+    match json.get(key) {
+        None => return Ok(&[]),
+        Some(value) => return value.as_array().ok_or_else(|| invalid(&format!("{}.{}", field, key), "expected an array")),
+    }
+}
+
+
+
+fn number(json: &Json, key: &str, field: &str, default: f32) -> Result<f32, SceneError> {
+    match json.get(key) {
+        None => return Ok(default),
+        Some(value) => return value.as_f32().ok_or_else(|| invalid(&format!("{}.{}", field, key), "expected a number")),
+    }
+}
+
+
+
+fn integer(json: &Json, key: &str, field: &str, default: u32) -> Result<u32, SceneError> {
+    match json.get(key) {
+        None => return Ok(default),
+        Some(value) => {
+            let integer = value.as_usize().and_then(|n| u32::try_from(n).ok());
+            return integer.ok_or_else(|| invalid(&format!("{}.{}", field, key), "expected a non-negative integer"));
+        }
+    }
+}
+
+
+
+fn vector(json: &Json, key: &str, field: &str, default: Vec3) -> Result<Vec3, SceneError> {
+    match json.get(key) {
+        None => return Ok(default),
+        Some(value) => match value.as_f32_array().as_deref() {
+            Some(&[x, y, z]) => return Ok(Vec3 { x, y, z }),
+            _ => return Err(invalid(&format!("{}.{}", field, key), "expected an array of three numbers")),
+        },
+    }
+}
+
+
+
+// directory of `file` as an absolute path, the file itself doesn't have to exist
+fn absolute_dir(file: &str) -> Result<PathBuf, SceneError> {
+    let absolute = std::path::absolute(file)?;
+    return Ok(normalize(absolute.parent().unwrap_or(Path::new(""))));
+}
+
+
+
+// resolves `.` and `..` without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    return normalized;
+}
+
+
+
+fn resolve(base_dir: &Path, relative: &str) -> String {
+    return normalize(&base_dir.join(relative)).to_string_lossy().into_owned();
+}
+
+
+
+// `path` relative to `base_dir`, both absolute. paths on another root are kept absolute
+fn relative_path(path: &str, base_dir: &Path) -> String {
+    let path = Path::new(path);
+    let common = path.components().zip(base_dir.components()).take_while(|(a, b)| a == b).count();
+    if common == 0 {
+        return path.to_string_lossy().into_owned();
+    }
+
+    let mut relative = PathBuf::new();
+    for _ in common..base_dir.components().count() {
+        relative.push("..");
+    }
+    for component in path.components().skip(common) {
+        relative.push(component);
+    }
+    return relative.to_string_lossy().into_owned();
+}
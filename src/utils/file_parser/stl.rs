@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Result, Write};
+
+use crate::types::normals::{generate_normals, NormalMode, NormalWeighting, DEFAULT_SMOOTHING_ANGLE};
+use crate::types::shader::SolidShader;
+use crate::{vec2, vec3, Model, Vec2, Vec3, Vertex};
+use super::error::{ByteReader, StlError};
+
+
+
+pub struct StlFile {
+    // solid name for ASCII files, the 80 byte header for binary ones
+    pub name: String,
+    pub model: Model,
+    pub warnings: Vec<String>,
+}
+
+
+
+const BINARY_HEADER_SIZE: usize = 84;
+const BINARY_TRIANGLE_SIZE: usize = 50;
+
+// positions closer than this fraction of the bounding box diagonal are welded
+const WELD_TOLERANCE: f32 = 1e-6;
+
+
+
+/// reads ASCII or binary STL, welding near-identical vertices and generating smooth normals with a crease angle
+pub fn parse_stl(path: &str) -> std::result::Result<StlFile, StlError> {
+    let bytes = std::fs::read(path)?;
+
+    // binary files may also start with "solid", their zero padded headers give them away
+    let looks_ascii = bytes.trim_ascii_start().starts_with(b"solid") && !bytes[..bytes.len().min(BINARY_HEADER_SIZE)].contains(&0);
+    let is_binary = match ByteReader::new(&bytes).u32_le(80) {
+        Ok(count) => bytes.len() == BINARY_HEADER_SIZE + count as usize * BINARY_TRIANGLE_SIZE || !looks_ascii,
+        Err(_) => !looks_ascii,
+    };
+
+    let (name, mut positions) = if is_binary { decode_binary(&bytes)? } else { decode_ascii(&bytes)? };
+
+    let mut warnings: Vec<String> = Vec::new();
+    weld(&mut positions);
+
+    // welding collapses slivers, those would only get in the way of normal generation
+    let triangle_count = positions.len() / 3;
+    let mut vertices: Vec<Vertex> = Vec::with_capacity(positions.len());
+    for triangle in positions.chunks_exact(3) {
+        if triangle[0] == triangle[1] || triangle[1] == triangle[2] || triangle[0] == triangle[2] {
+            continue;
+        }
+        for &position in triangle {
+            vertices.push(Vertex {
+                position,
+                texcoord: vec2!(0, 0),
+                normal: vec3!(0, 0, 0),
+                tangent: vec3!(0, 0, 0),
+                bitangent_sign: 1.0,
+                color: vec3!(1, 1, 1),
+                joints: [0; 4],
+                weights: [0.0; 4],
+            });
+        }
+    }
+    let dropped = triangle_count - vertices.len() / 3;
+    if dropped > 0 {
+        warnings.push(format!("{} degenerate triangles removed", dropped));
+    }
+
+    // facet normals in the wild are often wrong or zero, the winding is trusted instead
+    let mode = NormalMode::Smooth { angle_threshold: DEFAULT_SMOOTHING_ANGLE, weighting: NormalWeighting::Angle };
+    generate_normals(&mut vertices, None, mode);
+
+    let model = Model::new(vertices, Box::new(SolidShader::new()));
+    return Ok(StlFile { name, model, warnings });
+}
+
+
+
+fn decode_binary(bytes: &[u8]) -> std::result::Result<(String, Vec<Vec3>), StlError> {
+    let reader = ByteReader::new(bytes);
+    let triangles = reader.u32_le(80).unwrap_or(0);
+    let expected = BINARY_HEADER_SIZE + triangles as usize * BINARY_TRIANGLE_SIZE;
+    if bytes.len() < expected {
+        return Err(StlError::Truncated { triangles, expected, actual: bytes.len() });
+    }
+
+    let header = String::from_utf8_lossy(&bytes[..80]);
+    let name = header.trim_end_matches(['\0', ' ']).to_string();
+
+    let float = |offset: usize| f32::from_bits(reader.u32_le(offset).unwrap());
+    let mut positions: Vec<Vec3> = Vec::with_capacity(triangles as usize * 3);
+    for triangle in 0..triangles as usize {
+        // skip the facet normal, three vertices follow, then a 16 bit attribute
+        let base = BINARY_HEADER_SIZE + triangle * BINARY_TRIANGLE_SIZE + 12;
+        for corner in 0..3 {
+            let offset = base + corner * 12;
+            positions.push(vec3!(float(offset), float(offset + 4), float(offset + 8)));
+        }
+    }
+
+    return Ok((name, positions));
+}
+
+
+
+fn decode_ascii(bytes: &[u8]) -> std::result::Result<(String, Vec<Vec3>), StlError> {
+    let text = String::from_utf8_lossy(bytes);
+    let mut tokens = text
+        .lines()
+        .enumerate()
+        .flat_map(|(index, line)| line.split_whitespace().map(move |token| (index + 1, token)));
+
+    let mut name = String::new();
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut facet_vertices = 0;
+    let mut last_line = 1;
+
+    while let Some((line, token)) = tokens.next() {
+        last_line = line;
+        let syntax = |message: String| StlError::Syntax { line, message };
+
+        match token.to_ascii_lowercase().as_str() {
+            "solid" => {
+                // the name is the rest of the line
+                if name.is_empty() {
+                    name = text.lines().nth(line - 1).unwrap_or("").trim().trim_start_matches("solid").trim().to_string();
+                }
+                while tokens.clone().next().is_some_and(|(next_line, _)| next_line == line) {
+                    tokens.next();
+                }
+            }
+            "facet" => {
+                if facet_vertices != 0 {
+                    return Err(syntax("'facet' inside another facet".to_string()));
+                }
+            }
+            "normal" => {
+                // facet normals are recomputed from the winding
+                for _ in 0..3 {
+                    tokens.next();
+                }
+            }
+            "vertex" => {
+                let mut coordinates = [0.0; 3];
+                for coordinate in &mut coordinates {
+                    let (_, value) = tokens.next().ok_or_else(|| syntax("'vertex' needs three coordinates".to_string()))?;
+                    *coordinate = value.parse().map_err(|_| syntax(format!("invalid number '{}'", value)))?;
+                }
+                positions.push(vec3!(coordinates[0], coordinates[1], coordinates[2]));
+                facet_vertices += 1;
+            }
+            "endfacet" => {
+                if facet_vertices != 3 {
+                    return Err(syntax(format!("facet has {} vertices, expected 3", facet_vertices)));
+                }
+                facet_vertices = 0;
+            }
+            "endsolid" => {
+                // skip the repeated name
+                while tokens.clone().next().is_some_and(|(next_line, _)| next_line == line) {
+                    tokens.next();
+                }
+            }
+            "outer" | "loop" | "endloop" => {}
+            _ => return Err(syntax(format!("unexpected '{}'", token))),
+        }
+    }
+
+    if facet_vertices != 0 {
+        return Err(StlError::Syntax { line: last_line, message: "file ends inside a facet".to_string() });
+    }
+    return Ok((name, positions));
+}
+
+
+
+// snaps positions within tolerance of each other onto the first one seen
+fn weld(positions: &mut [Vec3]) {
+    if positions.is_empty() {
+        return;
+    }
+
+    let mut min = positions[0];
+    let mut max = positions[0];
+    for p in positions.iter() {
+        min = vec3!(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z));
+        max = vec3!(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z));
+    }
+    let tolerance = (max - min).length() * WELD_TOLERANCE;
+    if tolerance == 0.0 {
+        return;
+    }
+
+    let cell_of = |p: Vec3| -> [i64; 3] {
+        return [(p.x / tolerance).floor() as i64, (p.y / tolerance).floor() as i64, (p.z / tolerance).floor() as i64];
+    };
+    let mut cells: HashMap<[i64; 3], Vec<Vec3>> = HashMap::new();
+
+    for position in positions.iter_mut() {
+        let cell = cell_of(*position);
+
+        // a match can sit in any neighbouring cell
+        let mut found: Option<Vec3> = None;
+        'search: for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let Some(candidates) = cells.get(&[cell[0] + dx, cell[1] + dy, cell[2] + dz]) else { continue };
+                    if let Some(&candidate) = candidates.iter().find(|&&c| (c - *position).length() <= tolerance) {
+                        found = Some(candidate);
+                        break 'search;
+                    }
+                }
+            }
+        }
+
+        match found {
+            Some(canonical) => *position = canonical,
+            None => cells.entry(cell).or_default().push(*position),
+        }
+    }
+}
+
+
+
+/// writes the model's triangles in model space as binary STL, facet normals come from the winding
+pub fn write_stl(model: &Model, path: &str) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    let mut header = [0u8; 80];
+    let label = b"rasterizer-rust binary STL";
+    header[..label.len()].copy_from_slice(label);
+    writer.write_all(&header)?;
+
+    let triangles = model.vertices.len() / 3;
+    let count = u32::try_from(triangles).map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidInput, "too many triangles for STL"))?;
+    writer.write_all(&count.to_le_bytes())?;
+
+    for triangle in model.vertices.chunks_exact(3) {
+        let normal = (triangle[1].position - triangle[0].position).cross(triangle[2].position - triangle[0].position).normalize();
+        write_vec3(&mut writer, normal)?;
+        for vertex in triangle {
+            write_vec3(&mut writer, vertex.position)?;
+        }
+        writer.write_all(&0u16.to_le_bytes())?;
+    }
+
+    return writer.flush();
+}
+
+
+
+fn write_vec3(writer: &mut impl Write, v: Vec3) -> Result<()> {
+    writer.write_all(&v.x.to_le_bytes())?;
+    writer.write_all(&v.y.to_le_bytes())?;
+    return writer.write_all(&v.z.to_le_bytes());
+}
@@ -0,0 +1,38 @@
+use std::path::Path;
+
+use crate::Texture;
+use super::bitmap::decode_bitmap;
+use super::error::ImageError;
+use super::png::decode_png;
+use super::tga::decode_tga;
+
+
+
+/// loads any supported image, picking the decoder from the file signature
+pub fn read_texture(path: &str) -> Result<Texture, ImageError> {
+    let bytes = std::fs::read(path)?;
+    let extension = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or("");
+    return decode_texture(&bytes, extension).map_err(|err| match err {
+        ImageError::UnknownFormat(_) => ImageError::UnknownFormat(path.to_string()),
+        err => err,
+    });
+}
+
+
+
+/// decodes an in-memory image, `extension` is only consulted for formats without a signature
+pub fn decode_texture(bytes: &[u8], extension: &str) -> Result<Texture, ImageError> {
+    if bytes.starts_with(&[137, 80, 78, 71]) {
+        return decode_png(bytes);
+    }
+    if bytes.starts_with(b"BM") {
+        return decode_bitmap(bytes);
+    }
+
+    // TGA has no signature, trust the extension
+    if extension.eq_ignore_ascii_case("tga") {
+        return decode_tga(bytes);
+    }
+
+    return Err(ImageError::UnknownFormat(format!("'{}' image", extension)));
+}
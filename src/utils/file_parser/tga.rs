@@ -0,0 +1,231 @@
+use crate::Texture;
+use super::error::{ByteReader, ImageError};
+
+
+
+const HEADER_SIZE: usize = 18;
+
+const TYPE_COLOR_MAPPED: u8 = 1;
+const TYPE_TRUE_COLOR: u8 = 2;
+const TYPE_GRAYSCALE: u8 = 3;
+const TYPE_RLE_COLOR_MAPPED: u8 = 9;
+const TYPE_RLE_TRUE_COLOR: u8 = 10;
+const TYPE_RLE_GRAYSCALE: u8 = 11;
+
+// image descriptor bits
+const DESCRIPTOR_ALPHA_BITS: u8 = 0x0F;
+const DESCRIPTOR_RIGHT_TO_LEFT: u8 = 0x10;
+const DESCRIPTOR_TOP_TO_BOTTOM: u8 = 0x20;
+
+// 65535x65535 fits the header, keeps allocations sane
+const MAX_PIXELS: u64 = 1 << 28;
+
+
+
+struct TgaHeader {
+    image_type: u8,
+    color_map_type: u8,
+    color_map_first: u16,
+    color_map_length: u16,
+    color_map_depth: u8,
+    width: u32,
+    height: u32,
+    pixel_depth: u8,
+    alpha_bits: u8,
+    right_to_left: bool,
+    top_to_bottom: bool,
+}
+
+
+
+pub fn read_tga(path: &str) -> Result<Texture, ImageError> {
+    let bytes = std::fs::read(path)?;
+    return decode_tga(&bytes);
+}
+
+
+
+pub fn decode_tga(bytes: &[u8]) -> Result<Texture, ImageError> {
+    let reader = ByteReader::new(bytes);
+    let header = parse_header(&reader)?;
+
+    let id_length = reader.u8(0)? as usize;
+    let mut offset = HEADER_SIZE + id_length;
+
+    // a color map may be present even for true color images, it is skipped there
+    let mut palette = Vec::new();
+    if header.color_map_type == 1 {
+        let entry_size = (header.color_map_depth as usize).div_ceil(8);
+        let map_bytes = reader.slice(offset, header.color_map_length as usize * entry_size)?;
+        for entry in map_bytes.chunks_exact(entry_size) {
+            palette.push(decode_color(entry, header.color_map_depth, header.alpha_bits));
+        }
+        offset += map_bytes.len();
+    }
+
+    let bytes_per_pixel = (header.pixel_depth as usize).div_ceil(8);
+    let pixel_count = (header.width * header.height) as usize;
+
+    let packed = match header.image_type {
+        TYPE_RLE_COLOR_MAPPED | TYPE_RLE_TRUE_COLOR | TYPE_RLE_GRAYSCALE => {
+            decode_rle(&reader, offset, bytes_per_pixel, pixel_count)?
+        }
+        _ => reader.slice(offset, pixel_count * bytes_per_pixel)?.to_vec(),
+    };
+
+    let mut pixels = Vec::with_capacity(pixel_count);
+    for raw in packed.chunks_exact(bytes_per_pixel) {
+        let color = match header.image_type {
+            TYPE_COLOR_MAPPED | TYPE_RLE_COLOR_MAPPED => {
+                let index = if bytes_per_pixel == 1 { raw[0] as u32 } else { u16::from_le_bytes([raw[0], raw[1]]) as u32 };
+                let entry = index.wrapping_sub(header.color_map_first as u32);
+                *palette
+                    .get(entry as usize)
+                    .ok_or(ImageError::PaletteIndexOutOfRange { index, palette_len: palette.len() })?
+            }
+            TYPE_GRAYSCALE | TYPE_RLE_GRAYSCALE => {
+                let gray = raw[0] as u32;
+                let alpha = if bytes_per_pixel == 2 { raw[1] as u32 } else { 0xFF };
+                (alpha << 24) | (gray << 16) | (gray << 8) | gray
+            }
+            _ => decode_color(raw, header.pixel_depth, header.alpha_bits),
+        };
+        pixels.push(color);
+    }
+
+    // 32 bit writers that don't set the alpha bits often leave the channel zeroed
+    if header.pixel_depth == 32 && header.alpha_bits == 0 && pixels.iter().all(|c| c >> 24 == 0) {
+        for c in pixels.iter_mut() {
+            *c |= 0xFF000000;
+        }
+    }
+
+    // reorder into top-left origin
+    let width = header.width as usize;
+    let height = header.height as usize;
+    let mut texture_data = vec![0u32; pixel_count];
+    for row in 0..height {
+        let y_tex = if header.top_to_bottom { row } else { height - 1 - row };
+        for col in 0..width {
+            let x_tex = if header.right_to_left { width - 1 - col } else { col };
+            texture_data[y_tex * width + x_tex] = pixels[row * width + col];
+        }
+    }
+
+    return Ok(Texture::new(texture_data, header.width, header.height));
+}
+
+
+
+fn parse_header(reader: &ByteReader) -> Result<TgaHeader, ImageError> {
+    let color_map_type = reader.u8(1)?;
+    let image_type = reader.u8(2)?;
+    let descriptor = reader.u8(17)?;
+
+    let header = TgaHeader {
+        image_type,
+        color_map_type,
+        color_map_first: reader.u16_le(3)?,
+        color_map_length: reader.u16_le(5)?,
+        color_map_depth: reader.u8(7)?,
+        width: reader.u16_le(12)? as u32,
+        height: reader.u16_le(14)? as u32,
+        pixel_depth: reader.u8(16)?,
+        alpha_bits: descriptor & DESCRIPTOR_ALPHA_BITS,
+        right_to_left: descriptor & DESCRIPTOR_RIGHT_TO_LEFT != 0,
+        top_to_bottom: descriptor & DESCRIPTOR_TOP_TO_BOTTOM != 0,
+    };
+
+    if color_map_type > 1 {
+        return Err(ImageError::InvalidColorMapType(color_map_type));
+    }
+    if header.width == 0 || header.height == 0 || header.width as u64 * header.height as u64 > MAX_PIXELS {
+        return Err(ImageError::InvalidDimensions { width: header.width as i64, height: header.height as i64 });
+    }
+
+    let depth = header.pixel_depth;
+    let depth_ok = match image_type {
+        TYPE_COLOR_MAPPED | TYPE_RLE_COLOR_MAPPED => {
+            if color_map_type != 1 {
+                return Err(ImageError::MissingColorMap);
+            }
+            if ![15, 16, 24, 32].contains(&header.color_map_depth) {
+                return Err(ImageError::UnsupportedColorMapDepth(header.color_map_depth));
+            }
+            depth == 8 || depth == 16
+        }
+        TYPE_TRUE_COLOR | TYPE_RLE_TRUE_COLOR => [15, 16, 24, 32].contains(&depth),
+        TYPE_GRAYSCALE | TYPE_RLE_GRAYSCALE => depth == 8 || depth == 16,
+        _ => return Err(ImageError::UnsupportedImageType(image_type)),
+    };
+    if !depth_ok {
+        return Err(ImageError::UnsupportedBitDepth(depth as u16));
+    }
+
+    return Ok(header);
+}
+
+
+
+// true color and color map entries share the same BGR(A) layouts
+fn decode_color(raw: &[u8], depth: u8, alpha_bits: u8) -> u32 {
+    match depth {
+        15 | 16 => {
+            let value = u16::from_le_bytes([raw[0], raw[1]]) as u32;
+            let r = expand_5_bits((value >> 10) & 0x1F);
+            let g = expand_5_bits((value >> 5) & 0x1F);
+            let b = expand_5_bits(value & 0x1F);
+            // the attribute bit only means alpha when the descriptor says so
+            let a = if depth == 16 && alpha_bits == 1 && value & 0x8000 == 0 { 0 } else { 0xFF };
+            return (a << 24) | (r << 16) | (g << 8) | b;
+        }
+        24 => {
+            return 0xFF000000 | ((raw[2] as u32) << 16) | ((raw[1] as u32) << 8) | raw[0] as u32;
+        }
+        _ => {
+            return ((raw[3] as u32) << 24) | ((raw[2] as u32) << 16) | ((raw[1] as u32) << 8) | raw[0] as u32;
+        }
+    }
+}
+
+
+
+fn expand_5_bits(value: u32) -> u32 {
+    return (value << 3) | (value >> 2);
+}
+
+
+
+// packets may straddle scanlines, so decode into one flat run of raw pixels
+fn decode_rle(reader: &ByteReader, mut offset: usize, bytes_per_pixel: usize, pixel_count: usize) -> Result<Vec<u8>, ImageError> {
+    let total = pixel_count * bytes_per_pixel;
+    let mut out = Vec::with_capacity(total);
+
+    while out.len() < total {
+        let packet_start = offset;
+        let packet = reader
+            .u8(offset)
+            .map_err(|_| ImageError::CorruptRle { offset: packet_start, reason: "image data ends before the last pixel" })?;
+        offset += 1;
+
+        let count = (packet & 0x7F) as usize + 1;
+        let is_run = packet & 0x80 != 0;
+        let data_len = if is_run { bytes_per_pixel } else { count * bytes_per_pixel };
+        let data = reader
+            .slice(offset, data_len)
+            .map_err(|_| ImageError::CorruptRle { offset: packet_start, reason: "packet extends past end of data" })?;
+        offset += data_len;
+
+        if is_run {
+            for _ in 0..count {
+                out.extend_from_slice(data);
+            }
+        } else {
+            out.extend_from_slice(data);
+        }
+    }
+
+    // the last packet may overshoot the image, ignore the excess
+    out.truncate(total);
+    return Ok(out);
+}
@@ -0,0 +1,52 @@
+//! procedural meshes and textures shared by the golden tests and the benchmarks
+
+use std::f32::consts::PI;
+
+use crate::{vec2, vec3, Texture, Vec2, Vec3, Vertex};
+
+
+
+/// facing +z with a +x tangent, joints and weights unused
+pub fn vertex(position: Vec3, texcoord: Vec2, color: Vec3) -> Vertex {
+    return Vertex { position, texcoord, normal: vec3!(0, 0, 1), tangent: vec3!(1, 0, 0), bitangent_sign: 1.0, color, joints: [0; 4], weights: [0.0; 4] };
+}
+
+
+/// latitude/longitude sphere wound to face outwards, white with analytic normals, texcoords wrap once around
+pub fn sphere_vertices(center: Vec3, radius: f32, rings: u32, segments: u32) -> Vec<Vertex> {
+    let point = |ring: u32, segment: u32| -> Vertex {
+        let (u, v) = (segment as f32 / segments as f32, ring as f32 / rings as f32);
+        let (theta, phi) = (u * 2.0 * PI, v * PI);
+        let normal = vec3!(phi.sin() * theta.sin(), phi.cos(), phi.sin() * theta.cos());
+        let mut vertex = vertex(center + normal * radius, vec2!(u, v), vec3!(1, 1, 1));
+        vertex.normal = normal;
+        return vertex;
+    };
+
+    let mut vertices: Vec<Vertex> = Vec::new();
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let (a, b) = (point(ring, segment), point(ring, segment + 1));
+            let (c, d) = (point(ring + 1, segment), point(ring + 1, segment + 1));
+            vertices.extend_from_slice(&[a, c, d, a, d, b]);
+        }
+    }
+    return vertices;
+}
+
+
+/// light and dark cells tinted by position, so flips and offsets show up
+pub fn checker_texture(size: u32, cells: u32) -> Texture {
+    let cell_size = (size / cells).max(1);
+    let mut data: Vec<u32> = Vec::with_capacity((size * size) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let light = (x / cell_size + y / cell_size).is_multiple_of(2);
+            let base = if light { 200 } else { 60 };
+            let r = base + 55 * x / size;
+            let b = base + 55 * y / size;
+            data.push(0xFF000000 | r << 16 | base << 8 | b);
+        }
+    }
+    return Texture::new(data, size, size);
+}
@@ -0,0 +1,254 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use winit::event_loop::ControlFlow;
+
+
+
+// frames kept for the rolling statistics, two seconds at 60 fps
+pub const FRAME_HISTORY: usize = 120;
+
+// thread::sleep can overshoot by about a scheduler tick, the last stretch is spun instead
+const SPIN_MARGIN: Duration = Duration::from_micros(1500);
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FramePacing {
+    // render as fast as possible
+    Uncapped,
+    // render at `target_fps`, sleeping then spinning until each frame's deadline
+    Fixed,
+    // render only after input or when a frame is requested, idle otherwise
+    OnDemand,
+}
+
+
+
+impl FramePacing {
+    pub fn parse(name: &str) -> Option<FramePacing> {
+        match name {
+            "uncapped" => return Some(FramePacing::Uncapped),
+            "fixed" => return Some(FramePacing::Fixed),
+            "on_demand" | "on-demand" => return Some(FramePacing::OnDemand),
+            _ => return None,
+        }
+    }
+
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FramePacing::Uncapped => return "uncapped",
+            FramePacing::Fixed => return "fixed",
+            FramePacing::OnDemand => return "on_demand",
+        }
+    }
+}
+
+
+
+/// decides when the view renders and keeps statistics on the frames it did render
+pub struct FramePacer {
+    pub mode: FramePacing,
+    pub target_fps: u64,
+    pub stats: FrameStats,
+    next_deadline: Option<Instant>,
+    // on-demand state: input arrived, or the app asked for another frame
+    dirty: bool,
+    frame_requested: bool,
+    // the loop waited for input since the last frame
+    idled: bool,
+}
+
+
+
+impl FramePacer {
+    pub fn new(mode: FramePacing, target_fps: u64) -> FramePacer {
+        return FramePacer {
+            mode,
+            target_fps,
+            stats: FrameStats::new(),
+            next_deadline: None,
+            dirty: true,
+            frame_requested: false,
+            idled: false,
+        };
+    }
+
+
+    /// something happened that could change what's on screen
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+
+    /// asks for another frame even without input, e.g. while something is animating
+    pub fn request_frame(&mut self) {
+        self.frame_requested = true;
+    }
+
+
+    pub fn should_render(&self) -> bool {
+        return self.mode != FramePacing::OnDemand || self.dirty || self.frame_requested;
+    }
+
+
+    /// blocks until the next frame is due, only fixed pacing ever waits
+    pub fn wait(&mut self) {
+        if self.mode != FramePacing::Fixed || self.target_fps == 0 {
+            return;
+        }
+
+        let period = Duration::from_secs_f64(1.0 / self.target_fps as f64);
+        let now = Instant::now();
+        let deadline = self.next_deadline.unwrap_or(now);
+
+        if now < deadline {
+            let remaining = deadline - now;
+            if remaining > SPIN_MARGIN {
+                std::thread::sleep(remaining - SPIN_MARGIN);
+            }
+            while Instant::now() < deadline {
+                std::hint::spin_loop();
+            }
+        }
+
+        // a frame that ran long pushes the schedule back instead of rushing to catch up
+        let next = deadline + period;
+        self.next_deadline = Some(if next < now { now + period } else { next });
+    }
+
+
+    /// called before the frame runs, so frames it requests aren't forgotten when it finishes
+    pub fn frame_started(&mut self) {
+        self.dirty = false;
+        self.frame_requested = false;
+    }
+
+
+    pub fn frame_finished(&mut self, frame_start: Instant) {
+        self.stats.record(frame_start, frame_start.elapsed());
+        self.idled = false;
+    }
+
+
+    /// nothing to render, the loop waits for input. the gap until the next frame isn't frame time
+    pub fn idle(&mut self) {
+        self.idled = true;
+        self.stats.skip_interval();
+    }
+
+
+    /// whether the loop waited for input since the last frame, time since then wasn't spent rendering
+    pub fn woke_from_idle(&self) -> bool {
+        return self.idled;
+    }
+
+
+    /// how the event loop should wait after this iteration
+    pub fn control_flow(&self) -> ControlFlow {
+        if self.should_render() {
+            return ControlFlow::Poll;
+        }
+        return ControlFlow::Wait;
+    }
+}
+
+
+
+/// rolling frame timings over the last `FRAME_HISTORY` frames.
+/// frame time is the work done in a frame, the interval is start to start and includes any waiting
+pub struct FrameStats {
+    pub frame_count: u64,
+    frame_times: VecDeque<Duration>,
+    frame_intervals: VecDeque<Duration>,
+    last_start: Option<Instant>,
+}
+
+
+
+impl FrameStats {
+    pub fn new() -> FrameStats {
+        return FrameStats {
+            frame_count: 0,
+            frame_times: VecDeque::with_capacity(FRAME_HISTORY),
+            frame_intervals: VecDeque::with_capacity(FRAME_HISTORY),
+            last_start: None,
+        };
+    }
+
+
+    pub fn record(&mut self, frame_start: Instant, frame_time: Duration) {
+        if self.frame_times.len() == FRAME_HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+
+        if let Some(last_start) = self.last_start {
+            if self.frame_intervals.len() == FRAME_HISTORY {
+                self.frame_intervals.pop_front();
+            }
+            self.frame_intervals.push_back(frame_start - last_start);
+        }
+        self.last_start = Some(frame_start);
+        self.frame_count += 1;
+    }
+
+
+    /// the next frame starts a new interval instead of measuring from the last one
+    pub fn skip_interval(&mut self) {
+        self.last_start = None;
+    }
+
+
+    /// recent frame times, oldest first
+    pub fn frame_times(&self) -> impl Iterator<Item = Duration> + '_ {
+        return self.frame_times.iter().copied();
+    }
+
+
+    pub fn last_frame_time(&self) -> Duration {
+        return self.frame_times.back().copied().unwrap_or_default();
+    }
+
+
+    pub fn average_frame_time(&self) -> Duration {
+        return average(&self.frame_times);
+    }
+
+
+    pub fn max_frame_time(&self) -> Duration {
+        return self.frame_times.iter().max().copied().unwrap_or_default();
+    }
+
+
+    /// e.g. 0.99 for the time 99% of recent frames came in under
+    pub fn percentile_frame_time(&self, percentile: f32) -> Duration {
+        if self.frame_times.is_empty() {
+            return Duration::ZERO;
+        }
+        let mut sorted: Vec<Duration> = self.frame_times.iter().copied().collect();
+        sorted.sort();
+        let index = ((sorted.len() - 1) as f32 * percentile.clamp(0.0, 1.0)).round() as usize;
+        return sorted[index];
+    }
+
+
+    /// frames per second from the average interval, 0 until two frames have been seen
+    pub fn fps(&self) -> f32 {
+        let interval = average(&self.frame_intervals).as_secs_f32();
+        if interval == 0.0 {
+            return 0.0;
+        }
+        return 1.0 / interval;
+    }
+}
+
+
+
+fn average(samples: &VecDeque<Duration>) -> Duration {
+    if samples.is_empty() {
+        return Duration::ZERO;
+    }
+    return samples.iter().sum::<Duration>() / samples.len() as u32;
+}
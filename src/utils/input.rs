@@ -0,0 +1,421 @@
+use std::fmt;
+
+use winit::event::MouseButton;
+use winit::keyboard::KeyCode;
+use winit_input_helper::WinitInputHelper;
+
+
+
+/// a single key, button or wheel direction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Input {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    // either the left or right key, for chords like ctrl+s
+    Modifier(Modifier),
+    // the wheel turning this frame
+    ScrollUp,
+    ScrollDown,
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Modifier {
+    Shift,
+    Control,
+    Alt,
+}
+
+
+
+/// inputs that trigger an action together, e.g. ctrl+s. a single input is a chord of one
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chord {
+    pub inputs: Vec<Input>,
+}
+
+
+
+/// one contribution to an axis, the axis value is the sum of all of them
+#[derive(Debug, Clone, PartialEq)]
+pub enum AxisSource {
+    // +1 while `positive` is held, -1 while `negative` is
+    Keys { positive: Input, negative: Input },
+    // movement this frame, pixels for the mouse and lines for the wheel
+    MouseX,
+    MouseY,
+    ScrollX,
+    ScrollY,
+}
+
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AxisBinding {
+    pub source: AxisSource,
+    pub scale: f32,
+}
+
+
+
+/// named actions and axes bound to keys, mouse buttons and the wheel, so code asks for "jump" rather than space.
+/// `new` sets up every action and axis the app uses with its default bindings, config files rebind them by name
+#[derive(Debug, Clone)]
+pub struct InputMap {
+    pub actions: Vec<(String, Vec<Chord>)>,
+    pub axes: Vec<(String, Vec<AxisBinding>)>,
+}
+
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputError {
+    pub message: String,
+}
+
+
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+
+
+impl std::error::Error for InputError {}
+
+
+
+impl InputMap {
+    pub fn new() -> InputMap {
+        let mut map = InputMap { actions: Vec::new(), axes: Vec::new() };
+
+        let defaults = [
+            ("grab_mouse", "MouseLeft"),
+            ("release_mouse", "Escape"),
+            ("orbit", "MouseLeft"),
+            ("pan", "MouseRight, MouseMiddle"),
+            ("sprint", "LShift"),
+            ("jump", "Space"),
+            ("pause", "Space"),
+            ("toggle_hud", "F3"),
+            ("next_camera", "F4"),
+            ("next_animation", "F5"),
+            ("screenshot", "F12"),
+            ("record", "F9"),
+        ];
+        for (action, bindings) in defaults {
+            map.actions.push((action.to_string(), parse_chords(bindings).unwrap()));
+        }
+
+        let axes = [
+            ("move_x", "D/A, Right/Left"),
+            ("move_y", "E/Q"),
+            ("move_z", "W/S, Up/Down"),
+            ("look_x", "mouse_x"),
+            ("look_y", "mouse_y"),
+            ("zoom", "scroll_y"),
+        ];
+        for (axis, bindings) in axes {
+            map.axes.push((axis.to_string(), parse_axis_bindings(bindings).unwrap()));
+        }
+        return map;
+    }
+
+
+    /// replaces the bindings of an existing action or axis, e.g. ("jump", "Space, MouseRight") or ("look_y", "-mouse_y").
+    /// an empty list unbinds it
+    pub fn rebind(&mut self, name: &str, bindings: &str) -> Result<(), InputError> {
+        if let Some((_, chords)) = self.actions.iter_mut().find(|(action, _)| action == name) {
+            *chords = parse_chords(bindings)?;
+            return Ok(());
+        }
+        if let Some((_, sources)) = self.axes.iter_mut().find(|(axis, _)| axis == name) {
+            *sources = parse_axis_bindings(bindings)?;
+            return Ok(());
+        }
+        return Err(InputError { message: format!("unknown action or axis '{}'", name) });
+    }
+
+
+    /// true on the frame any of the action's chords completes
+    pub fn pressed(&self, input: &WinitInputHelper, action: &str) -> bool {
+        return self.chords(action).iter().any(|chord| chord.pressed(input));
+    }
+
+
+    pub fn held(&self, input: &WinitInputHelper, action: &str) -> bool {
+        return self.chords(action).iter().any(|chord| chord.held(input));
+    }
+
+
+    /// true on the frame a held chord breaks
+    pub fn released(&self, input: &WinitInputHelper, action: &str) -> bool {
+        return self.chords(action).iter().any(|chord| chord.released(input));
+    }
+
+
+    pub fn axis(&self, input: &WinitInputHelper, axis: &str) -> f32 {
+        let Some((_, sources)) = self.axes.iter().find(|(name, _)| name == axis) else {
+            return 0.0;
+        };
+        return sources.iter().map(|binding| binding.value(input)).sum();
+    }
+
+
+    // unknown actions have no chords, so they never fire
+    fn chords(&self, action: &str) -> &[Chord] {
+        return self.actions.iter().find(|(name, _)| name == action).map_or(&[], |(_, chords)| chords.as_slice());
+    }
+}
+
+
+
+impl Input {
+    fn pressed(self, input: &WinitInputHelper) -> bool {
+        match self {
+            Input::Key(key) => return input.key_pressed(key),
+            Input::Mouse(button) => return input.mouse_pressed(button),
+            Input::Modifier(modifier) => {
+                let (left, right) = modifier.keys();
+                return input.key_pressed(left) || input.key_pressed(right);
+            }
+            Input::ScrollUp => return input.scroll_diff().1 > 0.0,
+            Input::ScrollDown => return input.scroll_diff().1 < 0.0,
+        }
+    }
+
+
+    fn held(self, input: &WinitInputHelper) -> bool {
+        match self {
+            Input::Key(key) => return input.key_held(key),
+            Input::Mouse(button) => return input.mouse_held(button),
+            Input::Modifier(modifier) => {
+                let (left, right) = modifier.keys();
+                return input.key_held(left) || input.key_held(right);
+            }
+            // the wheel only "holds" for the frame it turns
+            Input::ScrollUp | Input::ScrollDown => return self.pressed(input),
+        }
+    }
+
+
+    fn released(self, input: &WinitInputHelper) -> bool {
+        match self {
+            Input::Key(key) => return input.key_released(key),
+            Input::Mouse(button) => return input.mouse_released(button),
+            Input::Modifier(modifier) => {
+                let (left, right) = modifier.keys();
+                return input.key_released(left) || input.key_released(right);
+            }
+            Input::ScrollUp | Input::ScrollDown => return false,
+        }
+    }
+}
+
+
+
+impl Modifier {
+    fn keys(self) -> (KeyCode, KeyCode) {
+        match self {
+            Modifier::Shift => return (KeyCode::ShiftLeft, KeyCode::ShiftRight),
+            Modifier::Control => return (KeyCode::ControlLeft, KeyCode::ControlRight),
+            Modifier::Alt => return (KeyCode::AltLeft, KeyCode::AltRight),
+        }
+    }
+}
+
+
+
+impl Chord {
+    // everything held, and the last of it went down this frame
+    fn pressed(&self, input: &WinitInputHelper) -> bool {
+        return !self.inputs.is_empty() && self.held(input) && self.inputs.iter().any(|i| i.pressed(input));
+    }
+
+
+    fn held(&self, input: &WinitInputHelper) -> bool {
+        return !self.inputs.is_empty() && self.inputs.iter().all(|i| i.held(input));
+    }
+
+
+    // was complete, and some part of it let go this frame
+    fn released(&self, input: &WinitInputHelper) -> bool {
+        return !self.inputs.is_empty()
+            && self.inputs.iter().any(|i| i.released(input))
+            && self.inputs.iter().all(|i| i.held(input) || i.released(input));
+    }
+}
+
+
+
+impl AxisBinding {
+    fn value(&self, input: &WinitInputHelper) -> f32 {
+        let value = match self.source {
+            AxisSource::Keys { positive, negative } => {
+                (positive.held(input) as i32 - negative.held(input) as i32) as f32
+            }
+            AxisSource::MouseX => input.mouse_diff().0,
+            AxisSource::MouseY => input.mouse_diff().1,
+            AxisSource::ScrollX => input.scroll_diff().0,
+            AxisSource::ScrollY => input.scroll_diff().1,
+        };
+        return value * self.scale;
+    }
+}
+
+
+
+/// comma separated chords, each one inputs joined with '+', e.g. "F12, Ctrl+P"
+pub fn parse_chords(text: &str) -> Result<Vec<Chord>, InputError> {
+    let mut chords: Vec<Chord> = Vec::new();
+    for chord in text.split(',').map(str::trim).filter(|chord| !chord.is_empty()) {
+        let inputs = chord.split('+').map(|name| parse_input(name.trim())).collect::<Result<Vec<Input>, InputError>>()?;
+        chords.push(Chord { inputs });
+    }
+    return Ok(chords);
+}
+
+
+
+/// comma separated sources: "mouse_x", "mouse_y", "scroll_x", "scroll_y" or a "Positive/Negative" key pair.
+/// a leading '-' flips one, e.g. "-mouse_y", and "*factor" scales it, e.g. "scroll_y*2"
+pub fn parse_axis_bindings(text: &str) -> Result<Vec<AxisBinding>, InputError> {
+    let mut bindings: Vec<AxisBinding> = Vec::new();
+    for item in text.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+        let (item, mut scale) = match item.split_once('*') {
+            Some((item, factor)) => {
+                let factor: f32 = factor.trim().parse().map_err(|_| InputError { message: format!("bad scale factor '{}'", factor.trim()) })?;
+                (item.trim(), factor)
+            }
+            None => (item, 1.0),
+        };
+        let item = match item.strip_prefix('-') {
+            Some(rest) => {
+                scale = -scale;
+                rest.trim()
+            }
+            None => item,
+        };
+
+        let source = match item.to_ascii_lowercase().as_str() {
+            "mouse_x" => AxisSource::MouseX,
+            "mouse_y" => AxisSource::MouseY,
+            "scroll_x" => AxisSource::ScrollX,
+            "scroll_y" => AxisSource::ScrollY,
+            _ => {
+                let Some((positive, negative)) = item.split_once('/') else {
+                    return Err(InputError { message: format!("expected mouse_x, mouse_y, scroll_x, scroll_y or a key pair like W/S, got '{}'", item) });
+                };
+                AxisSource::Keys { positive: parse_input(positive.trim())?, negative: parse_input(negative.trim())? }
+            }
+        };
+        bindings.push(AxisBinding { source, scale });
+    }
+    return Ok(bindings);
+}
+
+
+
+/// a key, mouse button or wheel direction by name, case insensitive: "W", "F5", "Space", "LShift", "Ctrl", "MouseLeft", "ScrollUp"
+pub fn parse_input(name: &str) -> Result<Input, InputError> {
+    let lower = name.to_ascii_lowercase();
+    let input = match lower.as_str() {
+        "shift" => Input::Modifier(Modifier::Shift),
+        "ctrl" | "control" => Input::Modifier(Modifier::Control),
+        "alt" => Input::Modifier(Modifier::Alt),
+        "mouseleft" => Input::Mouse(MouseButton::Left),
+        "mouseright" => Input::Mouse(MouseButton::Right),
+        "mousemiddle" => Input::Mouse(MouseButton::Middle),
+        "mouseback" | "mouse4" => Input::Mouse(MouseButton::Back),
+        "mouseforward" | "mouse5" => Input::Mouse(MouseButton::Forward),
+        "scrollup" => Input::ScrollUp,
+        "scrolldown" => Input::ScrollDown,
+        _ => match key_code(&lower) {
+            Some(key) => Input::Key(key),
+            None => return Err(InputError { message: format!("unknown key or button '{}'", name) }),
+        },
+    };
+    return Ok(input);
+}
+
+
+
+fn key_code(name: &str) -> Option<KeyCode> {
+    const LETTERS: [KeyCode; 26] = [
+        KeyCode::KeyA, KeyCode::KeyB, KeyCode::KeyC, KeyCode::KeyD, KeyCode::KeyE, KeyCode::KeyF, KeyCode::KeyG, KeyCode::KeyH, KeyCode::KeyI,
+        KeyCode::KeyJ, KeyCode::KeyK, KeyCode::KeyL, KeyCode::KeyM, KeyCode::KeyN, KeyCode::KeyO, KeyCode::KeyP, KeyCode::KeyQ, KeyCode::KeyR,
+        KeyCode::KeyS, KeyCode::KeyT, KeyCode::KeyU, KeyCode::KeyV, KeyCode::KeyW, KeyCode::KeyX, KeyCode::KeyY, KeyCode::KeyZ,
+    ];
+    const DIGITS: [KeyCode; 10] = [
+        KeyCode::Digit0, KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4,
+        KeyCode::Digit5, KeyCode::Digit6, KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+    ];
+    const FUNCTION_KEYS: [KeyCode; 12] = [
+        KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5, KeyCode::F6,
+        KeyCode::F7, KeyCode::F8, KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12,
+    ];
+
+    let bytes = name.as_bytes();
+    if bytes.len() == 1 && bytes[0].is_ascii_lowercase() {
+        return Some(LETTERS[(bytes[0] - b'a') as usize]);
+    }
+    if bytes.len() == 1 && bytes[0].is_ascii_digit() {
+        return Some(DIGITS[(bytes[0] - b'0') as usize]);
+    }
+    if let Some(number) = name.strip_prefix('f').and_then(|n| n.parse::<usize>().ok()) {
+        return FUNCTION_KEYS.get(number.wrapping_sub(1)).copied();
+    }
+
+    let key = match name {
+        "space" => KeyCode::Space,
+        "enter" | "return" => KeyCode::Enter,
+        "escape" | "esc" => KeyCode::Escape,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "up" => KeyCode::ArrowUp,
+        "down" => KeyCode::ArrowDown,
+        "left" => KeyCode::ArrowLeft,
+        "right" => KeyCode::ArrowRight,
+        "lshift" => KeyCode::ShiftLeft,
+        "rshift" => KeyCode::ShiftRight,
+        "lctrl" => KeyCode::ControlLeft,
+        "rctrl" => KeyCode::ControlRight,
+        "lalt" => KeyCode::AltLeft,
+        "ralt" => KeyCode::AltRight,
+        "minus" => KeyCode::Minus,
+        "equal" => KeyCode::Equal,
+        "comma" => KeyCode::Comma,
+        "period" => KeyCode::Period,
+        "slash" => KeyCode::Slash,
+        "backslash" => KeyCode::Backslash,
+        "semicolon" => KeyCode::Semicolon,
+        "quote" => KeyCode::Quote,
+        "backquote" => KeyCode::Backquote,
+        "bracketleft" => KeyCode::BracketLeft,
+        "bracketright" => KeyCode::BracketRight,
+        "printscreen" => KeyCode::PrintScreen,
+        "numpad0" => KeyCode::Numpad0,
+        "numpad1" => KeyCode::Numpad1,
+        "numpad2" => KeyCode::Numpad2,
+        "numpad3" => KeyCode::Numpad3,
+        "numpad4" => KeyCode::Numpad4,
+        "numpad5" => KeyCode::Numpad5,
+        "numpad6" => KeyCode::Numpad6,
+        "numpad7" => KeyCode::Numpad7,
+        "numpad8" => KeyCode::Numpad8,
+        "numpad9" => KeyCode::Numpad9,
+        "numpadadd" => KeyCode::NumpadAdd,
+        "numpadsubtract" => KeyCode::NumpadSubtract,
+        _ => return None,
+    };
+    return Some(key);
+}
@@ -0,0 +1,485 @@
+use std::fmt;
+
+
+
+/// parsed JSON document, objects keep their keys in file order
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+
+
+#[derive(Debug, Clone)]
+pub struct JsonError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+
+
+impl std::error::Error for JsonError {}
+
+
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Json, JsonError> {
+        let mut parser = Parser { text: text.as_bytes(), pos: 0, depth: 0 };
+        parser.skip_whitespace();
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos < parser.text.len() {
+            return Err(parser.error("trailing characters after document"));
+        }
+        return Ok(value);
+    }
+
+
+    /// member of an object, None for missing keys and non-objects
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => return members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => return None,
+        }
+    }
+
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(n) => return Some(*n),
+            _ => return None,
+        }
+    }
+
+
+    pub fn as_f32(&self) -> Option<f32> {
+        return self.as_f64().map(|n| n as f32);
+    }
+
+
+    /// non-negative integral numbers only
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(n) if *n >= 0.0 && n.fract() == 0.0 && *n <= usize::MAX as f64 => return Some(*n as usize),
+            _ => return None,
+        }
+    }
+
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => return Some(*b),
+            _ => return None,
+        }
+    }
+
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => return Some(s),
+            _ => return None,
+        }
+    }
+
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => return Some(items),
+            _ => return None,
+        }
+    }
+
+
+    pub fn as_object(&self) -> Option<&[(String, Json)]> {
+        match self {
+            Json::Object(members) => return Some(members),
+            _ => return None,
+        }
+    }
+
+
+    /// array of numbers, e.g. a vector or matrix
+    pub fn as_f32_array(&self) -> Option<Vec<f32>> {
+        return self.as_array()?.iter().map(|item| item.as_f32()).collect();
+    }
+
+
+    /// indented with two spaces, arrays of numbers stay on one line
+    pub fn to_string_pretty(&self) -> String {
+        let mut out = String::new();
+        self.write_pretty(&mut out, 0);
+        return out;
+    }
+
+
+    fn write_pretty(&self, out: &mut String, indent: usize) {
+        let pad = |out: &mut String, depth: usize| out.push_str(&"  ".repeat(depth));
+
+        match self {
+            Json::Array(items) if !items.is_empty() && !items.iter().all(|item| matches!(item, Json::Number(_))) => {
+                out.push_str("[\n");
+                for (i, item) in items.iter().enumerate() {
+                    pad(out, indent + 1);
+                    item.write_pretty(out, indent + 1);
+                    out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                pad(out, indent);
+                out.push(']');
+            }
+            Json::Object(members) if !members.is_empty() => {
+                out.push_str("{\n");
+                for (i, (key, value)) in members.iter().enumerate() {
+                    pad(out, indent + 1);
+                    write_string(out, key);
+                    out.push_str(": ");
+                    value.write_pretty(out, indent + 1);
+                    out.push_str(if i + 1 < members.len() { ",\n" } else { "\n" });
+                }
+                pad(out, indent);
+                out.push('}');
+            }
+            _ => out.push_str(&self.to_string()),
+        }
+    }
+}
+
+
+
+/// compact form, non-finite numbers become null since JSON can't represent them
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) if n.is_finite() => write!(f, "{}", n),
+            Json::Number(_) => write!(f, "null"),
+            Json::String(s) => {
+                let mut out = String::new();
+                write_string(&mut out, s);
+                write!(f, "{}", out)
+            }
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    let mut out = String::new();
+                    write_string(&mut out, key);
+                    write!(f, "{}: {}", out, value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+
+
+// nesting limit so hostile files can't overflow the stack
+const MAX_DEPTH: usize = 512;
+
+
+
+struct Parser<'a> {
+    text: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> JsonError {
+        let consumed = &self.text[..self.pos.min(self.text.len())];
+        let line = consumed.iter().filter(|&&c| c == b'\n').count() + 1;
+        let line_start = consumed.iter().rposition(|&c| c == b'\n').map_or(0, |i| i + 1);
+        let column = String::from_utf8_lossy(&consumed[line_start..]).chars().count() + 1;
+        return JsonError { line, column, message: message.to_string() };
+    }
+
+
+    fn peek(&self) -> Option<u8> {
+        return self.text.get(self.pos).copied();
+    }
+
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+
+    fn expect_literal(&mut self, literal: &str, value: Json) -> Result<Json, JsonError> {
+        if self.text[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            return Ok(value);
+        }
+        return Err(self.error("invalid literal"));
+    }
+
+
+    fn value(&mut self) -> Result<Json, JsonError> {
+        match self.peek() {
+            Some(b'{') => return self.object(),
+            Some(b'[') => return self.array(),
+            Some(b'"') => return Ok(Json::String(self.string()?)),
+            Some(b't') => return self.expect_literal("true", Json::Bool(true)),
+            Some(b'f') => return self.expect_literal("false", Json::Bool(false)),
+            Some(b'n') => return self.expect_literal("null", Json::Null),
+            Some(b'-' | b'0'..=b'9') => return self.number(),
+            Some(_) => return Err(self.error("unexpected character")),
+            None => return Err(self.error("unexpected end of input")),
+        }
+    }
+
+
+    fn enter(&mut self) -> Result<(), JsonError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.error("nesting too deep"));
+        }
+        self.pos += 1;
+        self.skip_whitespace();
+        return Ok(());
+    }
+
+
+    fn object(&mut self) -> Result<Json, JsonError> {
+        self.enter()?;
+        let mut members: Vec<(String, Json)> = Vec::new();
+
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            self.depth -= 1;
+            return Ok(Json::Object(members));
+        }
+
+        loop {
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected object key"));
+            }
+            let key = self.string()?;
+            self.skip_whitespace();
+            if self.peek() != Some(b':') {
+                return Err(self.error("expected ':' after object key"));
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            members.push((key, self.value()?));
+            self.skip_whitespace();
+
+            match self.peek() {
+                Some(b',') => {
+                    self.pos += 1;
+                    self.skip_whitespace();
+                }
+                Some(b'}') => {
+                    self.pos += 1;
+                    self.depth -= 1;
+                    return Ok(Json::Object(members));
+                }
+                _ => return Err(self.error("expected ',' or '}' in object")),
+            }
+        }
+    }
+
+
+    fn array(&mut self) -> Result<Json, JsonError> {
+        self.enter()?;
+        let mut items: Vec<Json> = Vec::new();
+
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            self.depth -= 1;
+            return Ok(Json::Array(items));
+        }
+
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+
+            match self.peek() {
+                Some(b',') => {
+                    self.pos += 1;
+                    self.skip_whitespace();
+                }
+                Some(b']') => {
+                    self.pos += 1;
+                    self.depth -= 1;
+                    return Ok(Json::Array(items));
+                }
+                _ => return Err(self.error("expected ',' or ']' in array")),
+            }
+        }
+    }
+
+
+    fn number(&mut self) -> Result<Json, JsonError> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+
+        // no leading zeros, at least one digit in each part
+        let digits = |parser: &mut Self| -> usize {
+            let first = parser.pos;
+            while let Some(b'0'..=b'9') = parser.peek() {
+                parser.pos += 1;
+            }
+            return parser.pos - first;
+        };
+
+        let integer_start = self.pos;
+        let integer_digits = digits(self);
+        if integer_digits == 0 || (integer_digits > 1 && self.text[integer_start] == b'0') {
+            self.pos = start;
+            return Err(self.error("invalid number"));
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if digits(self) == 0 {
+                return Err(self.error("expected digits after decimal point"));
+            }
+        }
+        if let Some(b'e' | b'E') = self.peek() {
+            self.pos += 1;
+            if let Some(b'+' | b'-') = self.peek() {
+                self.pos += 1;
+            }
+            if digits(self) == 0 {
+                return Err(self.error("expected digits in exponent"));
+            }
+        }
+
+        // the slice is plain ascii by construction
+        let literal = std::str::from_utf8(&self.text[start..self.pos]).unwrap();
+        return Ok(Json::Number(literal.parse().unwrap()));
+    }
+
+
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let Some(hex) = self.text.get(self.pos..self.pos + 4) else {
+            return Err(self.error("truncated unicode escape"));
+        };
+        let value = std::str::from_utf8(hex).ok().and_then(|hex| u32::from_str_radix(hex, 16).ok());
+        match value {
+            Some(value) => {
+                self.pos += 4;
+                return Ok(value);
+            }
+            None => return Err(self.error("invalid unicode escape")),
+        }
+    }
+
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.pos += 1;
+        let mut bytes: Vec<u8> = Vec::new();
+
+        loop {
+            let Some(c) = self.peek() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += 1;
+
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let Some(escape) = self.peek() else {
+                        return Err(self.error("unterminated string"));
+                    };
+                    self.pos += 1;
+                    let decoded = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => {
+                            self.pos -= 1;
+                            return Err(self.error("invalid escape sequence"));
+                        }
+                    };
+                    let mut buffer = [0; 4];
+                    bytes.extend_from_slice(decoded.encode_utf8(&mut buffer).as_bytes());
+                }
+                0..=0x1F => {
+                    self.pos -= 1;
+                    return Err(self.error("control character in string"));
+                }
+                _ => bytes.push(c),
+            }
+        }
+
+        // input came from a &str and escapes are encoded as utf-8, so this can't fail
+        return Ok(String::from_utf8(bytes).unwrap());
+    }
+
+
+    fn unicode_escape(&mut self) -> Result<char, JsonError> {
+        let high = self.hex4()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("invalid unicode escape"));
+        }
+
+        // utf-16 surrogate pair
+        if !self.text[self.pos..].starts_with(b"\\u") {
+            return Err(self.error("unpaired surrogate in unicode escape"));
+        }
+        self.pos += 2;
+        let low = self.hex4()?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return Err(self.error("unpaired surrogate in unicode escape"));
+        }
+        let code_point = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+        return char::from_u32(code_point).ok_or_else(|| self.error("invalid unicode escape"));
+    }
+}
@@ -0,0 +1,179 @@
+use crate::utils::random::{Random, ONE_MINUS_EPSILON};
+use crate::{vec2, Vec2};
+
+
+
+// halton bases, one per dimension
+const PRIMES: [u32; 16] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53];
+
+// void and cluster energy falloff in pixels, 1.5 is what Ulichney suggests
+const BLUE_NOISE_SIGMA: f32 = 1.5;
+// share of pixels set in the initial pattern
+const BLUE_NOISE_INITIAL_DENSITY: f32 = 0.1;
+
+
+
+/// `index` written in `base` and mirrored around the radix point, the van der Corput sequence
+pub fn radical_inverse(base: u32, mut index: u32) -> f32 {
+    let inverse_base = 1.0 / base as f64;
+    let (mut reversed, mut scale) = (0.0f64, inverse_base);
+    while index > 0 {
+        reversed += (index % base) as f64 * scale;
+        index /= base;
+        scale *= inverse_base;
+    }
+    return (reversed as f32).min(ONE_MINUS_EPSILON);
+}
+
+
+/// one coordinate of the `index`th Halton point, `dimension` picks the prime base
+pub fn halton(index: u32, dimension: usize) -> f32 {
+    return radical_inverse(PRIMES[dimension % PRIMES.len()], index);
+}
+
+
+/// bases 2 and 3, e.g. for sub-pixel jitter: `halton_2d(frame % 16) - 0.5`
+pub fn halton_2d(index: u32) -> Vec2 {
+    return vec2!(halton(index, 0), halton(index, 1));
+}
+
+
+/// the first two Sobol dimensions, a (0, 2)-sequence: every power of two run of points is stratified in every
+/// power of two grid. `scramble` xors each coordinate's bits, keeping that property while decorrelating uses,
+/// [0, 0] leaves the sequence as is
+pub fn sobol_2d(index: u32, scramble: [u32; 2]) -> Vec2 {
+    let x = index.reverse_bits();
+
+    // direction numbers of the polynomial x + 1, each one is the previous xor itself shifted
+    let (mut y, mut direction, mut bits) = (0u32, 1u32 << 31, index);
+    while bits != 0 {
+        if bits & 1 != 0 {
+            y ^= direction;
+        }
+        bits >>= 1;
+        direction ^= direction >> 1;
+    }
+
+    return vec2!(unit_float(x ^ scramble[0]), unit_float(y ^ scramble[1]));
+}
+
+
+
+/// a tileable blue noise threshold map from void and cluster: every value in [0, 1) appears once and
+/// neighbouring pixels get distant values, so it dithers and jitters without visible clumps
+#[derive(Debug, Clone)]
+pub struct BlueNoise {
+    pub size: u32,
+    // rank / size², row major
+    values: Vec<f32>,
+}
+
+
+
+impl BlueNoise {
+    /// takes O(size⁴), 64 is about a second in a debug build
+    pub fn new(size: u32, seed: u64) -> BlueNoise {
+        assert!(size >= 2, "blue noise needs at least a 2x2 tile");
+        let count = (size * size) as usize;
+        let kernel = gaussian_kernel(size);
+        let mut random = Random::new(seed);
+
+        // random initial points, then move the most crowded one to the emptiest spot until that changes nothing
+        let mut pattern = vec![false; count];
+        let mut energy = vec![0.0f32; count];
+        let initial = ((count as f32 * BLUE_NOISE_INITIAL_DENSITY) as usize).max(1);
+        let mut order: Vec<usize> = (0..count).collect();
+        random.shuffle(&mut order);
+        for &pixel in &order[..initial] {
+            toggle(&mut pattern, &mut energy, &kernel, size, pixel);
+        }
+        loop {
+            let cluster = tightest_cluster(&pattern, &energy);
+            toggle(&mut pattern, &mut energy, &kernel, size, cluster);
+            let void = largest_void(&pattern, &energy);
+            toggle(&mut pattern, &mut energy, &kernel, size, void);
+            if void == cluster {
+                break;
+            }
+        }
+
+        let mut ranks = vec![0usize; count];
+
+        // the initial points get the lowest ranks, most crowded first removed and ranked last
+        let (mut removing, mut removing_energy) = (pattern.clone(), energy.clone());
+        for rank in (0..initial).rev() {
+            let cluster = tightest_cluster(&removing, &removing_energy);
+            toggle(&mut removing, &mut removing_energy, &kernel, size, cluster);
+            ranks[cluster] = rank;
+        }
+
+        // then fill the emptiest spot, again and again. past half full, the largest void among the remaining
+        // pixels is the tightest cluster of them, so one loop covers both of Ulichney's later phases
+        for rank in initial..count {
+            let void = largest_void(&pattern, &energy);
+            toggle(&mut pattern, &mut energy, &kernel, size, void);
+            ranks[void] = rank;
+        }
+
+        let values = ranks.iter().map(|&rank| rank as f32 / count as f32).collect();
+        return BlueNoise { size, values };
+    }
+
+
+    /// threshold in [0, 1) at `(x, y)`, the tile repeats
+    pub fn sample(&self, x: u32, y: u32) -> f32 {
+        return self.values[((y % self.size) * self.size + x % self.size) as usize];
+    }
+
+
+    /// two values per pixel, the second read half a tile away where it doesn't correlate with the first
+    pub fn sample_2d(&self, x: u32, y: u32) -> Vec2 {
+        let half = self.size / 2;
+        return vec2!(self.sample(x, y), self.sample(x + half, y + half));
+    }
+}
+
+
+
+fn unit_float(bits: u32) -> f32 {
+    return (bits >> 8) as f32 * (1.0 / (1u32 << 24) as f32);
+}
+
+
+// energy contributed by a point to every offset on the torus
+fn gaussian_kernel(size: u32) -> Vec<f32> {
+    let wrapped = |d: u32| d.min(size - d) as f32;
+    let mut kernel = Vec::with_capacity((size * size) as usize);
+    for dy in 0..size {
+        for dx in 0..size {
+            let distance_sq = wrapped(dx).powi(2) + wrapped(dy).powi(2);
+            kernel.push((-distance_sq / (2.0 * BLUE_NOISE_SIGMA * BLUE_NOISE_SIGMA)).exp());
+        }
+    }
+    return kernel;
+}
+
+
+fn toggle(pattern: &mut [bool], energy: &mut [f32], kernel: &[f32], size: u32, pixel: usize) {
+    pattern[pixel] = !pattern[pixel];
+    let sign = if pattern[pixel] { 1.0 } else { -1.0 };
+    let (px, py) = (pixel as u32 % size, pixel as u32 / size);
+    for y in 0..size {
+        let row = ((y + size - py) % size * size) as usize;
+        for x in 0..size {
+            energy[(y * size + x) as usize] += sign * kernel[row + ((x + size - px) % size) as usize];
+        }
+    }
+}
+
+
+// the set pixel with the most set neighbours
+fn tightest_cluster(pattern: &[bool], energy: &[f32]) -> usize {
+    return (0..pattern.len()).filter(|&i| pattern[i]).max_by(|&a, &b| energy[a].total_cmp(&energy[b])).unwrap();
+}
+
+
+// the unset pixel furthest from any set one
+fn largest_void(pattern: &[bool], energy: &[f32]) -> usize {
+    return (0..pattern.len()).filter(|&i| !pattern[i]).min_by(|&a, &b| energy[a].total_cmp(&energy[b])).unwrap();
+}
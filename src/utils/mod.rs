@@ -1,3 +1,9 @@
+pub mod capture;
 pub mod file_parser;
+pub mod fixtures;
+pub mod frame_pacing;
+pub mod input;
+pub mod json;
+pub mod low_discrepancy;
 pub mod random;
 pub mod view;
\ No newline at end of file
@@ -1,43 +1,111 @@
-use crate::{Vec2, vec2};
+use std::f32::consts::PI;
 
+use crate::{vec2, vec3, Vec2, Vec3};
 
 
+
+const PCG_MULTIPLIER: u64 = 6364136223846793005;
+// stream used by `Random::new`, any value works, streams with different values never overlap
+const DEFAULT_STREAM: u64 = 0xCAFEF00DD15EA5E5;
+
+// largest f32 below 1, keeps samples built from arithmetic inside [0, 1)
+pub const ONE_MINUS_EPSILON: f32 = 1.0 - f32::EPSILON / 2.0;
+
+
+
+/// PCG32 (XSH-RR): 64 bits of state, 32-bit output. the same seed and stream always give the same sequence,
+/// on every platform. for parallel work give each thread or tile its own stream with `with_stream` or `split`
+#[derive(Debug, Clone)]
 pub struct Random {
-    state: u32,
+    state: u64,
+    // odd, selects one of 2^63 independent sequences
+    increment: u64,
 }
 
 
 
 impl Random {
-    pub fn new(seed: u32) -> Self {
-        Self { state: seed }
+    pub fn new(seed: u64) -> Self {
+        return Random::with_stream(seed, DEFAULT_STREAM);
+    }
+
+
+    /// seeds `stream` of 2^63, e.g. the thread or tile index, so results don't depend on scheduling
+    pub fn with_stream(seed: u64, stream: u64) -> Self {
+        let mut random = Random { state: 0, increment: stream << 1 | 1 };
+        random.next_u32();
+        random.state = random.state.wrapping_add(seed);
+        random.next_u32();
+        return random;
+    }
+
+
+    /// a new generator on a stream picked by this one, deterministic as long as splits happen in the same order
+    pub fn split(&mut self) -> Random {
+        let seed = self.next_u64();
+        let stream = self.next_u64();
+        return Random::with_stream(seed, stream);
     }
 
 
     pub fn next_u32(&mut self) -> u32 {
-        // Linear Congruential Generator constants  
-        self.state = self.state.wrapping_mul(1103515245).wrapping_add(12345);
-        return self.state;
+        let old = self.state;
+        self.state = old.wrapping_mul(PCG_MULTIPLIER).wrapping_add(self.increment);
+        let xorshifted = ((old >> 18 ^ old) >> 27) as u32;
+        return xorshifted.rotate_right((old >> 59) as u32);
     }
 
 
-    pub fn rand_range(&mut self, min: u32, max: u32) -> u32 {
-        return min + (self.next_u32() % (max - min + 1));
+    pub fn next_u64(&mut self) -> u64 {
+        return (self.next_u32() as u64) << 32 | self.next_u32() as u64;
     }
 
 
-    pub fn random_argb(&mut self) -> u32 {
-        let a = self.rand_range(0, 255) << 24;
-        let r = self.rand_range(0, 255) << 16;
-        let g = self.rand_range(0, 255) << 8;
-        let b = self.rand_range(0, 255);
-        return a | r | g | b;
+    /// uniform in [0, 1), every value a multiple of 2^-24
+    pub fn next_f32(&mut self) -> f32 {
+        return (self.next_u32() >> 8) as f32 * (1.0 / (1u32 << 24) as f32);
+    }
+
+
+    /// uniform in [0, 1), every value a multiple of 2^-53
+    pub fn next_f64(&mut self) -> f64 {
+        return (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64);
+    }
+
+
+    /// uniform in [0, bound) without modulo bias, Lemire's multiply and reject
+    pub fn below(&mut self, bound: u32) -> u32 {
+        assert!(bound > 0, "bound must be positive");
+        let mut product = self.next_u32() as u64 * bound as u64;
+        if (product as u32) < bound {
+            let threshold = bound.wrapping_neg() % bound;
+            while (product as u32) < threshold {
+                product = self.next_u32() as u64 * bound as u64;
+            }
+        }
+        return (product >> 32) as u32;
+    }
+
+
+    /// uniform in [min, max], both ends included
+    pub fn rand_range(&mut self, min: u32, max: u32) -> u32 {
+        assert!(min <= max, "empty range {}..={}", min, max);
+        let span = (max - min).wrapping_add(1);
+        if span == 0 {
+            return self.next_u32();
+        }
+        return min + self.below(span);
     }
 
 
+    /// uniform in [min, max)
     pub fn rand_f32_range(&mut self, min: f32, max: f32) -> f32 {
-        let unit = self.next_u32() as f32 / u32::MAX as f32; // [0.0, 1.0]
-        return min + unit * (max - min);
+        return min + self.next_f32() * (max - min);
+    }
+
+
+    pub fn random_argb(&mut self) -> u32 {
+        return self.next_u32();
     }
 
 
@@ -47,7 +115,122 @@ impl Random {
             self.rand_f32_range(y_min, y_max)
         );
     }
+
+
+    /// two uniform [0, 1) numbers, the input the `sample_*` functions take
+    pub fn next_vec2(&mut self) -> Vec2 {
+        return vec2!(self.next_f32(), self.next_f32());
+    }
+
+
+    /// normally distributed, Box-Muller
+    pub fn gaussian(&mut self, mean: f32, std_dev: f32) -> f32 {
+        // 1 - u is in (0, 1], ln stays finite
+        let radius = (-2.0 * (1.0 - self.next_f32()).ln()).sqrt();
+        let angle = 2.0 * PI * self.next_f32();
+        return mean + std_dev * radius * angle.cos();
+    }
+
+
+    /// uniform direction
+    pub fn on_unit_sphere(&mut self) -> Vec3 {
+        return sample_unit_sphere(self.next_vec2());
+    }
+
+
+    /// uniform point inside the unit disk
+    pub fn in_unit_disk(&mut self) -> Vec2 {
+        return sample_unit_disk(self.next_vec2());
+    }
+
+
+    /// uniform direction on the side of `normal`
+    pub fn on_hemisphere(&mut self, normal: Vec3) -> Vec3 {
+        return sample_hemisphere(self.next_vec2(), normal);
+    }
+
+
+    /// direction on the side of `normal` with density proportional to the cosine, for diffuse bounces
+    pub fn cosine_hemisphere(&mut self, normal: Vec3) -> Vec3 {
+        return sample_cosine_hemisphere(self.next_vec2(), normal);
+    }
+
+
+    /// Fisher-Yates, every order equally likely
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i as u32 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+        return items.get(self.below(items.len() as u32) as usize);
+    }
 }
 
 
 
+// the sample_* functions map a point of the unit square to a distribution, so they work with `Random`
+// as well as the low discrepancy sequences
+
+
+
+pub fn sample_unit_sphere(u: Vec2) -> Vec3 {
+    let z = 1.0 - 2.0 * u.x;
+    let radius = (1.0 - z * z).max(0.0).sqrt();
+    let angle = 2.0 * PI * u.y;
+    return vec3!(radius * angle.cos(), radius * angle.sin(), z);
+}
+
+
+/// Shirley's concentric mapping, keeps neighbouring samples neighbours
+pub fn sample_unit_disk(u: Vec2) -> Vec2 {
+    let (x, y) = (2.0 * u.x - 1.0, 2.0 * u.y - 1.0);
+    if x == 0.0 && y == 0.0 {
+        return vec2!(0, 0);
+    }
+    let (radius, angle) = if x.abs() > y.abs() {
+        (x, PI / 4.0 * (y / x))
+    } else {
+        (y, PI / 2.0 - PI / 4.0 * (x / y))
+    };
+    return vec2!(radius * angle.cos(), radius * angle.sin());
+}
+
+
+pub fn sample_hemisphere(u: Vec2, normal: Vec3) -> Vec3 {
+    let z = u.x;
+    let radius = (1.0 - z * z).max(0.0).sqrt();
+    let angle = 2.0 * PI * u.y;
+    return to_basis(vec3!(radius * angle.cos(), radius * angle.sin(), z), normal);
+}
+
+
+pub fn sample_cosine_hemisphere(u: Vec2, normal: Vec3) -> Vec3 {
+    let disk = sample_unit_disk(u);
+    let z = (1.0 - disk.dot(disk)).max(0.0).sqrt();
+    return to_basis(vec3!(disk.x, disk.y, z), normal);
+}
+
+
+/// two unit vectors perpendicular to `normal` and each other, Duff et al.'s branchless construction
+pub fn orthonormal_basis(normal: Vec3) -> (Vec3, Vec3) {
+    let sign = 1.0f32.copysign(normal.z);
+    let a = -1.0 / (sign + normal.z);
+    let b = normal.x * normal.y * a;
+    let tangent = vec3!(1.0 + sign * normal.x * normal.x * a, sign * b, -sign * normal.x);
+    let bitangent = vec3!(b, sign + normal.y * normal.y * a, -normal.y);
+    return (tangent, bitangent);
+}
+
+
+// a direction around +z to one around `normal`
+fn to_basis(local: Vec3, normal: Vec3) -> Vec3 {
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    return tangent * local.x + bitangent * local.y + normal * local.z;
+}
@@ -1,5 +1,10 @@
 use std::sync::Arc;
 use std::num::NonZeroU32;
+use std::time::Instant;
+use crate::config::DEFAULT_TARGET_FPS;
+use crate::utils::capture::Capture;
+use crate::utils::frame_pacing::{FramePacer, FramePacing};
+use crate::utils::input::InputMap;
 use crate::vec2;
 use winit::{
     dpi::{LogicalSize, PhysicalSize},
@@ -16,11 +21,21 @@ use crate::{graphics::render_target::RenderTarget, types::vec2::Vec2};
 
 pub struct View {
     pub input: WinitInputHelper,
+    // what the named actions and axes are bound to
+    pub input_map: InputMap,
     pub window: Arc<Window>,
     pub surface: Surface<Arc<Window>, Arc<Window>>,
     pub is_focused: bool,
     pub width: u32,
     pub height: u32,
+    pub pacer: FramePacer,
+    pub present_mode: PresentMode,
+    pub scale_filter: ScaleFilter,
+    // reallocate the render target to the window size times `render_scale` whenever it changes
+    pub follow_window: bool,
+    pub render_scale: f32,
+    // screenshots and recordings, driven by the screenshot and record actions in `capture_frame`
+    pub capture: Capture,
     event_loop: Option<EventLoop<()>>, 
 }
 
@@ -45,10 +60,17 @@ impl View {
 
         Self {
             input,
+            input_map: InputMap::new(),
             window,
             surface,
             width,
             height,
+            pacer: FramePacer::new(FramePacing::Fixed, DEFAULT_TARGET_FPS),
+            present_mode: PresentMode::Fit,
+            scale_filter: ScaleFilter::Nearest,
+            follow_window: false,
+            render_scale: 1.0,
+            capture: Capture::new(),
             event_loop: Some(event_loop),
             is_focused: false,
         }
@@ -66,17 +88,62 @@ impl View {
     }
 
 
+    pub fn key_pressed(&self, key: KeyCode) -> bool {
+        return self.input.key_pressed(key);
+    }
+
+
     pub fn mouse_pressed(&self, button: MouseButton) -> bool{
         return self.input.mouse_pressed(button);
     }
 
 
+    pub fn mouse_held(&self, button: MouseButton) -> bool {
+        return self.input.mouse_held(button);
+    }
+
+
     pub fn mouse_delta(&self) -> Vec2 {
         let (x, y) = self.input.mouse_diff();
         return vec2!(x, y);
     }
 
 
+    /// wheel movement this frame in lines, positive y scrolls up
+    pub fn scroll_delta(&self) -> Vec2 {
+        let (x, y) = self.input.scroll_diff();
+        return vec2!(x, y);
+    }
+
+
+    /// true on the frame one of the action's bindings is pressed, see `InputMap` for the names
+    pub fn action_pressed(&self, action: &str) -> bool {
+        return self.input_map.pressed(&self.input, action);
+    }
+
+
+    pub fn action_held(&self, action: &str) -> bool {
+        return self.input_map.held(&self.input, action);
+    }
+
+
+    pub fn action_released(&self, action: &str) -> bool {
+        return self.input_map.released(&self.input, action);
+    }
+
+
+    /// sum of the axis' bindings this frame, -1 to 1 per key pair, pixels or wheel lines for the mouse
+    pub fn axis(&self, axis: &str) -> f32 {
+        return self.input_map.axis(&self.input, axis);
+    }
+
+
+    /// with on-demand pacing, draws another frame even if no input arrives
+    pub fn request_frame(&mut self) {
+        self.pacer.request_frame();
+    }
+
+
     pub fn set_mouse_grab(&mut self, grab: bool) {
         let mode = if grab {
             self.window.set_cursor_visible(false);
@@ -102,6 +169,13 @@ impl View {
 
         event_loop
             .run(move |event, elwt| {
+                // anything but our own redraws may change the picture
+                match &event {
+                    Event::WindowEvent { event: WindowEvent::RedrawRequested, .. } => {}
+                    Event::WindowEvent { .. } | Event::DeviceEvent { .. } => self.pacer.mark_dirty(),
+                    _ => {}
+                }
+
                 if self.input.update(&event) {
                     if self.input.close_requested() || self.input.destroyed() {
                         elwt.exit();
@@ -110,9 +184,17 @@ impl View {
 
                     self.handle_resize();
 
-                    frame_fn(&mut self);
+                    if self.pacer.should_render() {
+                        self.pacer.wait();
+                        let frame_start = Instant::now();
+                        self.pacer.frame_started();
+                        frame_fn(&mut self);
+                        self.pacer.frame_finished(frame_start);
+                    } else {
+                        self.pacer.idle();
+                    }
 
-                    self.window.request_redraw();
+                    elwt.set_control_flow(self.pacer.control_flow());
                 }
 
                 if let Event::WindowEvent {
@@ -138,6 +220,56 @@ impl View {
     }
 
 
+    /// resizes `target` to match the window when `follow_window` is set, returns whether it was reallocated
+    pub fn sync_render_target(&self, target: &mut RenderTarget) -> bool {
+        if !self.follow_window || self.width == 0 || self.height == 0 {
+            return false;
+        }
+        let width = ((self.width as f32 * self.render_scale).round() as u32).max(1);
+        let height = ((self.height as f32 * self.render_scale).round() as u32).max(1);
+        if target.width == width && target.height == height {
+            return false;
+        }
+        target.resize(width, height);
+        return true;
+    }
+
+
+    /// handles the screenshot and record actions and adds `target` to a running recording.
+    /// call it once a frame after rendering, before anything that shouldn't end up in the capture like the HUD
+    pub fn capture_frame(&mut self, target: &RenderTarget) {
+        if self.action_pressed("screenshot") {
+            match self.capture.screenshot(target) {
+                Ok(path) => println!("Saved screenshot to {}", path.display()),
+                Err(err) => eprintln!("Screenshot failed: {}", err),
+            }
+        }
+
+        if self.action_pressed("record") {
+            if self.capture.is_recording() {
+                match self.capture.stop_recording() {
+                    Ok(Some((path, frames))) => println!("Recorded {} frames to {}", frames, path.display()),
+                    Ok(None) => {}
+                    Err(err) => eprintln!("Recording failed: {}", err),
+                }
+                return;
+            }
+            match self.capture.start_recording(target) {
+                Ok(path) => println!("Recording to {} at {} fps, the record action stops it", path.display(), self.capture.fps),
+                Err(err) => eprintln!("Recording failed: {}", err),
+            }
+        }
+
+        if let Err(err) = self.capture.record_frame(target) {
+            eprintln!("Recording stopped: {}", err);
+        }
+        if self.capture.is_recording() {
+            // every frame counts while recording, even with on-demand pacing
+            self.request_frame();
+        }
+    }
+
+
     pub fn draw(&mut self, target: &RenderTarget){
         let (win_width, win_height) = self.get_size();
 
@@ -145,62 +277,200 @@ impl View {
             return;
         }
 
+        let rect = present_rect(self.present_mode, target.width, target.height, win_width, win_height);
         let mut window_buffer = self.surface.buffer_mut().unwrap();
-        View::scale_buffer(
-            &mut window_buffer,
-            win_width,
-            win_height,
-            &target.color_buffer,
-            target.width, 
-            target.height,
-        );
+
+        // the image covers everything but the bars
+        if rect.width != win_width || rect.height != win_height {
+            window_buffer.fill(0);
+        }
+        match self.scale_filter {
+            ScaleFilter::Nearest => scale_nearest(&mut window_buffer, win_width, rect, &target.color_buffer, target.width, target.height),
+            ScaleFilter::Bilinear => scale_bilinear(&mut window_buffer, win_width, rect, &target.color_buffer, target.width, target.height),
+        }
 
         window_buffer.present().unwrap();
     }
-    
+}
 
-    pub fn scale_buffer(
-        dst_buf: &mut [u32],
-        dst_width: u32,
-        dst_height: u32,
-        src_buf: &[u32],
-        src_width: u32,
-        src_height: u32,
-    ) {
-        if src_buf.is_empty() || dst_buf.is_empty() {
-            return;
+
+
+/// how the render target is fitted into the window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PresentMode {
+    // fill the window, distorting the image if the aspect ratios differ
+    Stretch,
+    // largest size that keeps the aspect ratio, letterboxed or pillarboxed
+    Fit,
+    // largest whole multiple of the render size, like fit when the window is smaller than it
+    Integer,
+}
+
+
+
+impl PresentMode {
+    pub fn parse(name: &str) -> Option<PresentMode> {
+        match name {
+            "stretch" => return Some(PresentMode::Stretch),
+            "fit" | "letterbox" => return Some(PresentMode::Fit),
+            "integer" => return Some(PresentMode::Integer),
+            _ => return None,
         }
-    
-        if dst_width == src_width && dst_height == src_height {
-            let len = src_buf.len().min(dst_buf.len());
-            dst_buf[..len].copy_from_slice(&src_buf[..len]);
-            return;
+    }
+
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PresentMode::Stretch => return "stretch",
+            PresentMode::Fit => return "fit",
+            PresentMode::Integer => return "integer",
         }
-    
-        let x_scale_factor: u64 = (src_width as u64 * 65536) / dst_width as u64;
-        let y_scale_factor: u64 = (src_height as u64 * 65536) / dst_height as u64;
-    
-        for dst_y in 0..dst_height {
-            let src_y = ((dst_y as u64 * y_scale_factor) / 65536) as u32;
-    
-            if src_y >= src_height {
-                continue;
-            }
-    
-            let src_row_start_index = (src_y * src_width) as usize;
-            let dst_row_start_index = (dst_y * dst_width) as usize;
-    
-            for dst_x in 0..dst_width {
-                let src_x = ((dst_x as u64 * x_scale_factor) / 65536) as u32;
-    
-                let src_index = src_row_start_index + src_x as usize;
-                let dst_index = dst_row_start_index + dst_x as usize;  
+    }
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScaleFilter {
+    Nearest,
+    Bilinear,
+}
+
+
+
+impl ScaleFilter {
+    pub fn parse(name: &str) -> Option<ScaleFilter> {
+        match name {
+            "nearest" => return Some(ScaleFilter::Nearest),
+            "bilinear" => return Some(ScaleFilter::Bilinear),
+            _ => return None,
+        }
+    }
+
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ScaleFilter::Nearest => return "nearest",
+            ScaleFilter::Bilinear => return "bilinear",
+        }
+    }
+}
+
+
+
+/// area of the window the image is drawn to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PresentRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
 
-                dst_buf[dst_index] = src_buf[src_index];
+
+
+pub fn present_rect(mode: PresentMode, src_width: u32, src_height: u32, dst_width: u32, dst_height: u32) -> PresentRect {
+    let centered = |width: u32, height: u32| PresentRect { x: (dst_width - width) / 2, y: (dst_height - height) / 2, width, height };
+    if src_width == 0 || src_height == 0 {
+        return centered(0, 0);
+    }
+
+    let fit = || {
+        let scale = (dst_width as f64 / src_width as f64).min(dst_height as f64 / src_height as f64);
+        let width = ((src_width as f64 * scale).round() as u32).clamp(1, dst_width);
+        let height = ((src_height as f64 * scale).round() as u32).clamp(1, dst_height);
+        return centered(width, height);
+    };
+
+    match mode {
+        PresentMode::Stretch => return centered(dst_width, dst_height),
+        PresentMode::Fit => return fit(),
+        PresentMode::Integer => {
+            let scale = (dst_width / src_width).min(dst_height / src_height);
+            if scale == 0 {
+                return fit();
             }
+            return centered(src_width * scale, src_height * scale);
+        }
+    }
+}
+
+
+
+/// nearest neighbour scaling of `src` into `rect` of a buffer `dst_width` pixels wide
+pub fn scale_nearest(dst_buf: &mut [u32], dst_width: u32, rect: PresentRect, src_buf: &[u32], src_width: u32, src_height: u32) {
+    if src_buf.is_empty() || dst_buf.is_empty() || rect.width == 0 || rect.height == 0 {
+        return;
+    }
+
+    // source column for every destination column, 16.16 fixed point steps
+    let x_scale_factor: u64 = (src_width as u64 * 65536) / rect.width as u64;
+    let y_scale_factor: u64 = (src_height as u64 * 65536) / rect.height as u64;
+    let columns: Vec<usize> = (0..rect.width as u64).map(|x| ((x * x_scale_factor) >> 16) as usize).collect();
+
+    for row in 0..rect.height {
+        let src_y = (((row as u64 * y_scale_factor) >> 16) as u32).min(src_height - 1);
+        let src_row = &src_buf[(src_y * src_width) as usize..((src_y + 1) * src_width) as usize];
+        let dst_row_start = ((rect.y + row) * dst_width + rect.x) as usize;
+        let dst_row = &mut dst_buf[dst_row_start..dst_row_start + rect.width as usize];
+
+        if rect.width == src_width {
+            dst_row.copy_from_slice(src_row);
+            continue;
+        }
+        for (dst, &src_x) in dst_row.iter_mut().zip(&columns) {
+            *dst = src_row[src_x];
         }
     }
 }
 
 
 
+/// bilinear scaling of `src` into `rect`, sampling at pixel centers so edges don't shift
+pub fn scale_bilinear(dst_buf: &mut [u32], dst_width: u32, rect: PresentRect, src_buf: &[u32], src_width: u32, src_height: u32) {
+    if src_buf.is_empty() || dst_buf.is_empty() || rect.width == 0 || rect.height == 0 {
+        return;
+    }
+
+    let columns = bilinear_taps(src_width, rect.width);
+    let rows = bilinear_taps(src_height, rect.height);
+
+    for (row, &(y0, y1, weight_y)) in rows.iter().enumerate() {
+        let top = &src_buf[y0 * src_width as usize..(y0 + 1) * src_width as usize];
+        let bottom = &src_buf[y1 * src_width as usize..(y1 + 1) * src_width as usize];
+        let dst_row_start = ((rect.y + row as u32) * dst_width + rect.x) as usize;
+        let dst_row = &mut dst_buf[dst_row_start..dst_row_start + rect.width as usize];
+
+        for (dst, &(x0, x1, weight_x)) in dst_row.iter_mut().zip(&columns) {
+            let upper = lerp_argb(top[x0], top[x1], weight_x);
+            let lower = lerp_argb(bottom[x0], bottom[x1], weight_x);
+            *dst = lerp_argb(upper, lower, weight_y);
+        }
+    }
+}
+
+
+
+// (first texel, second texel, weight of the second out of 256) for every destination pixel
+fn bilinear_taps(src_len: u32, dst_len: u32) -> Vec<(usize, usize, u32)> {
+    let scale = src_len as f32 / dst_len as f32;
+    return (0..dst_len)
+        .map(|i| {
+            let position = ((i as f32 + 0.5) * scale - 0.5).max(0.0);
+            let first = (position as u32).min(src_len - 1);
+            let second = (first + 1).min(src_len - 1);
+            let weight = (((position - first as f32) * 256.0).round() as u32).min(256);
+            return (first as usize, second as usize, weight);
+        })
+        .collect();
+}
+
+
+
+// blends two packed argb colors, two channels per multiply
+fn lerp_argb(a: u32, b: u32, weight: u32) -> u32 {
+    let inverse = 256 - weight;
+    let red_blue = (((a & 0x00FF00FF) * inverse + (b & 0x00FF00FF) * weight) >> 8) & 0x00FF00FF;
+    let alpha_green = (((a >> 8) & 0x00FF00FF) * inverse + ((b >> 8) & 0x00FF00FF) * weight) & 0xFF00FF00;
+    return red_blue | alpha_green;
+}